@@ -0,0 +1,65 @@
+// Ações do menu rápido de um host (Espaço na lista), para o que não merece
+// um atalho global
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum QuickAction {
+    Connect,
+    ConnectWithOptions,
+    Record,
+    Sftp,
+    RunCommand,
+    Ping,
+    Edit,
+    Duplicate,
+    Delete,
+    CopyCommand,
+}
+
+pub const ALL: [QuickAction; 10] = [
+    QuickAction::Connect,
+    QuickAction::ConnectWithOptions,
+    QuickAction::Record,
+    QuickAction::Sftp,
+    QuickAction::RunCommand,
+    QuickAction::Ping,
+    QuickAction::Edit,
+    QuickAction::Duplicate,
+    QuickAction::Delete,
+    QuickAction::CopyCommand,
+];
+
+impl QuickAction {
+    pub fn label(self) -> &'static str {
+        match self {
+            QuickAction::Connect => "Connect",
+            QuickAction::ConnectWithOptions => "Connect with options",
+            QuickAction::Record => "Connect and record (asciinema)",
+            QuickAction::Sftp => "SFTP",
+            QuickAction::RunCommand => "Run command",
+            QuickAction::Ping => "Ping",
+            QuickAction::Edit => "Edit",
+            QuickAction::Duplicate => "Duplicate",
+            QuickAction::Delete => "Delete",
+            QuickAction::CopyCommand => "Copy ssh command",
+        }
+    }
+
+    // Tecla que dispara a ação com o menu aberto
+    pub fn key(self) -> char {
+        match self {
+            QuickAction::Connect => 'c',
+            QuickAction::ConnectWithOptions => 'o',
+            QuickAction::Record => 'a',
+            QuickAction::Sftp => 's',
+            QuickAction::RunCommand => 'r',
+            QuickAction::Ping => 'p',
+            QuickAction::Edit => 'e',
+            QuickAction::Duplicate => 'u',
+            QuickAction::Delete => 'd',
+            QuickAction::CopyCommand => 'y',
+        }
+    }
+
+    pub fn from_key(key: char) -> Option<Self> {
+        ALL.into_iter().find(|a| a.key() == key)
+    }
+}
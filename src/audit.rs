@@ -0,0 +1,214 @@
+use std::collections::BTreeMap;
+use serde::Serialize;
+
+use crate::effective;
+use crate::ssh_config::SshHost;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Low,
+    Medium,
+    High,
+}
+
+impl Severity {
+    pub fn label(self) -> &'static str {
+        match self {
+            Severity::Low => "low",
+            Severity::Medium => "medium",
+            Severity::High => "high",
+        }
+    }
+}
+
+// Opção efetiva arriscada de um host; `source` é o bloco de onde ela vem
+// e `block`, o seu padrão (`Host *` etc.)
+#[derive(Debug, Clone, Serialize)]
+pub struct Finding {
+    pub severity: Severity,
+    pub key: String,
+    pub value: String,
+    #[serde(skip)]
+    pub source: usize,
+    pub block: String,
+    pub title: String,
+    pub explanation: &'static str,
+    pub recommendation: &'static str,
+}
+
+// Algoritmos obsoletos por diretiva (a lista pode vir com prefixo +, - ou ^)
+fn legacy_algorithms(key: &str, value: &str) -> Vec<String> {
+    if value.starts_with('-') {
+        return Vec::new();
+    }
+    let is_legacy = |name: &str| match key {
+        "ciphers" => name.ends_with("-cbc") || name.starts_with("arcfour") || name.starts_with("rijndael-cbc"),
+        "kexalgorithms" => name.ends_with("-sha1"),
+        "macs" => name.contains("md5") || name.starts_with("hmac-sha1") || name.starts_with("umac-64") || name.contains("ripemd"),
+        "hostkeyalgorithms" | "pubkeyacceptedalgorithms" | "pubkeyacceptedkeytypes" => {
+            name.starts_with("ssh-rsa") || name.starts_with("ssh-dss")
+        }
+        _ => false,
+    };
+    value.trim_start_matches(['+', '^'])
+        .split(',')
+        .map(str::trim)
+        .filter(|name| is_legacy(&name.to_lowercase()))
+        .map(String::from)
+        .collect()
+}
+
+// `trusted` são padrões (como os do `Host`) dos hosts onde encaminhar o agente é aceito
+pub fn audit_host(hosts: &[SshHost], index: usize, trusted: &[String]) -> Vec<Finding> {
+    let Some(host) = hosts.get(index) else {
+        return Vec::new();
+    };
+    let alias = effective::host_alias(host);
+    let (options, _) = effective::resolve(hosts, index);
+    let mut findings = Vec::new();
+    for option in options {
+        let value = option.value.to_lowercase();
+        let mut finding = |severity: Severity, title: String, explanation: &'static str, recommendation: &'static str| {
+            findings.push(Finding {
+                severity,
+                key: option.key.clone(),
+                value: option.value.clone(),
+                source: option.source,
+                block: hosts[option.source].name.clone(),
+                title,
+                explanation,
+                recommendation,
+            });
+        };
+        match option.key.as_str() {
+            "stricthostkeychecking" if value == "no" || value == "off" => finding(
+                Severity::High,
+                "Chave do servidor não é verificada".to_string(),
+                "O ssh aceita qualquer chave de host, inclusive uma diferente da conhecida, sem avisar; um ataque man-in-the-middle passa despercebido.",
+                "Use `StrictHostKeyChecking accept-new` (aceita só a primeira chave) ou `yes`.",
+            ),
+            "userknownhostsfile" if value == "/dev/null" => finding(
+                Severity::High,
+                "known_hosts descartado".to_string(),
+                "As chaves de host nunca são guardadas, então toda conexão é tratada como a primeira e uma troca de chave não é detectada.",
+                "Remova a diretiva ou aponte para um arquivo known_hosts real.",
+            ),
+            "forwardagent" if value != "no" && !is_trusted(alias, trusted) => finding(
+                Severity::High,
+                "Agente encaminhado para host não confiável".to_string(),
+                "Quem tem root no servidor pode usar o seu agente ssh para se autenticar em outros hosts como você enquanto a sessão estiver aberta.",
+                "Desligue o ForwardAgent e use ProxyJump; se o host for confiável, inclua-o em `trusted_hosts`.",
+            ),
+            // Mesmo em host confiável o agente fica exposto a quem tiver root nele
+            "forwardagent" if value != "no" => finding(
+                Severity::Low,
+                "Agente encaminhado (host confiável)".to_string(),
+                "O host está em `trusted_hosts`, mas quem tiver root nele ainda pode usar o seu agente enquanto a sessão estiver aberta.",
+                "Prefira ProxyJump; mantenha o ForwardAgent só onde ele é indispensável.",
+            ),
+            "passwordauthentication" if value == "yes" => finding(
+                Severity::Medium,
+                "Autenticação por senha habilitada".to_string(),
+                "Senhas podem ser adivinhadas ou capturadas por um servidor falso; chaves não expõem o segredo ao servidor.",
+                "Use `PasswordAuthentication no` e autentique com chave.",
+            ),
+            "forwardx11trusted" if value == "yes" => finding(
+                Severity::Medium,
+                "X11 encaminhado sem restrições".to_string(),
+                "Clientes X11 remotos têm acesso total ao display local: podem capturar teclas e a tela.",
+                "Use `ForwardX11Trusted no`, ou abra o X11 só quando precisar com `ssh -X`.",
+            ),
+            "forwardx11" if value == "yes" => finding(
+                Severity::Low,
+                "X11 encaminhado por padrão".to_string(),
+                "Toda sessão expõe o display local ao servidor, mesmo quando ele não é usado.",
+                "Remova a diretiva e use `ssh -X` quando precisar.",
+            ),
+            "ciphers" | "kexalgorithms" | "macs" | "hostkeyalgorithms" | "pubkeyacceptedalgorithms" | "pubkeyacceptedkeytypes" => {
+                let legacy = legacy_algorithms(&option.key, &option.value);
+                if !legacy.is_empty() {
+                    finding(
+                        Severity::Medium,
+                        format!("Algoritmos obsoletos: {}", legacy.join(", ")),
+                        "Cifras CBC/RC4, trocas de chave e MACs com SHA-1/MD5 e chaves ssh-rsa/ssh-dss têm ataques conhecidos e foram desativados por padrão no OpenSSH.",
+                        "Remova os algoritmos obsoletos e atualize o servidor para aceitar os padrões atuais.",
+                    );
+                }
+            }
+            _ => {}
+        }
+    }
+    findings.sort_by_key(|f| std::cmp::Reverse(f.severity));
+    findings
+}
+
+fn is_trusted(alias: &str, trusted: &[String]) -> bool {
+    trusted.iter().any(|t| effective::pattern_matches(t, alias))
+}
+
+// Hosts concretos com ForwardAgent efetivo ligado, e se são confiáveis
+pub fn agent_forwarding(hosts: &[SshHost], trusted: &[String]) -> BTreeMap<usize, bool> {
+    hosts.iter()
+        .enumerate()
+        .filter(|(_, h)| !h.is_separator && !h.is_pattern)
+        .filter(|&(i, _)| {
+            effective::resolve(hosts, i).0.iter()
+                .any(|o| o.key == "forwardagent" && !o.value.eq_ignore_ascii_case("no"))
+        })
+        .map(|(i, h)| (i, is_trusted(effective::host_alias(h), trusted)))
+        .collect()
+}
+
+// Hosts concretos com algum achado, os mais graves primeiro
+pub fn audit_all(hosts: &[SshHost], trusted: &[String]) -> Vec<(usize, Vec<Finding>)> {
+    let mut results: Vec<(usize, Vec<Finding>)> = hosts.iter()
+        .enumerate()
+        .filter(|(_, h)| !h.is_separator && !h.is_pattern)
+        .map(|(i, _)| (i, audit_host(hosts, i, trusted)))
+        .filter(|(_, findings)| !findings.is_empty())
+        .collect();
+    results.sort_by_key(|(_, findings)| std::cmp::Reverse(worst(findings)));
+    results
+}
+
+pub fn worst(findings: &[Finding]) -> Option<Severity> {
+    findings.iter().map(|f| f.severity).max()
+}
+
+// StrictHostKeyChecking e UserKnownHostsFile efetivos, com o bloco de onde
+// cada um vem; `None` é o padrão do ssh (`ask` e `~/.ssh/known_hosts`)
+#[derive(Debug, Clone, Default)]
+pub struct HostKeyPolicy {
+    pub checking: Option<(String, usize)>,
+    pub known_hosts: Option<(String, usize)>,
+}
+
+impl HostKeyPolicy {
+    pub fn from_options(options: &[effective::EffectiveOption]) -> Self {
+        let find = |key: &str| options.iter().find(|o| o.key == key).map(|o| (o.value.clone(), o.source));
+        Self {
+            checking: find("stricthostkeychecking"),
+            known_hosts: find("userknownhostsfile"),
+        }
+    }
+
+    pub fn is_disabled(&self) -> bool {
+        self.checking.as_ref().is_some_and(|(v, _)| v.eq_ignore_ascii_case("no") || v.eq_ignore_ascii_case("off"))
+    }
+
+    pub fn discards_known_hosts(&self) -> bool {
+        self.known_hosts.as_ref().is_some_and(|(v, _)| v.split_whitespace().all(|f| f == "/dev/null"))
+    }
+
+    // High: chave não verificada; Low: pergunta na primeira conexão; `None`: segura
+    pub fn risk(&self) -> Option<Severity> {
+        if self.is_disabled() || self.discards_known_hosts() {
+            return Some(Severity::High);
+        }
+        match self.checking.as_ref().map(|(v, _)| v.to_lowercase()) {
+            Some(v) if v == "yes" || v == "accept-new" => None,
+            _ => Some(Severity::Low),
+        }
+    }
+}
@@ -0,0 +1,99 @@
+use std::fs;
+use std::io::{ErrorKind, Write};
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+use crate::config::AppConfig;
+
+// Cópias guardadas por arquivo (`config_backups`); definido uma vez ao abrir
+static KEEP: OnceLock<usize> = OnceLock::new();
+
+pub fn configure(keep: usize) {
+    let _ = KEEP.set(keep);
+}
+
+// Cópias ficam em `<state dir>/backups`, com o caminho relativo ao home:
+// `~/.ssh/config` vira `backups/.ssh/config.bak.1` (a mais recente). Fora do
+// workdir, para não caírem num `Include config.d/*` nem no status do git
+pub fn backup_path(path: &Path, n: usize) -> PathBuf {
+    stored_path(path, &format!("bak.{}", n))
+}
+
+fn stored_path(path: &Path, suffix: &str) -> PathBuf {
+    let relative = home::home_dir()
+        .and_then(|home| path.strip_prefix(home).ok().map(Path::to_path_buf))
+        .unwrap_or_else(|| path.components().skip(1).collect());
+    let mut name = relative.into_os_string();
+    name.push(format!(".{}", suffix));
+    AppConfig::get_state_dir().join("backups").join(name)
+}
+
+// Cópia avulsa ao lado das rotativas (`config.pre-db`), que a rotação não
+// apaga; uma cópia anterior com o mesmo rótulo é mantida
+pub fn snapshot(path: &Path, label: &str) -> Result<PathBuf, Box<dyn std::error::Error>> {
+    let copy = stored_path(path, label);
+    if copy.exists() {
+        return Ok(copy);
+    }
+    if let Some(parent) = copy.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::copy(path, &copy).map_err(|e| format!("backup de {} falhou: {}", path.display(), e))?;
+    Ok(copy)
+}
+
+// Desloca `.bak.1`…`.bak.N-1` uma posição e copia o conteúdo atual de
+// `target` (o destino do link, se `path` for um) para `.bak.1`
+fn rotate(path: &Path, target: &Path, keep: usize) -> std::io::Result<()> {
+    let first = backup_path(path, 1);
+    if let Some(parent) = first.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    for n in (1..keep).rev() {
+        match fs::rename(backup_path(path, n), backup_path(path, n + 1)) {
+            Err(e) if e.kind() != ErrorKind::NotFound => return Err(e),
+            _ => {}
+        }
+    }
+    fs::copy(target, first)?;
+    Ok(())
+}
+
+// Grava como `replace`, guardando antes o conteúdo anterior
+pub fn write(path: &Path, content: impl AsRef<[u8]>) -> Result<(), Box<dyn std::error::Error>> {
+    let target = fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+    let keep = KEEP.get().copied().unwrap_or(0);
+    if target.exists() && keep > 0 {
+        rotate(path, &target, keep).map_err(|e| format!("backup de {} falhou: {}", path.display(), e))?;
+    }
+    replace(path, content)
+}
+
+// Grava num temporário ao lado e renomeia por cima, para o arquivo nunca
+// ficar pela metade (queda, disco cheio). Um link simbólico (config de
+// dotfiles) continua link: a gravação vai para o destino
+pub fn replace(path: &Path, content: impl AsRef<[u8]>) -> Result<(), Box<dyn std::error::Error>> {
+    let target = fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+    let previous = fs::metadata(&target).ok();
+    let file_name = target.file_name().ok_or_else(|| format!("{} não é um arquivo", path.display()))?;
+    // Oculto, para não casar com um `Include` com curinga enquanto existe
+    let mut temp_name = std::ffi::OsString::from(".");
+    temp_name.push(file_name);
+    temp_name.push(".lazysshrs-tmp");
+    let temp = target.with_file_name(temp_name);
+    let result = (|| -> std::io::Result<()> {
+        let mut file = fs::File::create(&temp)?;
+        file.write_all(content.as_ref())?;
+        // O ssh recusa um config com permissões abertas demais; mantém as do original
+        if let Some(metadata) = &previous {
+            file.set_permissions(metadata.permissions())?;
+        }
+        file.sync_all()?;
+        fs::rename(&temp, &target)
+    })();
+    if result.is_err() {
+        let _ = fs::remove_file(&temp);
+    }
+    result?;
+    Ok(())
+}
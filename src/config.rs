@@ -1,10 +1,130 @@
+use std::collections::BTreeMap;
 use std::fs;
 use std::path::PathBuf;
 use serde::{Deserialize, Serialize};
 
+use crate::connectivity::ReachMethod;
+use crate::search::MatcherKind;
+use crate::sync;
+use crate::table::{self, TableColumn};
+
 #[derive(Debug, Serialize, Deserialize)]
+#[serde(default)]
 pub struct AppConfig {
     pub workdir: String,
+    // Conteúdo inicial de novas pastas; `{folder}` é substituído pelo nome
+    pub folder_template: String,
+    pub templates: Vec<HostTemplate>,
+    // Buscas salvas (grupos inteligentes), abertas com `F`
+    pub saved_searches: Vec<SavedSearch>,
+    // Seções recolhidas na lista, pelo arquivo do include (`Defaults` para a dos padrões)
+    pub collapsed_sections: Vec<String>,
+    // Passar pela tela de confirmação (Confirm/ConfirmEdit) antes de gravar o formulário
+    pub confirm_forms: bool,
+    // Ações destrutivas que ainda pedem confirmação: delete, overwrite, include-removal
+    pub confirm_destructive: Vec<Destructive>,
+    // Mostrar no formulário os valores herdados de blocos como `Host *`
+    pub inherit_wildcard_defaults: bool,
+    // Verificação padrão do `p`: tcp, icmp ou both (pode ser trocada por host)
+    pub reachability: ReachMethod,
+    // Segundos até um resultado de verificação ser considerado desatualizado
+    pub check_ttl_secs: u64,
+    // Pastas cujas seções vêm primeiro na lista, nesta ordem; as demais seguem
+    // a ordem dos Include
+    pub section_order: Vec<String>,
+    // Índices 1–9 nos primeiros hosts da tela; o dígito conecta direto
+    pub quick_connect: bool,
+    // Coluna com o último status (●) e a faixa de latência na lista de hosts
+    pub status_column: bool,
+    // Painel de detalhes: trechos na ordem em que aparecem (`options` = demais
+    // diretivas) e diretivas que nunca são mostradas
+    pub detail_fields: Vec<String>,
+    pub detail_hidden_options: Vec<String>,
+    // Perfil de acessibilidade: ASCII, alto contraste e menos redesenhos
+    pub accessible: bool,
+    // Abrir com endereços e caminhos de chaves ocultos (`H` alterna)
+    pub privacy_mode: bool,
+    // Padrões de host (como os do `Host`) onde a auditoria aceita ForwardAgent
+    pub trusted_hosts: Vec<String>,
+    // Gravar toda sessão ssh com `asciinema rec` (a ação `a` do menu grava uma só)
+    pub record_sessions: bool,
+    // No kitty ou no WezTerm, abrir as sessões em abas com o nome do host, sem sair da interface
+    pub terminal_tabs: bool,
+    // Cópias anteriores guardadas de cada arquivo do ssh_config alterado; 0 desliga
+    pub config_backups: usize,
+    // Permitir conectar e testar blocos só de padrões (`Host *`), que ficam na seção Defaults
+    pub pattern_actions: bool,
+    // Abrir na visão em tabela (`L` alterna) e suas colunas, na ordem, com largura
+    pub table_view: bool,
+    pub table_columns: Vec<TableColumn>,
+    // Verificar em segundo plano, ao abrir, os hosts sem resultado recente
+    pub sweep_on_startup: bool,
+    // Máximo de verificações simultâneas na varredura
+    pub sweep_concurrency: usize,
+    // Espera após a última tecla antes de recalcular a busca
+    pub search_debounce_ms: u64,
+    // Algoritmo da busca: skim, substring ou nucleo
+    pub matcher: MatcherKind,
+    // Peso (%) do uso frequente/recente na ordem da busca; 0 desliga
+    pub search_frecency_weight: u32,
+    // O que fazer com os túneis da sessão anterior: ask, auto ou never
+    pub restore_tunnels: RestoreTunnels,
+    // Banco SQLite com os hosts das pastas (vazio = editar os arquivos direto)
+    pub database: String,
+    // Cifrar metadados e estado com uma senha pedida ao abrir
+    pub encrypt_state: bool,
+    // Commitar as alterações quando o workdir é um repositório git
+    pub git_autocommit: bool,
+    // Destino do `R` (sincronizar com outro host) e arquivos que nunca são enviados
+    pub sync_remote_dir: String,
+    pub sync_excludes: Vec<String>,
+    // Porta do `lazysshrs serve`, sempre em 127.0.0.1
+    pub serve_port: u16,
+    // Editor do `E`: code (Remote-SSH), nvim (netrw via scp), nvim-oil ou um
+    // comando com {host} e {path}
+    pub remote_editor: String,
+    // Clientes de acesso gráfico (`g`), com {host}, {port} e {uri}
+    pub rdp_client: String,
+    pub vnc_client: String,
+}
+
+// Apagar um host, gravar um host com o nome de outro já existente e tirar um
+// Include (mesclar arquivos)
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Destructive {
+    Delete,
+    Overwrite,
+    IncludeRemoval,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RestoreTunnels {
+    #[default]
+    Ask,
+    Auto,
+    Never,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HostTemplate {
+    pub name: String,
+    pub hostname: Option<String>,
+    pub user: Option<String>,
+    pub port: Option<u16>,
+    pub identity_file: Option<String>,
+    pub local_forwards: Vec<String>,
+    pub options: BTreeMap<String, String>,
+}
+
+// Consulta com nome, ex.: "prod db boxes" = `tag:prod db`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SavedSearch {
+    pub name: String,
+    pub query: String,
 }
 
 impl Default for AppConfig {
@@ -12,6 +132,47 @@ impl Default for AppConfig {
         let home_dir = home::home_dir().unwrap_or_else(|| PathBuf::from("/"));
         Self {
             workdir: home_dir.join(".ssh").to_string_lossy().to_string(),
+            folder_template: String::new(),
+            templates: Vec::new(),
+            saved_searches: Vec::new(),
+            collapsed_sections: Vec::new(),
+            confirm_forms: true,
+            confirm_destructive: vec![Destructive::Delete, Destructive::Overwrite, Destructive::IncludeRemoval],
+            inherit_wildcard_defaults: true,
+            reachability: ReachMethod::Tcp,
+            check_ttl_secs: 300,
+            section_order: Vec::new(),
+            quick_connect: true,
+            status_column: true,
+            detail_fields: ["host", "description", "hostname", "user", "port", "identityfile", "proxyjump", "localforward", "options",
+                "hostkeys", "status", "check", "tags", "mac", "connections", "notes", "source"]
+                .map(String::from)
+                .to_vec(),
+            detail_hidden_options: Vec::new(),
+            accessible: false,
+            privacy_mode: false,
+            trusted_hosts: Vec::new(),
+            record_sessions: false,
+            terminal_tabs: true,
+            config_backups: 5,
+            pattern_actions: false,
+            table_view: false,
+            table_columns: table::default_columns(),
+            sweep_on_startup: false,
+            sweep_concurrency: 4,
+            search_debounce_ms: 75,
+            matcher: MatcherKind::Skim,
+            search_frecency_weight: 100,
+            restore_tunnels: RestoreTunnels::Ask,
+            database: String::new(),
+            encrypt_state: false,
+            git_autocommit: true,
+            sync_remote_dir: "~/.ssh".to_string(),
+            sync_excludes: sync::default_excludes(),
+            serve_port: 7722,
+            remote_editor: "code".to_string(),
+            rdp_client: "xfreerdp /v:{host}:{port}".to_string(),
+            vnc_client: "vncviewer {host}::{port}".to_string(),
         }
     }
 }
@@ -48,6 +209,12 @@ impl AppConfig {
         Ok(home_dir.join(".config").join("lazysshrs"))
     }
 
+    // Dados gerados pela aplicação (metadados, estado)
+    pub fn get_state_dir() -> PathBuf {
+        let home_dir = home::home_dir().unwrap_or_else(|| PathBuf::from("/"));
+        home_dir.join(".local").join("share").join("lazysshrs")
+    }
+
     pub fn get_main_config_path(&self) -> PathBuf {
         PathBuf::from(&self.workdir).join("config")
     }
@@ -0,0 +1,261 @@
+use std::path::PathBuf;
+
+// Bloco `Host` como está no arquivo, com comentários e indentação
+#[derive(Debug, Clone, PartialEq)]
+pub struct HostBlock {
+    pub name: String,
+    pub lines: Vec<String>,
+}
+
+impl HostBlock {
+    // Diretivas normalizadas, para comparar blocos ignorando formatação e comentários
+    pub fn directives(&self) -> Vec<(String, String)> {
+        self.lines.iter().skip(1).filter_map(|l| directive(l)).collect()
+    }
+
+    // Todos os valores de uma diretiva, na ordem (LocalForward, IdentityFile...)
+    fn values(&self, key: &str) -> Vec<String> {
+        self.directives().into_iter().filter(|(k, _)| k == key).map(|(_, v)| v).collect()
+    }
+
+    fn comments(&self) -> Vec<&str> {
+        self.lines.iter().map(|l| l.trim()).filter(|l| l.starts_with('#')).collect()
+    }
+
+    pub fn text(&self) -> String {
+        let mut text = self.lines.join("\n");
+        text.push('\n');
+        text
+    }
+}
+
+fn directive(line: &str) -> Option<(String, String)> {
+    let line = line.trim();
+    if line.is_empty() || line.starts_with('#') {
+        return None;
+    }
+    let (key, value) = line.split_once(|c: char| c.is_whitespace() || c == '=')?;
+    Some((key.to_lowercase(), value.trim_start_matches([' ', '\t', '=']).trim().to_string()))
+}
+
+fn same(a: &Option<HostBlock>, b: &Option<HostBlock>) -> bool {
+    match (a, b) {
+        (Some(a), Some(b)) => a.directives() == b.directives(),
+        (None, None) => true,
+        _ => false,
+    }
+}
+
+// Texto antes do primeiro Host (Includes, comentários) e os blocos, na ordem
+#[derive(Debug, Clone, Default)]
+pub struct ParsedFile {
+    pub preamble: Vec<String>,
+    pub blocks: Vec<HostBlock>,
+}
+
+impl ParsedFile {
+    pub fn parse(content: &str) -> Self {
+        let mut parsed = ParsedFile::default();
+        for line in content.lines() {
+            let trimmed = line.trim_start();
+            let is_host = trimmed.get(..5).is_some_and(|k| k.eq_ignore_ascii_case("host "));
+            if is_host {
+                parsed.blocks.push(HostBlock { name: trimmed[5..].trim().to_string(), lines: vec![line.to_string()] });
+            } else if let Some(block) = parsed.blocks.last_mut() {
+                block.lines.push(line.to_string());
+            } else {
+                parsed.preamble.push(line.to_string());
+            }
+        }
+        // Linhas em branco no fim de um bloco ficam como separador ao regravar
+        for block in &mut parsed.blocks {
+            while block.lines.len() > 1 && block.lines.last().is_some_and(|l| l.trim().is_empty()) {
+                block.lines.pop();
+            }
+        }
+        parsed
+    }
+
+    fn get(&self, name: &str) -> Option<HostBlock> {
+        self.blocks.iter().find(|b| b.name == name).cloned()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Resolution {
+    Local,
+    Remote,
+    Merge,
+}
+
+impl Resolution {
+    pub fn label(&self) -> &'static str {
+        match self {
+            Resolution::Local => "keep local",
+            Resolution::Remote => "take remote",
+            Resolution::Merge => "merge fields",
+        }
+    }
+
+    pub fn next(&self) -> Self {
+        match self {
+            Resolution::Local => Resolution::Remote,
+            Resolution::Remote => Resolution::Merge,
+            Resolution::Merge => Resolution::Local,
+        }
+    }
+}
+
+// Host alterado dos dois lados desde a base comum
+#[derive(Debug, Clone)]
+pub struct HostConflict {
+    pub host: String,
+    pub local: Option<HostBlock>,
+    pub remote: Option<HostBlock>,
+    pub base: Option<HostBlock>,
+    pub resolution: Resolution,
+}
+
+impl HostConflict {
+    // Campo a campo: o lado que mudou em relação à base vence; se os dois mudaram, o local.
+    // Cada diretiva conta com todos os seus valores, então repetidas (LocalForward,
+    // IdentityFile) vão juntas. Os comentários seguem o lado que os alterou, no lugar
+    // em que estão, e as diretivas escolhidas são trocadas nesse bloco
+    fn merged(&self) -> Option<HostBlock> {
+        let (Some(local), Some(remote)) = (&self.local, &self.remote) else {
+            return self.local.clone().or_else(|| self.remote.clone());
+        };
+        let base = self.base.clone().unwrap_or_else(|| HostBlock { name: local.name.clone(), lines: Vec::new() });
+        let skeleton = if self.base.is_some() && local.comments() == base.comments() { remote } else { local };
+
+        let mut keys: Vec<String> = Vec::new();
+        for (key, _) in local.directives().into_iter().chain(remote.directives()) {
+            if !keys.contains(&key) {
+                keys.push(key);
+            }
+        }
+        let mut lines = skeleton.lines.clone();
+        for key in keys {
+            let (l, r, b) = (local.values(&key), remote.values(&key), base.values(&key));
+            let chosen = if l == b { r } else { l };
+            if chosen == skeleton.values(&key) {
+                continue;
+            }
+            let positions: Vec<usize> = (1..lines.len()).filter(|&i| directive(&lines[i]).is_some_and(|(k, _)| k == key)).collect();
+            // Recupera a grafia original da diretiva; a indentação é a do bloco
+            let source = local.lines.iter().chain(&remote.lines).skip(1)
+                .find(|line| directive(line).is_some_and(|(k, _)| k == key));
+            let original = source.and_then(|line| line.split(|c: char| c.is_whitespace() || c == '=').find(|k| !k.is_empty())).unwrap_or(&key);
+            let indent = positions.first().copied()
+                .or_else(|| (1..lines.len()).find(|&i| directive(&lines[i]).is_some()))
+                .map(|i| lines[i][..lines[i].len() - lines[i].trim_start().len()].to_string())
+                .filter(|i| !i.is_empty())
+                .unwrap_or_else(|| "    ".to_string());
+            let replacement: Vec<String> = chosen.iter().map(|v| format!("{}{} {}", indent, original, v)).collect();
+            // No lugar da primeira ocorrência; diretiva nova vai depois da última
+            let at = match positions.first() {
+                Some(&first) => first,
+                None => (1..lines.len()).rev().find(|&i| directive(&lines[i]).is_some()).map_or(1, |i| i + 1),
+            };
+            for &i in positions.iter().rev() {
+                lines.remove(i);
+            }
+            lines.splice(at..at, replacement);
+        }
+        Some(HostBlock { name: local.name.clone(), lines })
+    }
+
+    pub fn resolved(&self) -> Option<HostBlock> {
+        match self.resolution {
+            Resolution::Local => self.local.clone(),
+            Resolution::Remote => self.remote.clone(),
+            Resolution::Merge => self.merged(),
+        }
+    }
+}
+
+// Arquivo em conflito: versões base (:1), local (:2) e remota (:3)
+#[derive(Debug, Clone)]
+pub struct FileMerge {
+    pub path: PathBuf,
+    local: ParsedFile,
+    remote: ParsedFile,
+    base: ParsedFile,
+    pub conflicts: Vec<HostConflict>,
+}
+
+impl FileMerge {
+    pub fn new(path: PathBuf, base: Option<&str>, local: &str, remote: &str) -> Self {
+        let base = ParsedFile::parse(base.unwrap_or_default());
+        let local = ParsedFile::parse(local);
+        let remote = ParsedFile::parse(remote);
+        let conflicts = host_names(&local, &remote)
+            .into_iter()
+            .filter_map(|name| {
+                let (l, r, b) = (local.get(&name), remote.get(&name), base.get(&name));
+                let conflicting = !same(&l, &r) && !same(&l, &b) && !same(&r, &b);
+                conflicting.then_some(HostConflict { host: name, local: l, remote: r, base: b, resolution: Resolution::Local })
+            })
+            .collect();
+        Self { path, local, remote, base, conflicts }
+    }
+
+    // Host importado com um nome que já existe em `path`: o arquivo atual é o
+    // lado local e o mesmo arquivo com o bloco importado é o remoto; a base não
+    // tem o host, então cada lado só ganha nos campos que o outro não define.
+    // None se o arquivo não tiver um bloco com exatamente esse nome
+    pub fn import(path: PathBuf, local: &str, imported: HostBlock) -> Option<Self> {
+        let local = ParsedFile::parse(local);
+        let mut remote = local.clone();
+        *remote.blocks.iter_mut().find(|b| b.name == imported.name)? = imported.clone();
+        let mut base = local.clone();
+        base.blocks.retain(|b| b.name != imported.name);
+        let (l, r) = (local.get(&imported.name), remote.get(&imported.name));
+        let conflicts = if same(&l, &r) {
+            Vec::new()
+        } else {
+            vec![HostConflict { host: imported.name, local: l, remote: r, base: None, resolution: Resolution::Local }]
+        };
+        Some(Self { path, local, remote, base, conflicts })
+    }
+
+    // Conteúdo final: hosts sem conflito seguem a regra de três vias,
+    // os em conflito seguem a escolha do usuário
+    pub fn resolve(&self) -> String {
+        let preamble = if self.local.preamble == self.base.preamble { &self.remote.preamble } else { &self.local.preamble };
+        let mut lines: Vec<String> = preamble.clone();
+        while lines.last().is_some_and(|l| l.trim().is_empty()) {
+            lines.pop();
+        }
+
+        for name in host_names(&self.local, &self.remote) {
+            let block = match self.conflicts.iter().find(|c| c.host == name) {
+                Some(conflict) => conflict.resolved(),
+                None => {
+                    let (l, r, b) = (self.local.get(&name), self.remote.get(&name), self.base.get(&name));
+                    if same(&l, &b) { r } else { l }
+                }
+            };
+            if let Some(block) = block {
+                if !lines.is_empty() {
+                    lines.push(String::new());
+                }
+                lines.extend(block.lines);
+            }
+        }
+        let mut content = lines.join("\n");
+        content.push('\n');
+        content
+    }
+}
+
+// Hosts na ordem local, seguidos dos que só existem no remoto
+fn host_names(local: &ParsedFile, remote: &ParsedFile) -> Vec<String> {
+    let mut names: Vec<String> = local.blocks.iter().map(|b| b.name.clone()).collect();
+    for block in &remote.blocks {
+        if !names.contains(&block.name) {
+            names.push(block.name.clone());
+        }
+    }
+    names
+}
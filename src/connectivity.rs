@@ -1,30 +1,231 @@
-use std::net::{TcpStream, ToSocketAddrs};
-use std::time::Duration;
-use std::process::Command;
+use std::io::{Read, Write};
+use std::net::{SocketAddr, TcpStream, ToSocketAddrs};
+use std::thread;
+use std::time::{Duration, Instant};
+use std::process::{Command, Stdio};
+use serde::{Deserialize, Serialize};
+
+use crate::effective;
+use crate::metadata::HostMeta;
+use crate::recording;
+use crate::ssh_config::{self, SshHost};
+
+// Portas verificadas além da porta SSH do host
+pub const WELL_KNOWN_PORTS: &[u16] = &[80, 443, 3306, 5432, 6379, 8080];
+
+// Forma de verificar se um host está no ar
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ReachMethod {
+    #[default]
+    Tcp,
+    Icmp,
+    Both,
+}
+
+impl ReachMethod {
+    pub fn label(&self) -> &'static str {
+        match self {
+            ReachMethod::Tcp => "tcp",
+            ReachMethod::Icmp => "icmp",
+            ReachMethod::Both => "tcp+icmp",
+        }
+    }
+
+    // Ciclo usado na lista: herdar do global -> tcp -> icmp -> ambos
+    pub fn cycle(current: Option<ReachMethod>) -> Option<ReachMethod> {
+        match current {
+            None => Some(ReachMethod::Tcp),
+            Some(ReachMethod::Tcp) => Some(ReachMethod::Icmp),
+            Some(ReachMethod::Icmp) => Some(ReachMethod::Both),
+            Some(ReachMethod::Both) => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Service {
+    Ssh,
+    Http,
+    MySql,
+    Postgres,
+    Redis,
+}
+
+impl Service {
+    pub fn label(&self) -> &'static str {
+        match self {
+            Service::Ssh => "SSH",
+            Service::Http => "HTTP",
+            Service::MySql => "MySQL",
+            Service::Postgres => "Postgres",
+            Service::Redis => "Redis",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct PortResult {
+    pub port: u16,
+    pub open: bool,
+    pub latency: Option<Duration>,
+    pub service: Option<Service>,
+    pub banner: Option<String>,
+}
 
 pub struct ConnectivityTest;
 
 impl ConnectivityTest {
-    pub fn test_tcp_connection(hostname: &str, port: u16) -> bool {
-        let address = format!("{}:{}", hostname, port);
-        
-        match address.to_socket_addrs() {
-            Ok(mut addrs) => {
-                if let Some(addr) = addrs.next() {
-                    TcpStream::connect_timeout(&addr, Duration::from_secs(5)).is_ok()
-                } else {
-                    false
+    // ICMP echo via o `ping` do sistema (sockets raw exigem privilégios)
+    pub fn icmp_ping(hostname: &str) -> Option<Duration> {
+        let mut cmd = Command::new("ping");
+        if cfg!(target_os = "windows") {
+            cmd.args(["-n", "1", "-w", "2000"]);
+        } else if cfg!(target_os = "macos") {
+            cmd.args(["-c", "1", "-t", "2"]);
+        } else {
+            cmd.args(["-c", "1", "-W", "2"]);
+        }
+        let started = Instant::now();
+        let output = cmd.arg(hostname).stdin(Stdio::null()).stderr(Stdio::null()).output().ok()?;
+        if !output.status.success() {
+            return None;
+        }
+        // Usa o tempo informado pelo ping; sem ele, o tempo do processo
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let reported = stdout.split("time=").nth(1)
+            .and_then(|rest| rest.split(|c: char| !c.is_ascii_digit() && c != '.').next())
+            .and_then(|ms| ms.parse::<f64>().ok())
+            .map(|ms| Duration::from_micros((ms * 1000.0) as u64));
+        Some(reported.unwrap_or_else(|| started.elapsed()))
+    }
+
+    // Executa o health-check do host; o código de saída decide se está no ar
+    pub fn run_check_command(command: &str, timeout: Duration) -> (bool, String) {
+        let shell = if cfg!(target_os = "windows") { ("cmd", "/C") } else { ("sh", "-c") };
+        let child = Command::new(shell.0)
+            .args([shell.1, command])
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::piped())
+            .spawn();
+        let mut child = match child {
+            Ok(child) => child,
+            Err(e) => return (false, format!("erro ao executar: {}", e)),
+        };
+
+        let deadline = Instant::now() + timeout;
+        loop {
+            match child.try_wait() {
+                Ok(Some(status)) => {
+                    let mut stderr = String::new();
+                    if let Some(mut pipe) = child.stderr.take() {
+                        let _ = pipe.read_to_string(&mut stderr);
+                    }
+                    let detail = match stderr.lines().last().map(str::trim).filter(|l| !l.is_empty()) {
+                        Some(line) => format!("código {} ({})", status.code().unwrap_or(-1), line),
+                        None => format!("código {}", status.code().unwrap_or(-1)),
+                    };
+                    return (status.success(), detail);
+                }
+                Ok(None) if Instant::now() < deadline => thread::sleep(Duration::from_millis(50)),
+                _ => {
+                    let _ = child.kill();
+                    let _ = child.wait();
+                    return (false, format!("tempo esgotado após {}s", timeout.as_secs()));
                 }
             }
-            Err(_) => false,
         }
     }
-    
-    pub fn connect_ssh(host_name: &str) -> Result<(), Box<dyn std::error::Error>> {
+
+    // Testa as portas em paralelo e identifica o serviço das que estiverem abertas
+    pub fn scan(hostname: &str, ports: &[u16]) -> Vec<PortResult> {
+        let handles: Vec<_> = ports.iter().map(|&port| {
+            let hostname = hostname.to_string();
+            thread::spawn(move || {
+                let addr = format!("{}:{}", hostname, port).to_socket_addrs().ok().and_then(|mut a| a.next());
+                let started = Instant::now();
+                let latency = addr
+                    .filter(|a| TcpStream::connect_timeout(a, Duration::from_secs(2)).is_ok())
+                    .map(|_| started.elapsed());
+                let open = latency.is_some();
+                let (service, banner) = match addr.filter(|_| open).and_then(Self::detect_service) {
+                    Some((service, banner)) => (Some(service), banner),
+                    None => (None, None),
+                };
+                PortResult { port, open, latency, service, banner }
+            })
+        }).collect();
+        handles.into_iter().filter_map(|h| h.join().ok()).collect()
+    }
+
+    // Serviços que falam primeiro (SSH, MySQL) são reconhecidos pelo banner;
+    // os demais respondem a uma sonda específica
+    pub fn detect_service(addr: SocketAddr) -> Option<(Service, Option<String>)> {
+        if let Some(greeting) = Self::probe(addr, None) {
+            if greeting.starts_with(b"SSH-") {
+                let banner = String::from_utf8_lossy(&greeting).lines().next().map(|l| l.trim().to_string());
+                return Some((Service::Ssh, banner));
+            }
+            // Handshake do MySQL: cabeçalho de 4 bytes seguido do protocolo 10
+            if greeting.len() > 5 && greeting[4] == 0x0a {
+                let version: Vec<u8> = greeting[5..].iter().take_while(|&&b| b != 0).copied().collect();
+                return Some((Service::MySql, Some(String::from_utf8_lossy(&version).to_string())));
+            }
+            return None;
+        }
+
+        if let Some(reply) = Self::probe(addr, Some(b"HEAD / HTTP/1.0\r\n\r\n")) {
+            if reply.starts_with(b"HTTP/") {
+                let text = String::from_utf8_lossy(&reply).to_string();
+                let server = text.lines()
+                    .find_map(|l| l.split_once(':').filter(|(k, _)| k.eq_ignore_ascii_case("server")).map(|(_, v)| v.trim().to_string()));
+                return Some((Service::Http, server));
+            }
+        }
+        if let Some(reply) = Self::probe(addr, Some(b"PING\r\n")) {
+            if reply.starts_with(b"+PONG") || reply.starts_with(b"-NOAUTH") {
+                return Some((Service::Redis, None));
+            }
+        }
+        // SSLRequest do Postgres: o servidor responde 'S' ou 'N'
+        if let Some(reply) = Self::probe(addr, Some(&[0, 0, 0, 8, 0x04, 0xd2, 0x16, 0x2f])) {
+            if reply.len() == 1 && (reply[0] == b'S' || reply[0] == b'N') {
+                return Some((Service::Postgres, None));
+            }
+        }
+        None
+    }
+
+    fn probe(addr: SocketAddr, payload: Option<&[u8]>) -> Option<Vec<u8>> {
+        let mut stream = TcpStream::connect_timeout(&addr, Duration::from_secs(2)).ok()?;
+        stream.set_read_timeout(Some(Duration::from_secs(1))).ok()?;
+        if let Some(payload) = payload {
+            stream.write_all(payload).ok()?;
+        }
+        let mut buffer = [0u8; 512];
+        let read = stream.read(&mut buffer).ok()?;
+        (read > 0).then(|| buffer[..read].to_vec())
+    }
+
+    // Argumentos do ssh; com `remote_command`, ele aloca um terminal (`-t`) e
+    // roda o comando no lugar do shell de login
+    pub fn ssh_args(host_name: &str, extra_args: &[String], remote_command: Option<&str>) -> Vec<String> {
+        let mut args = Vec::new();
+        if remote_command.is_some() {
+            args.push("-t".to_string());
+        }
+        args.extend(extra_args.iter().cloned());
+        args.push(host_name.to_string());
+        args.extend(remote_command.map(String::from));
+        args
+    }
+
+    pub fn connect_ssh(host_name: &str, extra_args: &[String], remote_command: Option<&str>) -> Result<(), Box<dyn std::error::Error>> {
         use std::process::Stdio;
         
         let mut cmd = Command::new("ssh");
-        cmd.arg(host_name)
+        cmd.args(Self::ssh_args(host_name, extra_args, remote_command))
            .stdin(Stdio::inherit())
            .stdout(Stdio::inherit())
            .stderr(Stdio::inherit());
@@ -37,4 +238,66 @@ impl ConnectivityTest {
         
         Ok(())
     }
-}
\ No newline at end of file
+
+    // Programa de uma sessão, igual na interface e no `lazysshrs connect`: o
+    // console alternativo do host, com `{host}` e `{hostname}` trocados, ou o ssh.
+    // Argumentos extras são do ssh: com eles, o console fica de fora
+    pub fn session_argv(host: &SshHost, meta: &HostMeta, extra_args: &[String]) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+        match meta.console.as_deref().filter(|_| extra_args.is_empty()) {
+            Some(command) => {
+                let alias = effective::host_alias(host);
+                let hostname = host.hostname.clone().unwrap_or_else(|| alias.to_string());
+                ssh_config::split_args(&command.replace("{host}", alias).replace("{hostname}", &hostname))
+                    .ok_or_else(|| format!("aspas sem fechar no console de {}", host.name).into())
+            }
+            None => Ok(std::iter::once("ssh".to_string())
+                .chain(Self::ssh_args(&host.name, extra_args, meta.post_login.as_deref()))
+                .collect()),
+        }
+    }
+
+    // Roda a sessão no terminal atual; com `record`, dentro do `asciinema rec`
+    pub fn run_session(host_name: &str, argv: &[String], record: bool) -> Result<(), Box<dyn std::error::Error>> {
+        if record {
+            return recording::record(&recording::new_path(host_name), host_name, argv);
+        }
+        Self::run_console(argv)
+    }
+
+    // Conexão alternativa (telnet, console serial), como `connect_ssh`
+    pub fn run_console(argv: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+        let (program, args) = argv.split_first().ok_or("comando de console vazio")?;
+        let status = Command::new(program).args(args).status()
+            .map_err(|e| format!("{}: {}", program, e))?;
+        if !status.success() {
+            return Err(format!("{} terminou com código {:?}", program, status.code()).into());
+        }
+        Ok(())
+    }
+
+    pub fn sftp(host_name: &str) -> Result<(), Box<dyn std::error::Error>> {
+        let status = Command::new("sftp").arg(host_name).status()?;
+        if !status.success() {
+            return Err(format!("sftp terminou com código {:?}", status.code()).into());
+        }
+        Ok(())
+    }
+
+    // Comando avulso no host; a saída fica na tela até o Enter
+    pub fn run_remote(host_name: &str, command: &str) -> Result<(), Box<dyn std::error::Error>> {
+        let status = Command::new("ssh").arg(host_name).arg(command).status()?;
+        let code = status.code().map(|c| c.to_string()).unwrap_or_else(|| "?".to_string());
+        println!("\n[código de saída {}] Enter para voltar", code);
+        let mut line = String::new();
+        std::io::stdin().read_line(&mut line)?;
+        Ok(())
+    }
+
+    pub fn jump_args(jumps: &[String]) -> Vec<String> {
+        if jumps.is_empty() {
+            Vec::new()
+        } else {
+            vec!["-J".to_string(), jumps.join(",")]
+        }
+    }
+}
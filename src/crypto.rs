@@ -0,0 +1,130 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+use argon2::Argon2;
+use chacha20poly1305::aead::{Aead, KeyInit};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+
+use crate::backup;
+
+// Formato cifrado: MAGIC + sal (16) + nonce (12) + texto cifrado
+const MAGIC: &[u8] = b"LAZYSSHRS-ENC1\n";
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 12;
+
+struct Cipher {
+    passphrase: String,
+    salt: [u8; SALT_LEN],
+    key: Key,
+    // Falso quando a cifragem foi desligada: os arquivos voltam a ser gravados em texto
+    encrypt_writes: bool,
+}
+
+// Chave da sessão, definida uma vez na inicialização
+static CIPHER: OnceLock<Cipher> = OnceLock::new();
+
+fn derive(passphrase: &str, salt: &[u8]) -> Result<Key, Box<dyn std::error::Error>> {
+    let mut key = Key::default();
+    Argon2::default()
+        .hash_password_into(passphrase.as_bytes(), salt, &mut key)
+        .map_err(|e| format!("erro ao derivar a chave: {}", e))?;
+    Ok(key)
+}
+
+fn random<const N: usize>() -> Result<[u8; N], Box<dyn std::error::Error>> {
+    let mut bytes = [0u8; N];
+    getrandom::getrandom(&mut bytes).map_err(|e| format!("erro ao gerar bytes aleatórios: {}", e))?;
+    Ok(bytes)
+}
+
+fn split(data: &[u8]) -> Option<(&[u8], &[u8], &[u8])> {
+    let body = data.strip_prefix(MAGIC)?;
+    if body.len() < SALT_LEN + NONCE_LEN {
+        return None;
+    }
+    let (salt, rest) = body.split_at(SALT_LEN);
+    let (nonce, ciphertext) = rest.split_at(NONCE_LEN);
+    Some((salt, nonce, ciphertext))
+}
+
+impl Cipher {
+    fn encrypt(&self, plaintext: &[u8]) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+        let nonce: [u8; NONCE_LEN] = random()?;
+        let ciphertext = ChaCha20Poly1305::new(&self.key)
+            .encrypt(Nonce::from_slice(&nonce), plaintext)
+            .map_err(|_| "erro ao cifrar")?;
+        let mut out = MAGIC.to_vec();
+        out.extend_from_slice(&self.salt);
+        out.extend_from_slice(&nonce);
+        out.extend_from_slice(&ciphertext);
+        Ok(out)
+    }
+
+    fn decrypt(&self, data: &[u8]) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+        let (salt, nonce, ciphertext) = split(data).ok_or("arquivo cifrado inválido")?;
+        // Arquivo gravado com outro sal: deriva a chave correspondente
+        let key = if salt == self.salt { self.key } else { derive(&self.passphrase, salt)? };
+        ChaCha20Poly1305::new(&key)
+            .decrypt(Nonce::from_slice(nonce), ciphertext)
+            .map_err(|_| "senha incorreta ou arquivo corrompido".into())
+    }
+}
+
+pub fn is_encrypted(path: &Path) -> bool {
+    fs::read(path).is_ok_and(|data| data.starts_with(MAGIC))
+}
+
+// Pede a senha (ou lê LAZYSSHRS_PASSPHRASE) e valida contra os arquivos já cifrados
+pub fn unlock(files: &[PathBuf], encrypt_writes: bool) -> Result<(), Box<dyn std::error::Error>> {
+    let encrypted: Vec<Vec<u8>> = files.iter()
+        .filter_map(|path| fs::read(path).ok())
+        .filter(|data| data.starts_with(MAGIC))
+        .collect();
+
+    let passphrase = match std::env::var("LAZYSSHRS_PASSPHRASE") {
+        Ok(passphrase) => passphrase,
+        Err(_) if encrypted.is_empty() => {
+            let passphrase = rpassword::prompt_password("Nova senha para os metadados do lazysshrs: ")?;
+            if rpassword::prompt_password("Repita a senha: ")? != passphrase {
+                return Err("as senhas não conferem".into());
+            }
+            passphrase
+        }
+        Err(_) => rpassword::prompt_password("Senha dos metadados do lazysshrs: ")?,
+    };
+    if passphrase.is_empty() {
+        return Err("a senha não pode ser vazia".into());
+    }
+
+    let salt: [u8; SALT_LEN] = match encrypted.first().and_then(|data| split(data)) {
+        Some((salt, _, _)) => salt.try_into()?,
+        None => random()?,
+    };
+    let key = derive(&passphrase, &salt)?;
+    let cipher = Cipher { passphrase, salt, key, encrypt_writes };
+    for data in &encrypted {
+        cipher.decrypt(data)?;
+    }
+    let _ = CIPHER.set(cipher);
+    Ok(())
+}
+
+// Lê um arquivo de estado, decifrando se necessário
+pub fn read_to_string(path: &Path) -> Option<String> {
+    let data = fs::read(path).ok()?;
+    if !data.starts_with(MAGIC) {
+        return String::from_utf8(data).ok();
+    }
+    let plaintext = CIPHER.get()?.decrypt(&data).ok()?;
+    String::from_utf8(plaintext).ok()
+}
+
+// Grava um arquivo de estado, cifrado quando a cifragem está ligada; pelo
+// temporário renomeado, para um arquivo truncado não virar um estado vazio
+pub fn write(path: &Path, content: &str) -> Result<(), Box<dyn std::error::Error>> {
+    match CIPHER.get().filter(|c| c.encrypt_writes) {
+        Some(cipher) => backup::replace(path, cipher.encrypt(content.as_bytes())?),
+        None => backup::replace(path, content),
+    }
+}
@@ -0,0 +1,411 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use rusqlite::{params, Connection};
+
+use crate::backup;
+use crate::config::AppConfig;
+use crate::ssh_config::{self, SshConfig, SshHost};
+
+// Primeira linha dos arquivos de include gerados a partir do banco
+pub const GENERATED_HEADER: &str = "# Gerado pelo lazysshrs a partir do banco de hosts; edições manuais serão sobrescritas";
+
+// Tabela de hosts; o mesmo nome pode existir em pastas diferentes. `position`
+// guarda a ordem dos blocos no include, que o ssh usa (o primeiro valor vale)
+const HOSTS_TABLE: &str = "CREATE TABLE IF NOT EXISTS hosts (
+    name TEXT NOT NULL,
+    folder TEXT NOT NULL,
+    hostname TEXT,
+    user TEXT,
+    port INTEGER,
+    directives TEXT NOT NULL,
+    updated_at INTEGER NOT NULL,
+    description TEXT,
+    position INTEGER NOT NULL,
+    PRIMARY KEY (folder, name)
+);";
+
+pub fn is_generated(path: &Path) -> bool {
+    fs::read_to_string(path).is_ok_and(|content| content.starts_with(GENERATED_HEADER))
+}
+
+// O banco só escreve includes que ele mesmo gerou (ou que ainda não existem);
+// os demais continuam editados como arquivo comum
+pub fn manages(path: &Path) -> bool {
+    !path.exists() || is_generated(path)
+}
+
+// Host como guardado no banco: diretivas na ordem em que são escritas
+#[derive(Debug, Clone, Default)]
+pub struct HostRecord {
+    pub name: String,
+    pub folder: String,
+    // Comentários gravados acima do `Host` no include gerado
+    pub description: Option<String>,
+    pub directives: Vec<(String, String)>,
+}
+
+impl HostRecord {
+    pub fn from_host(host: &SshHost, folder: &str) -> Self {
+        let mut directives = Vec::new();
+        if let Some(hostname) = &host.hostname {
+            directives.push(("HostName".to_string(), hostname.clone()));
+        }
+        if let Some(user) = &host.user {
+            directives.push(("User".to_string(), user.clone()));
+        }
+        if let Some(port) = host.port {
+            directives.push(("Port".to_string(), port.to_string()));
+        }
+        for identity_file in &host.identity_files {
+            directives.push(("IdentityFile".to_string(), identity_file.clone()));
+        }
+        for forward in &host.local_forwards {
+            directives.push(("LocalForward".to_string(), forward.clone()));
+        }
+        directives.extend(host.other_options.iter().map(|(k, v)| (k.clone(), v.clone())));
+        Self { name: host.name.clone(), folder: folder.to_string(), description: host.description.clone(), directives }
+    }
+
+    fn value(&self, key: &str) -> Option<&str> {
+        self.directives.iter().find(|(k, _)| k.eq_ignore_ascii_case(key)).map(|(_, v)| v.as_str())
+    }
+
+    fn block(&self) -> String {
+        let mut block = String::new();
+        for comment in ssh_config::description_lines(self.description.as_deref()) {
+            block.push_str(&comment);
+            block.push('\n');
+        }
+        block.push_str(&format!("Host {}\n", self.name));
+        for (key, value) in &self.directives {
+            block.push_str(&format!("    {} {}\n", key, ssh_config::quote(key, value)));
+        }
+        block
+    }
+}
+
+// Banco SQLite como fonte única dos hosts das pastas; os includes são gerados dele
+pub struct HostDb {
+    conn: Connection,
+    // Com `encrypt_state` o histórico não vai para o banco, que fica em texto
+    log_connections: bool,
+}
+
+impl HostDb {
+    // `database` vazio na configuração desativa o modo banco
+    pub fn open_configured(config: &AppConfig) -> Result<Option<Self>, Box<dyn std::error::Error>> {
+        let path = config.database.trim();
+        if path.is_empty() {
+            return Ok(None);
+        }
+        let path = match path.strip_prefix("~/") {
+            Some(rest) => home::home_dir().ok_or("diretório home não encontrado")?.join(rest),
+            None => PathBuf::from(path),
+        };
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let mut db = Self::open(&path)?;
+        db.log_connections = !config.encrypt_state;
+        Ok(Some(db))
+    }
+
+    pub fn open(path: &Path) -> Result<Self, Box<dyn std::error::Error>> {
+        let conn = Connection::open(path)?;
+        conn.execute_batch(HOSTS_TABLE)?;
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS connections (
+                name TEXT NOT NULL,
+                connected_at INTEGER NOT NULL
+            );",
+        )?;
+        conn.execute_batch("CREATE INDEX IF NOT EXISTS hosts_folder ON hosts(folder);")?;
+        Ok(Self { conn, log_connections: true })
+    }
+
+    pub fn is_empty(&self) -> Result<bool, Box<dyn std::error::Error>> {
+        let count: i64 = self.conn.query_row("SELECT COUNT(*) FROM hosts", [], |row| row.get(0))?;
+        Ok(count == 0)
+    }
+
+    // Primeira execução: copia os hosts das pastas de include para o banco e
+    // regera os includes. Só entram os arquivos que o banco consegue reescrever
+    // sem perder nada (só blocos Host, com descrições); os outros continuam
+    // como includes comuns e são devolvidos. Antes de regerar, cada arquivo
+    // importado ganha uma cópia `.pre-db` nos backups
+    pub fn import(&mut self, hosts: &[SshHost], workdir: &Path) -> Result<Vec<PathBuf>, Box<dyn std::error::Error>> {
+        let mut files: Vec<(PathBuf, Vec<HostRecord>)> = Vec::new();
+        for host in hosts.iter().filter(|h| !h.is_separator) {
+            let Some(folder) = host.source_dir.as_deref() else {
+                continue;
+            };
+            let path = workdir.join(folder).join("config");
+            if host.source_file.as_deref() != Some(path.as_path()) {
+                continue;
+            }
+            let record = HostRecord::from_host(host, folder);
+            match files.iter_mut().find(|(p, _)| *p == path) {
+                Some((_, records)) => records.push(record),
+                None => files.push((path, vec![record])),
+            }
+        }
+
+        let mut skipped = Vec::new();
+        let mut imported = Vec::new();
+        let tx = self.conn.transaction()?;
+        for (path, records) in &files {
+            let content = SshConfig::read_text(path)?;
+            if !lossless(&content, records) {
+                skipped.push(path.clone());
+                continue;
+            }
+            backup::snapshot(path, "pre-db")?;
+            for (position, record) in records.iter().enumerate() {
+                insert(&tx, record, Some(position as i64))?;
+            }
+            imported.push(path.clone());
+        }
+        tx.commit()?;
+        if !imported.is_empty() {
+            self.generate(workdir, &imported)?;
+        }
+        Ok(skipped)
+    }
+
+    // Host novo, no fim da pasta ou em `position`; falha se a pasta já tiver
+    // um host com o mesmo nome
+    pub fn insert(&self, record: &HostRecord, position: Option<i64>) -> Result<(), Box<dyn std::error::Error>> {
+        insert(&self.conn, record, position)
+    }
+
+    // Regrava um host já existente na mesma pasta, sem mudar sua posição
+    pub fn upsert(&self, record: &HostRecord) -> Result<(), Box<dyn std::error::Error>> {
+        upsert(&self.conn, record, None)
+    }
+
+    // Regrava um host na posição que ele tinha (desfazer uma edição)
+    pub fn restore(&self, record: &HostRecord, position: Option<i64>) -> Result<(), Box<dyn std::error::Error>> {
+        upsert(&self.conn, record, position)
+    }
+
+    // Posição do bloco no include gerado da pasta
+    pub fn position(&self, folder: &str, name: &str) -> Result<Option<i64>, Box<dyn std::error::Error>> {
+        let mut statement = self.conn.prepare("SELECT position FROM hosts WHERE folder = ?1 AND name = ?2")?;
+        let mut rows = statement.query_map(params![folder, name], |row| row.get(0))?;
+        Ok(rows.next().transpose()?)
+    }
+
+    pub fn delete(&self, folder: &str, name: &str) -> Result<(), Box<dyn std::error::Error>> {
+        self.conn.execute("DELETE FROM hosts WHERE folder = ?1 AND name = ?2", params![folder, name])?;
+        Ok(())
+    }
+
+    pub fn record_connection(&self, name: &str) -> Result<(), Box<dyn std::error::Error>> {
+        if !self.log_connections {
+            return Ok(());
+        }
+        self.conn.execute("INSERT INTO connections (name, connected_at) VALUES (?1, ?2)", params![name, now()])?;
+        Ok(())
+    }
+
+    fn records(&self) -> Result<Vec<HostRecord>, Box<dyn std::error::Error>> {
+        let mut statement = self.conn.prepare("SELECT name, folder, description, directives FROM hosts ORDER BY folder, position, rowid")?;
+        let rows = statement.query_map([], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?, row.get::<_, Option<String>>(2)?, row.get::<_, String>(3)?))
+        })?;
+        let mut records = Vec::new();
+        for row in rows {
+            let (name, folder, description, directives) = row?;
+            records.push(HostRecord { name, folder, description, directives: serde_json::from_str(&directives)? });
+        }
+        Ok(records)
+    }
+
+    // Reescreve `<workdir>/<pasta>/config` de cada pasta do banco; includes
+    // gerados antes cuja pasta ficou vazia são esvaziados. Devolve os arquivos
+    // escritos. Um arquivo que não foi gerado pelo banco nunca é sobrescrito
+    pub fn regenerate(&self, workdir: &Path) -> Result<Vec<PathBuf>, Box<dyn std::error::Error>> {
+        self.generate(workdir, &[])
+    }
+
+    // `adopted`: arquivos comuns que acabaram de ser importados e passam a ser gerados
+    fn generate(&self, workdir: &Path, adopted: &[PathBuf]) -> Result<Vec<PathBuf>, Box<dyn std::error::Error>> {
+        let mut files: Vec<(PathBuf, String)> = Vec::new();
+        for record in self.records()? {
+            let path = workdir.join(&record.folder).join("config");
+            if files.last().map(|(p, _)| p) != Some(&path) {
+                files.push((path, format!("{}\n", GENERATED_HEADER)));
+            }
+            if let Some((_, content)) = files.last_mut() {
+                content.push('\n');
+                content.push_str(&record.block());
+            }
+        }
+
+        for entry in fs::read_dir(workdir)?.flatten() {
+            let path = entry.path().join("config");
+            let generated = fs::read_to_string(&path).is_ok_and(|c| c.starts_with(GENERATED_HEADER));
+            if generated && !files.iter().any(|(p, _)| *p == path) {
+                files.push((path, format!("{}\n", GENERATED_HEADER)));
+            }
+        }
+
+        if let Some((path, _)) = files.iter().find(|(path, _)| !manages(path) && !adopted.contains(path)) {
+            return Err(format!("{} não foi gerado pelo lazysshrs; o banco não o sobrescreve", path.display()).into());
+        }
+        for (path, content) in &files {
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            backup::write(path, content)?;
+        }
+        Ok(files.into_iter().map(|(p, _)| p).collect())
+    }
+}
+
+fn insert(conn: &Connection, record: &HostRecord, position: Option<i64>) -> Result<(), Box<dyn std::error::Error>> {
+    let exists: bool = conn.query_row(
+        "SELECT COUNT(*) > 0 FROM hosts WHERE folder = ?1 AND name = ?2",
+        params![record.folder, record.name],
+        |row| row.get(0),
+    )?;
+    if exists {
+        return Err(format!("a pasta {} já tem o host {} no banco", record.folder, record.name).into());
+    }
+    upsert(conn, record, position)
+}
+
+// Sem `position`, um host existente fica onde está e um novo vai para o fim da pasta
+fn upsert(conn: &Connection, record: &HostRecord, position: Option<i64>) -> Result<(), Box<dyn std::error::Error>> {
+    let position = match position {
+        Some(position) => position,
+        None => conn.query_row(
+            "SELECT COALESCE(
+                (SELECT position FROM hosts WHERE folder = ?1 AND name = ?2),
+                (SELECT MAX(position) + 1 FROM hosts WHERE folder = ?1),
+                0
+            )",
+            params![record.folder, record.name],
+            |row| row.get(0),
+        )?,
+    };
+    conn.execute(
+        "INSERT INTO hosts (name, folder, hostname, user, port, directives, updated_at, description, position)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)
+         ON CONFLICT(folder, name) DO UPDATE SET hostname = ?3, user = ?4, port = ?5,
+             directives = ?6, updated_at = ?7, description = ?8, position = ?9",
+        params![
+            record.name,
+            record.folder,
+            record.value("Hostname"),
+            record.value("User"),
+            record.value("Port").and_then(|p| p.parse::<u16>().ok()),
+            serde_json::to_string(&record.directives)?,
+            now(),
+            record.description,
+            position,
+        ],
+    )?;
+    Ok(())
+}
+
+// Se o arquivo equivale ao que o banco gera dos seus hosts: cada bloco com
+// a descrição, a linha Host e as mesmas diretivas (a ordem das diferentes não
+// importa ao ssh). Comentários soltos, Match, Include ou diretivas antes do
+// primeiro Host fazem a comparação falhar
+fn lossless(content: &str, records: &[HostRecord]) -> bool {
+    let mut names: Vec<&str> = records.iter().map(|r| r.name.as_str()).collect();
+    names.sort_unstable();
+    names.dedup();
+    if names.len() != records.len() {
+        return false;
+    }
+    let generated: String = records.iter().map(|r| format!("\n{}", r.block())).collect();
+    blocks(content) == blocks(&generated)
+}
+
+// Blocos normalizados: comentários e linha Host, depois as diretivas como o
+// ssh as lê, ordenadas pela palavra-chave (as repetidas mantêm a ordem)
+fn blocks(content: &str) -> Vec<(Vec<String>, Vec<String>)> {
+    let mut blocks: Vec<(Vec<String>, Vec<String>)> = Vec::new();
+    let mut pending: Vec<String> = Vec::new();
+    for line in content.lines().map(str::trim).filter(|l| !l.is_empty() && *l != GENERATED_HEADER) {
+        if line.starts_with('#') {
+            pending.push(format!("# {}", line.trim_start_matches('#').trim()));
+            continue;
+        }
+        let directive = match ssh_config::split_line(line) {
+            Some((key, raw)) => format!("{} {}", key.to_lowercase(), ssh_config::unquote(key, raw)),
+            None => line.to_lowercase(),
+        };
+        if directive.starts_with("host ") {
+            pending.push(directive);
+            blocks.push((std::mem::take(&mut pending), Vec::new()));
+            continue;
+        }
+        // Comentário no meio do bloco (ou diretiva antes do primeiro Host)
+        match blocks.last_mut() {
+            Some((_, body)) if pending.is_empty() => body.push(directive),
+            _ => {
+                pending.push(directive);
+                blocks.push((std::mem::take(&mut pending), Vec::new()));
+            }
+        }
+    }
+    if !pending.is_empty() {
+        blocks.push((pending, Vec::new()));
+    }
+    for (_, body) in blocks.iter_mut() {
+        body.sort_by(|a, b| a.split(' ').next().cmp(&b.split(' ').next()));
+    }
+    blocks
+}
+
+fn now() -> i64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs() as i64).unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(name: &str, hostname: &str) -> HostRecord {
+        HostRecord {
+            name: name.to_string(),
+            folder: "work".to_string(),
+            description: None,
+            directives: vec![("HostName".to_string(), hostname.to_string())],
+        }
+    }
+
+    #[test]
+    fn lossless_compares_blocks_in_file_order() {
+        let content = "Host web1\n    HostName one\n\nHost web*\n    HostName star\n";
+        assert!(lossless(content, &[record("web1", "one"), record("web*", "star")]));
+        assert!(!lossless(content, &[record("web*", "star"), record("web1", "one")]));
+        assert!(!lossless(&format!("# solto\n\n{}", content), &[record("web1", "one"), record("web*", "star")]));
+    }
+
+    #[test]
+    fn regenerate_keeps_block_positions() {
+        let workdir = std::env::temp_dir().join(format!("lazysshrs-regenerate-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&workdir);
+        fs::create_dir_all(&workdir).unwrap();
+        let db = HostDb::open(&workdir.join("hosts.db")).unwrap();
+        db.insert(&record("web1", "one"), Some(0)).unwrap();
+        db.insert(&record("web*", "star"), Some(1)).unwrap();
+        db.insert(&record("app", "app"), None).unwrap();
+        db.upsert(&record("web1", "uno")).unwrap();
+        db.regenerate(&workdir).unwrap();
+
+        let content = fs::read_to_string(workdir.join("work/config")).unwrap();
+        let names: Vec<&str> = content.lines().filter_map(|l| l.strip_prefix("Host ")).collect();
+        assert_eq!(names, ["web1", "web*", "app"]);
+        let config = SshConfig::load_from_workdir(&workdir.join("work")).unwrap();
+        let web1 = config.hosts.iter().find(|h| h.name == "web1").unwrap();
+        assert_eq!(web1.hostname.as_deref(), Some("uno"));
+        let _ = fs::remove_dir_all(&workdir);
+    }
+}
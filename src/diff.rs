@@ -0,0 +1,90 @@
+use std::path::PathBuf;
+
+use similar::{DiffTag, TextDiff};
+
+// Arquivo a sincronizar: conteúdo local e o que está hoje do outro lado
+#[derive(Debug, Clone)]
+pub struct FileDiff {
+    pub path: PathBuf,
+    pub local: Option<String>,
+    pub remote: Option<String>,
+    pub excluded: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FileStatus {
+    New,
+    Deleted,
+    Changed,
+    Unchanged,
+}
+
+impl FileStatus {
+    pub fn label(&self) -> &'static str {
+        match self {
+            FileStatus::New => "new",
+            FileStatus::Deleted => "deleted",
+            FileStatus::Changed => "changed",
+            FileStatus::Unchanged => "same",
+        }
+    }
+}
+
+impl FileDiff {
+    pub fn status(&self) -> FileStatus {
+        match (&self.remote, &self.local) {
+            (None, _) => FileStatus::New,
+            (Some(_), None) => FileStatus::Deleted,
+            (Some(remote), Some(local)) if remote == local => FileStatus::Unchanged,
+            _ => FileStatus::Changed,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RowKind {
+    Same,
+    Removed,
+    Added,
+    Changed,
+}
+
+// Linha da visão lado a lado: (número, texto) de cada lado
+pub struct Row {
+    pub left: Option<(usize, String)>,
+    pub right: Option<(usize, String)>,
+    pub kind: RowKind,
+}
+
+// Esquerda = versão atual do outro lado, direita = versão local que será enviada
+pub fn side_by_side(old: &str, new: &str) -> Vec<Row> {
+    let diff = TextDiff::from_lines(old, new);
+    let old_lines: Vec<&str> = old.lines().collect();
+    let new_lines: Vec<&str> = new.lines().collect();
+    let line = |lines: &[&str], i: usize| Some((i + 1, lines.get(i).copied().unwrap_or_default().to_string()));
+
+    let mut rows = Vec::new();
+    for op in diff.ops() {
+        let (tag, old_range, new_range) = op.as_tag_tuple();
+        match tag {
+            DiffTag::Equal => rows.extend(old_range.zip(new_range).map(|(o, n)| Row {
+                left: line(&old_lines, o),
+                right: line(&new_lines, n),
+                kind: RowKind::Same,
+            })),
+            DiffTag::Delete => rows.extend(old_range.map(|o| Row { left: line(&old_lines, o), right: None, kind: RowKind::Removed })),
+            DiffTag::Insert => rows.extend(new_range.map(|n| Row { left: None, right: line(&new_lines, n), kind: RowKind::Added })),
+            DiffTag::Replace => {
+                let (old_len, new_len) = (old_range.len(), new_range.len());
+                for i in 0..old_len.max(new_len) {
+                    rows.push(Row {
+                        left: (i < old_len).then(|| line(&old_lines, old_range.start + i)).flatten(),
+                        right: (i < new_len).then(|| line(&new_lines, new_range.start + i)).flatten(),
+                        kind: RowKind::Changed,
+                    });
+                }
+            }
+        }
+    }
+    rows
+}
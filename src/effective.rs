@@ -0,0 +1,166 @@
+use crate::ssh_config::SshHost;
+
+// Diretivas que acumulam valores em vez de seguir "o primeiro vence"
+const CUMULATIVE: &[&str] = &[
+    "identityfile",
+    "certificatefile",
+    "localforward",
+    "remoteforward",
+    "dynamicforward",
+    "sendenv",
+    "setenv",
+];
+
+#[derive(Debug, Clone)]
+pub struct EffectiveOption {
+    pub key: String,
+    pub value: String,
+    pub source: usize,
+}
+
+#[derive(Debug, Clone)]
+pub struct Shadowed {
+    pub key: String,
+    pub block: usize,
+    pub winner: usize,
+    pub winning_value: String,
+}
+
+// Casamento de padrões do ssh_config: `*`, `?` e negação com `!`
+pub fn pattern_matches(patterns: &str, alias: &str) -> bool {
+    let mut matched = false;
+    for pattern in patterns.split_whitespace() {
+        if let Some(negated) = pattern.strip_prefix('!') {
+            if wildcard_match(negated, alias) {
+                return false;
+            }
+        } else if wildcard_match(pattern, alias) {
+            matched = true;
+        }
+    }
+    matched
+}
+
+fn wildcard_match(pattern: &str, text: &str) -> bool {
+    // Caminho rápido para padrões literais, a grande maioria em configs grandes
+    if !pattern.contains(['*', '?']) {
+        return pattern.eq_ignore_ascii_case(text);
+    }
+    let p: Vec<char> = pattern.chars().collect();
+    let t: Vec<char> = text.chars().collect();
+    let (mut pi, mut ti) = (0, 0);
+    let mut star: Option<(usize, usize)> = None;
+
+    while ti < t.len() {
+        if pi < p.len() && (p[pi] == '?' || p[pi].eq_ignore_ascii_case(&t[ti])) {
+            pi += 1;
+            ti += 1;
+        } else if pi < p.len() && p[pi] == '*' {
+            star = Some((pi, ti));
+            pi += 1;
+        } else if let Some((star_pi, star_ti)) = star {
+            pi = star_pi + 1;
+            ti = star_ti + 1;
+            star = Some((star_pi, star_ti + 1));
+        } else {
+            return false;
+        }
+    }
+    p[pi..].iter().all(|&c| c == '*')
+}
+
+pub fn is_wildcard(pattern: &str) -> bool {
+    pattern.contains('*') || pattern.contains('?') || pattern.starts_with('!')
+}
+
+// Nome usado pelo ssh para avaliar os blocos: o primeiro padrão literal
+pub fn host_alias(host: &SshHost) -> &str {
+    host.name
+        .split_whitespace()
+        .find(|p| !is_wildcard(p))
+        .unwrap_or(&host.name)
+}
+
+pub fn host_options(host: &SshHost) -> Vec<(String, String)> {
+    let mut options = Vec::new();
+    if let Some(hostname) = &host.hostname {
+        options.push(("hostname".to_string(), hostname.clone()));
+    }
+    if let Some(user) = &host.user {
+        options.push(("user".to_string(), user.clone()));
+    }
+    if let Some(port) = host.port {
+        options.push(("port".to_string(), port.to_string()));
+    }
+    for identity_file in &host.identity_files {
+        options.push(("identityfile".to_string(), identity_file.clone()));
+    }
+    for local_forward in &host.local_forwards {
+        options.push(("localforward".to_string(), local_forward.clone()));
+    }
+    for (key, value) in host.other_options.iter() {
+        options.push((key.to_lowercase(), value.clone()));
+    }
+    options
+}
+
+// Índices dos blocos na ordem em que o ssh os lê; a lista pode ter as seções
+// reordenadas (`section_order`)
+fn load_order(hosts: &[SshHost]) -> Vec<usize> {
+    let mut blocks: Vec<usize> = (0..hosts.len()).collect();
+    blocks.sort_by_key(|&i| hosts[i].load_index);
+    blocks
+}
+
+// Resolve as opções efetivas de `hosts[index]` percorrendo os blocos na ordem de carga
+pub fn resolve(hosts: &[SshHost], index: usize) -> (Vec<EffectiveOption>, Vec<Shadowed>) {
+    let mut effective: Vec<EffectiveOption> = Vec::new();
+    let mut shadowed = Vec::new();
+    let Some(target) = hosts.get(index) else {
+        return (effective, shadowed);
+    };
+    let alias = host_alias(target);
+
+    for block in load_order(hosts) {
+        let host = &hosts[block];
+        if host.is_separator || !pattern_matches(&host.name, alias) {
+            continue;
+        }
+        for (key, value) in host_options(host) {
+            if CUMULATIVE.contains(&key.as_str()) {
+                effective.push(EffectiveOption { key, value, source: block });
+                continue;
+            }
+            match effective.iter().find(|o| o.key == key) {
+                Some(winner) => shadowed.push(Shadowed {
+                    key,
+                    block,
+                    winner: winner.source,
+                    winning_value: winner.value.clone(),
+                }),
+                None => effective.push(EffectiveOption { key, value, source: block }),
+            }
+        }
+    }
+    (effective, shadowed)
+}
+
+// Valores que um novo host `alias` herdaria dos blocos com curinga
+pub fn inherited_options(hosts: &[SshHost], alias: &str) -> Vec<EffectiveOption> {
+    let mut inherited: Vec<EffectiveOption> = Vec::new();
+    for block in load_order(hosts) {
+        let host = &hosts[block];
+        if host.is_separator
+            || !host.name.split_whitespace().any(is_wildcard)
+            || !pattern_matches(&host.name, alias)
+        {
+            continue;
+        }
+        for (key, value) in host_options(host) {
+            if !inherited.iter().any(|o| o.key == key) {
+                inherited.push(EffectiveOption { key, value, source: block });
+            }
+        }
+    }
+    inherited
+}
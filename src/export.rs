@@ -0,0 +1,172 @@
+use std::fs;
+use std::path::Path;
+use serde::Serialize;
+
+use crate::audit::{Finding, Severity};
+use crate::forward::ForwardSpec;
+use crate::state::{self, CheckResult};
+
+// Uma linha da matriz de alcançabilidade
+#[derive(Debug, Serialize)]
+pub struct ReachabilityRow {
+    pub host: String,
+    pub address: String,
+    pub port: u16,
+    pub status: String,
+    pub latency_ms: Option<u64>,
+    pub timestamp: String,
+}
+
+impl ReachabilityRow {
+    pub fn new(host: &str, address: &str, port: u16, result: &CheckResult) -> Self {
+        Self {
+            host: host.to_string(),
+            address: address.to_string(),
+            port,
+            status: if result.up { "up" } else { "down" }.to_string(),
+            latency_ms: result.latency_ms.filter(|_| result.up),
+            timestamp: state::format_timestamp(result.checked_at),
+        }
+    }
+}
+
+// O formato sai da extensão do arquivo: .json, senão CSV
+pub fn write_reachability(path: &Path, rows: &[ReachabilityRow]) -> Result<(), Box<dyn std::error::Error>> {
+    let is_json = path.extension().is_some_and(|e| e.eq_ignore_ascii_case("json"));
+    let content = if is_json {
+        serde_json::to_string_pretty(rows)?
+    } else {
+        let mut csv = String::from("host,address,port,status,latency_ms,timestamp\n");
+        for row in rows {
+            let fields = [
+                csv_field(&row.host),
+                csv_field(&row.address),
+                row.port.to_string(),
+                row.status.clone(),
+                row.latency_ms.map(|l| l.to_string()).unwrap_or_default(),
+                row.timestamp.clone(),
+            ];
+            csv.push_str(&fields.join(","));
+            csv.push('\n');
+        }
+        csv
+    };
+    fs::write(path, content)?;
+    Ok(())
+}
+
+// Achados de um host no relatório de auditoria
+#[derive(Debug, Serialize)]
+pub struct AuditHost {
+    pub host: String,
+    pub location: String,
+    pub findings: Vec<Finding>,
+}
+
+#[derive(Debug, Serialize)]
+struct AuditReport<'a> {
+    generated_at: String,
+    hosts: &'a [AuditHost],
+}
+
+// Relatório para revisão de acessos: .json, senão Markdown
+pub fn write_audit(path: &Path, hosts: &[AuditHost]) -> Result<(), Box<dyn std::error::Error>> {
+    let generated_at = state::format_timestamp(state::now());
+    let is_json = path.extension().is_some_and(|e| e.eq_ignore_ascii_case("json"));
+    let content = if is_json {
+        serde_json::to_string_pretty(&AuditReport { generated_at, hosts })?
+    } else {
+        let count = |severity: Severity| hosts.iter().flat_map(|h| &h.findings).filter(|f| f.severity == severity).count();
+        let mut md = format!(
+            "# Auditoria de segurança SSH\n\nGerado em {}: {} hosts com achados ({} high, {} medium, {} low).\n",
+            generated_at, hosts.len(), count(Severity::High), count(Severity::Medium), count(Severity::Low)
+        );
+        for host in hosts {
+            md.push_str(&format!("\n## {}\n\nDefinido em `{}`.\n\n", host.host, host.location));
+            for finding in &host.findings {
+                let origin = if finding.block == host.host { String::new() } else { format!(" (via `Host {}`)", finding.block) };
+                md.push_str(&format!(
+                    "- **[{}] {}**: `{} {}`{}\n  - {}\n  - Recomendação: {}\n",
+                    finding.severity.label().to_uppercase(), finding.title, finding.key, finding.value, origin,
+                    finding.explanation, finding.recommendation
+                ));
+            }
+        }
+        md
+    };
+    fs::write(path, content)?;
+    Ok(())
+}
+
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+// Argumentos do ssh equivalentes às opções efetivas do host, sem depender do ssh_config
+pub fn ssh_args(alias: &str, options: &[(String, String)]) -> Vec<String> {
+    let get = |key: &str| options.iter().find(|(k, _)| k == key).map(|(_, v)| v.as_str());
+    let mut args = vec!["ssh".to_string()];
+    if let Some(port) = get("port").filter(|p| *p != "22") {
+        args.extend(["-p".to_string(), port.to_string()]);
+    }
+    for (_, identity_file) in options.iter().filter(|(k, _)| k == "identityfile") {
+        args.extend(["-i".to_string(), identity_file.to_string()]);
+    }
+    if let Some(proxy_jump) = get("proxyjump").filter(|p| !p.eq_ignore_ascii_case("none")) {
+        args.extend(["-J".to_string(), proxy_jump.to_string()]);
+    }
+    for (_, spec) in options.iter().filter(|(k, _)| k == "localforward") {
+        if let Some(forward) = ForwardSpec::parse(spec) {
+            args.extend(["-L".to_string(), forward.to_l_arg()]);
+        }
+    }
+    let hostname = get("hostname").unwrap_or(alias);
+    match get("user") {
+        Some(user) => args.push(format!("{}@{}", user, hostname)),
+        None => args.push(hostname.to_string()),
+    }
+    args
+}
+
+// Nome válido para alias/função de shell
+pub fn shell_name(alias: &str) -> String {
+    alias.chars().map(|c| if c.is_ascii_alphanumeric() || c == '_' || c == '-' { c } else { '_' }).collect()
+}
+
+pub fn shell_quote(arg: &str) -> String {
+    let safe = !arg.is_empty()
+        && arg.chars().all(|c| c.is_ascii_alphanumeric() || "-_./:@=,~+".contains(c));
+    if safe {
+        arg.to_string()
+    } else {
+        format!("'{}'", arg.replace('\'', "'\\''"))
+    }
+}
+
+pub fn alias_line(name: &str, args: &[String]) -> String {
+    let command: Vec<String> = args.iter().map(|a| shell_quote(a)).collect();
+    format!("alias {}={}", shell_name(name), shell_quote(&command.join(" ")))
+}
+
+pub fn function_block(name: &str, args: &[String]) -> String {
+    let command: Vec<String> = args.iter().map(|a| shell_quote(a)).collect();
+    format!("{}() {{ {} \"$@\"; }}", shell_name(name), command.join(" "))
+}
+
+// Arquivo para `source` com um alias por host
+pub fn write_aliases(path: &Path, hosts: &[(String, Vec<String>)]) -> Result<(), Box<dyn std::error::Error>> {
+    let mut content = String::from("# Gerado pelo lazysshrs\n");
+    for (name, args) in hosts {
+        content.push_str(&alias_line(name, args));
+        content.push('\n');
+    }
+    if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(path, content)?;
+    Ok(())
+}
@@ -0,0 +1,56 @@
+use std::fs;
+use std::path::Path;
+use serde::{Deserialize, Serialize};
+
+use crate::ssh_config::SshConfig;
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FolderDefaults {
+    pub user: Option<String>,
+    pub port: Option<u16>,
+    pub identity_file: Option<String>,
+    pub proxy_jump: Option<String>,
+}
+
+// Metadados de uma pasta de include, em `<workdir>/<pasta>/lazysshrs.toml`
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FolderMeta {
+    // Título do separador da pasta na lista, no lugar do nome do diretório
+    pub title: Option<String>,
+    pub defaults: FolderDefaults,
+}
+
+impl FolderMeta {
+    pub fn load(workdir: &Path, folder: &str) -> Self {
+        Self::load_dir(&workdir.join(folder))
+    }
+
+    // Includes fora do workdir também podem ter o seu `lazysshrs.toml`
+    pub fn load_dir(dir: &Path) -> Self {
+        fs::read_to_string(dir.join("lazysshrs.toml"))
+            .ok()
+            .and_then(|content| toml::from_str(&content).ok())
+            .unwrap_or_default()
+    }
+}
+
+// Títulos (`title` de cada pasta) e ordem (`section_order`) das seções de include
+pub fn arrange_sections(config: &mut SshConfig, order: &[String]) {
+    for separator in config.hosts.iter_mut().filter(|h| h.is_separator) {
+        let title = separator.source_file.as_deref()
+            .and_then(Path::parent)
+            .and_then(|dir| FolderMeta::load_dir(dir).title)
+            .filter(|t| !t.trim().is_empty());
+        // Arquivos soltos da pasta (`config.d/work`) mantêm o nome depois do título
+        let file_name = separator.source_file.as_deref().and_then(Path::file_name).and_then(|n| n.to_str()).unwrap_or("config");
+        match title {
+            Some(title) if file_name == "config" => separator.name = format!("── {} ──", title.trim()),
+            Some(title) => separator.name = format!("── {}/{} ──", title.trim(), file_name),
+            None => {}
+        }
+    }
+    config.arrange_sections(order);
+    config.gather_defaults();
+}
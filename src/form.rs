@@ -1,4 +1,15 @@
-#[derive(Debug, Clone)]
+use std::collections::HashMap;
+
+use crate::config::HostTemplate;
+use crate::folder::FolderDefaults;
+use crate::forward::{self, ForwardSpec};
+
+pub const FIELD_COUNT: usize = 13;
+
+const YES_NO: &[&str] = &["yes", "no"];
+const STRICT_HOST_KEY_CHECKING: &[&str] = &["yes", "accept-new", "no", "ask", "off"];
+
+#[derive(Debug, Clone, Default)]
 pub struct HostForm {
     pub folder: String,
     pub host: String,
@@ -6,28 +17,71 @@ pub struct HostForm {
     pub user: String,
     pub port: String,
     pub identity_file: String,
-    pub local_forward: String,
+    // Bastião(ões) para o `ProxyJump`, como `bastion` ou `a,b`
+    pub proxy_jump: String,
+    pub local_forwards: Vec<ForwardSpec>,
+    // Entrada de LocalForward em edição no campo 7 e sua parte (porta local, host, porta remota)
+    pub forward_index: usize,
+    pub forward_subfield: usize,
+    pub forward_agent: String,
+    pub compression: String,
+    pub strict_host_key_checking: String,
+    // Gravada como comentários `# ...` acima do `Host`; uma linha por comentário
+    pub description: String,
+    // Comando rodado depois do login (`ssh -t host '<cmd>'`); vai para os metadados, não para o ssh_config
+    pub post_login: String,
+    // Opções adicionais gravadas junto com o host (ex.: ProxyJump da pasta)
+    pub extra_options: Vec<(String, String)>,
+    // Valores herdados de blocos com curinga, por índice de campo
+    pub inherited: HashMap<usize, String>,
     pub current_field: usize,
 }
 
-impl Default for HostForm {
-    fn default() -> Self {
-        Self {
-            folder: String::new(),
-            host: String::new(),
-            hostname: String::new(),
-            user: String::new(),
-            port: String::new(),
-            identity_file: String::new(),
-            local_forward: String::new(),
-            current_field: 0,
+impl HostForm {
+    pub fn field_names() -> Vec<&'static str> {
+        vec![
+            "Pasta", "Host", "Hostname", "User", "Port", "IdentityFile", "ProxyJump", "LocalForward",
+            "ForwardAgent", "Compression", "StrictHostKeyChecking", "Description", "PostLogin",
+        ]
+    }
+
+    // Nome da diretiva no ssh_config para os campos que não são texto livre
+    pub fn toggle_key(index: usize) -> Option<&'static str> {
+        match index {
+            8 => Some("forwardagent"),
+            9 => Some("compression"),
+            10 => Some("stricthostkeychecking"),
+            _ => None,
         }
     }
-}
 
-impl HostForm {
-    pub fn field_names() -> Vec<&'static str> {
-        vec!["Pasta", "Host", "Hostname", "User", "Port", "IdentityFile", "LocalForward"]
+    // Valores válidos dos campos alternáveis; vazio significa "não definido"
+    pub fn toggle_options(index: usize) -> Option<&'static [&'static str]> {
+        match index {
+            8 | 9 => Some(YES_NO),
+            10 => Some(STRICT_HOST_KEY_CHECKING),
+            _ => None,
+        }
+    }
+
+    pub fn is_toggle(index: usize) -> bool {
+        Self::toggle_options(index).is_some()
+    }
+
+    pub fn cycle_field(&mut self, index: usize, forward: bool) {
+        let Some(options) = Self::toggle_options(index) else {
+            return;
+        };
+        // Ciclo: (vazio) -> opções... -> (vazio)
+        let position = options.iter().position(|o| *o == self.get_field(index));
+        let next = match (position, forward) {
+            (None, true) => Some(0),
+            (None, false) => Some(options.len() - 1),
+            (Some(i), true) if i + 1 < options.len() => Some(i + 1),
+            (Some(i), false) if i > 0 => Some(i - 1),
+            _ => None,
+        };
+        self.set_field(index, next.map(|i| options[i].to_string()).unwrap_or_default());
     }
 
     pub fn get_field(&self, index: usize) -> &str {
@@ -38,7 +92,13 @@ impl HostForm {
             3 => &self.user,
             4 => &self.port,
             5 => &self.identity_file,
-            6 => &self.local_forward,
+            6 => &self.proxy_jump,
+            7 => self.local_forwards.get(self.forward_index).map(|f| f.field(self.forward_subfield)).unwrap_or(""),
+            8 => &self.forward_agent,
+            9 => &self.compression,
+            10 => &self.strict_host_key_checking,
+            11 => &self.description,
+            12 => &self.post_login,
             _ => "",
         }
     }
@@ -51,20 +111,166 @@ impl HostForm {
             3 => self.user = value,
             4 => self.port = value,
             5 => self.identity_file = value,
-            6 => self.local_forward = value,
+            6 => self.proxy_jump = value,
+            7 => {
+                if self.forward_index >= self.local_forwards.len() {
+                    self.add_forward();
+                }
+                *self.local_forwards[self.forward_index].field_mut(self.forward_subfield) = value;
+            }
+            8 => self.forward_agent = value,
+            9 => self.compression = value,
+            10 => self.strict_host_key_checking = value,
+            11 => self.description = value,
+            12 => self.post_login = value,
             _ => {}
         }
     }
 
+    // Preenche apenas os campos ainda vazios
+    pub fn apply_defaults(&mut self, defaults: &FolderDefaults) {
+        if self.user.is_empty() {
+            if let Some(user) = &defaults.user {
+                self.user = user.clone();
+            }
+        }
+        if self.port.is_empty() {
+            if let Some(port) = defaults.port {
+                self.port = port.to_string();
+            }
+        }
+        if self.identity_file.is_empty() {
+            if let Some(identity_file) = &defaults.identity_file {
+                self.identity_file = identity_file.clone();
+            }
+        }
+        if self.proxy_jump.is_empty() {
+            if let Some(proxy_jump) = &defaults.proxy_jump {
+                self.proxy_jump = proxy_jump.clone();
+            }
+        }
+    }
+
+    // Template sobrescreve os campos que define; Pasta e Host são mantidos
+    pub fn apply_template(&mut self, template: &HostTemplate) {
+        if let Some(hostname) = &template.hostname {
+            self.hostname = hostname.clone();
+        }
+        if let Some(user) = &template.user {
+            self.user = user.clone();
+        }
+        if let Some(port) = template.port {
+            self.port = port.to_string();
+        }
+        if let Some(identity_file) = &template.identity_file {
+            self.identity_file = identity_file.clone();
+        }
+        for local_forward in &template.local_forwards {
+            self.add_forward_spec(local_forward);
+        }
+        for (key, value) in &template.options {
+            if key.eq_ignore_ascii_case("proxyjump") {
+                self.proxy_jump = value.clone();
+            } else {
+                self.set_extra_option(key, value, true);
+            }
+        }
+    }
+
+    pub fn set_extra_option(&mut self, key: &str, value: &str, overwrite: bool) {
+        match self.extra_options.iter_mut().find(|(k, _)| k.eq_ignore_ascii_case(key)) {
+            Some(option) if overwrite => option.1 = value.to_string(),
+            Some(_) => {}
+            None => self.extra_options.push((key.to_string(), value.to_string())),
+        }
+    }
+
+    pub fn field_index(key: &str) -> Option<usize> {
+        match key {
+            "hostname" => Some(2),
+            "user" => Some(3),
+            "port" => Some(4),
+            "identityfile" => Some(5),
+            "proxyjump" => Some(6),
+            _ => None,
+        }
+    }
+
+    fn is_filled(&self, index: usize) -> bool {
+        !self.get_field(index).is_empty() || self.inherited.contains_key(&index)
+    }
+
+    // Especificações que o editor não entende são mantidas como opção extra
+    pub fn add_forward_spec(&mut self, spec: &str) {
+        match ForwardSpec::parse(spec) {
+            Some(forward) => {
+                if !self.local_forwards.contains(&forward) {
+                    self.local_forwards.retain(|f| !f.is_empty());
+                    self.local_forwards.push(forward);
+                }
+            }
+            None => self.extra_options.push(("LocalForward".to_string(), spec.to_string())),
+        }
+    }
+
+    pub fn add_forward(&mut self) {
+        self.local_forwards.push(ForwardSpec::default());
+        self.forward_index = self.local_forwards.len() - 1;
+        self.forward_subfield = 0;
+    }
+
+    pub fn remove_forward(&mut self) {
+        if self.forward_index < self.local_forwards.len() {
+            self.local_forwards.remove(self.forward_index);
+        }
+        self.forward_index = self.forward_index.min(self.local_forwards.len().saturating_sub(1));
+    }
+
+    pub fn select_forward(&mut self, forward: bool) {
+        let len = self.local_forwards.len();
+        if len > 0 {
+            self.forward_index = if forward { (self.forward_index + 1) % len } else { (self.forward_index + len - 1) % len };
+        }
+    }
+
+    pub fn forward_errors(&self) -> Vec<String> {
+        let mut errors = Vec::new();
+        for (i, forward) in self.local_forwards.iter().enumerate() {
+            for error in forward.errors() {
+                errors.push(format!("LocalForward {}: {}", i + 1, error));
+            }
+        }
+        errors.extend(forward::overlapping(&self.local_forwards));
+        errors
+    }
+
     pub fn is_valid(&self) -> bool {
-        !self.folder.is_empty() && !self.host.is_empty() && !self.hostname.is_empty() && !self.user.is_empty()
+        let toggles_valid = (0..FIELD_COUNT).all(|i| match Self::toggle_options(i) {
+            Some(options) => self.get_field(i).is_empty() || options.contains(&self.get_field(i)),
+            None => true,
+        });
+        !self.folder.is_empty() && !self.host.is_empty() && self.is_filled(2) && self.is_filled(3) && toggles_valid
+            && self.forward_errors().is_empty()
     }
 
+    // No campo LocalForward, Tab percorre as partes do encaminhamento antes de avançar
     pub fn next_field(&mut self) {
-        self.current_field = (self.current_field + 1) % 7;
+        if self.current_field == 7 && self.forward_subfield < 2 {
+            self.forward_subfield += 1;
+            return;
+        }
+        self.forward_subfield = 0;
+        self.current_field = (self.current_field + 1) % FIELD_COUNT;
     }
 
     pub fn prev_field(&mut self) {
-        self.current_field = if self.current_field == 0 { 6 } else { self.current_field - 1 };
+        if self.current_field == 7 && self.forward_subfield > 0 {
+            self.forward_subfield -= 1;
+            return;
+        }
+        self.current_field = if self.current_field == 0 { FIELD_COUNT - 1 } else { self.current_field - 1 };
+        if self.current_field == 7 {
+            self.forward_subfield = 2;
+        }
     }
 }
\ No newline at end of file
@@ -0,0 +1,143 @@
+// Especificação de LocalForward: `[bind:]LPORT RHOST:RPORT`
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ForwardSpec {
+    pub local: String,
+    pub remote_host: String,
+    pub remote_port: String,
+}
+
+impl ForwardSpec {
+    pub fn parse(spec: &str) -> Option<Self> {
+        let mut parts = spec.split_whitespace();
+        let local = parts.next()?;
+        let remote = parts.next()?;
+        if parts.next().is_some() {
+            return None;
+        }
+        let (remote_host, remote_port) = split_host_port(remote)?;
+        let forward = Self {
+            local: local.to_string(),
+            remote_host: remote_host.to_string(),
+            remote_port: remote_port.to_string(),
+        };
+        forward.errors().is_empty().then_some(forward)
+    }
+
+    // Aceita `8080 host:80`, `8080 → host:80` ou a sintaxe do -L (`8080:host:80`)
+    pub fn parse_flexible(input: &str) -> Option<Self> {
+        let input = input.replace('→', " ");
+        if let Some(forward) = Self::parse(&input) {
+            return Some(forward);
+        }
+        let (rest, remote_port) = input.trim().rsplit_once(':')?;
+        let (local, remote_host) = rest.rsplit_once(':')?;
+        Self::parse(&format!("{} {}:{}", local, remote_host, remote_port))
+    }
+
+    // Argumento para `ssh -L`: [bind:]LPORT:RHOST:RPORT
+    pub fn to_l_arg(&self) -> String {
+        let host = self.remote_host.trim();
+        if host.contains(':') {
+            format!("{}:[{}]:{}", self.local.trim(), host, self.remote_port.trim())
+        } else {
+            format!("{}:{}:{}", self.local.trim(), host, self.remote_port.trim())
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.local.trim().is_empty() && self.remote_host.trim().is_empty() && self.remote_port.trim().is_empty()
+    }
+
+    pub fn to_spec(&self) -> String {
+        let host = self.remote_host.trim();
+        if host.contains(':') {
+            format!("{} [{}]:{}", self.local.trim(), host, self.remote_port.trim())
+        } else {
+            format!("{} {}:{}", self.local.trim(), host, self.remote_port.trim())
+        }
+    }
+
+    pub fn local_port(&self) -> Option<u16> {
+        parse_port(self.local.trim().rsplit(':').next()?)
+    }
+
+    // Portas remotas que costumam servir HTTP(S)
+    pub fn url(&self) -> Option<String> {
+        let local_port = self.local_port()?;
+        match parse_port(self.remote_port.trim())? {
+            443 | 8443 => Some(format!("https://localhost:{}", local_port)),
+            80 | 3000 | 5000 | 8000 | 8080 | 8081 | 8888 | 9000 | 9090 => Some(format!("http://localhost:{}", local_port)),
+            _ => None,
+        }
+    }
+
+    pub fn field(&self, index: usize) -> &str {
+        match index {
+            0 => &self.local,
+            1 => &self.remote_host,
+            _ => &self.remote_port,
+        }
+    }
+
+    pub fn field_mut(&mut self, index: usize) -> &mut String {
+        match index {
+            0 => &mut self.local,
+            1 => &mut self.remote_host,
+            _ => &mut self.remote_port,
+        }
+    }
+
+    pub fn errors(&self) -> Vec<String> {
+        let mut errors = Vec::new();
+        if self.is_empty() {
+            return errors;
+        }
+
+        let remote_host = self.remote_host.trim();
+        // Porta no lugar do host e host no lugar da porta local
+        if parse_port(remote_host).is_some() && self.local_port().is_none() && !self.local.trim().is_empty() {
+            errors.push("Parece invertido: informe a porta local primeiro e depois host:porta remota".to_string());
+            return errors;
+        }
+        if self.local_port().is_none() {
+            errors.push(format!("Porta local inválida: '{}'", self.local.trim()));
+        }
+        if remote_host.is_empty() || remote_host.contains(char::is_whitespace) {
+            errors.push("Host remoto inválido".to_string());
+        }
+        if parse_port(self.remote_port.trim()).is_none() {
+            errors.push(format!("Porta remota inválida: '{}'", self.remote_port.trim()));
+        }
+        errors
+    }
+}
+
+fn parse_port(value: &str) -> Option<u16> {
+    value.parse::<u16>().ok().filter(|p| *p > 0)
+}
+
+fn split_host_port(remote: &str) -> Option<(&str, &str)> {
+    // Endereços IPv6 vêm entre colchetes: [::1]:80
+    if let Some(rest) = remote.strip_prefix('[') {
+        let (host, port) = rest.split_once("]:")?;
+        return Some((host, port));
+    }
+    remote.rsplit_once(':')
+}
+
+// Portas locais repetidas entre os encaminhamentos de um mesmo host
+pub fn overlapping(forwards: &[ForwardSpec]) -> Vec<String> {
+    let mut seen: Vec<(String, u16)> = Vec::new();
+    let mut errors = Vec::new();
+    for forward in forwards {
+        let Some(port) = forward.local_port() else {
+            continue;
+        };
+        let bind = forward.local.trim().rsplit_once(':').map(|(b, _)| b.to_string()).unwrap_or_default();
+        if seen.iter().any(|(b, p)| *p == port && (b == &bind || b.is_empty() || bind.is_empty())) {
+            errors.push(format!("Porta local {} usada mais de uma vez", port));
+        }
+        seen.push((bind, port));
+    }
+    errors
+}
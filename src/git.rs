@@ -0,0 +1,180 @@
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output, Stdio};
+
+use crate::diff::FileDiff;
+
+// Estado do repositório git do workdir, mostrado na barra de status
+#[derive(Debug, Clone)]
+pub struct GitStatus {
+    pub branch: String,
+    pub changed: usize,
+}
+
+impl GitStatus {
+    pub fn is_clean(&self) -> bool {
+        self.changed == 0
+    }
+}
+
+fn git(dir: &Path, args: &[&str]) -> std::io::Result<Output> {
+    Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .args(args)
+        .stdin(Stdio::null())
+        .output()
+}
+
+fn run(dir: &Path, args: &[&str]) -> Result<Output, Box<dyn std::error::Error>> {
+    let output = git(dir, args)?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let detail = stderr.lines().find(|l| !l.trim().is_empty()).unwrap_or("erro desconhecido");
+        return Err(format!("git {}: {}", args[0], detail.trim()).into());
+    }
+    Ok(output)
+}
+
+// None quando o workdir não é um repositório (ou o git não está instalado)
+pub fn status(dir: &Path) -> Option<GitStatus> {
+    let output = git(dir, &["status", "--porcelain", "--branch"]).ok().filter(|o| o.status.success())?;
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let mut lines = stdout.lines();
+    // "## main...origin/main [ahead 1]" ou "## No commits yet on main"
+    let header = lines.next().unwrap_or_default().trim_start_matches("## ");
+    let branch = header.strip_prefix("No commits yet on ").unwrap_or(header);
+    let branch = branch.split("...").next().unwrap_or(branch).split(' ').next().unwrap_or_default();
+    Some(GitStatus { branch: branch.to_string(), changed: lines.count() })
+}
+
+// Adiciona e commita só os arquivos informados; devolve false se nada mudou
+pub fn commit(dir: &Path, paths: &[PathBuf], message: &str) -> Result<bool, Box<dyn std::error::Error>> {
+    // Arquivos removidos entram apenas se o git os conhecia
+    let mut paths: Vec<String> = paths.iter()
+        .filter(|p| p.starts_with(dir) && (p.exists() || is_tracked(dir, p)))
+        .map(|p| p.to_string_lossy().to_string())
+        .collect();
+    paths.sort();
+    paths.dedup();
+    if paths.is_empty() {
+        return Ok(false);
+    }
+    let with_paths = |args: &[&str]| -> Vec<String> {
+        args.iter().map(|a| a.to_string()).chain(["--".to_string()]).chain(paths.iter().cloned()).collect()
+    };
+
+    let add = with_paths(&["add"]);
+    run(dir, &add.iter().map(String::as_str).collect::<Vec<_>>())?;
+    let diff = with_paths(&["diff", "--cached", "--quiet"]);
+    if git(dir, &diff.iter().map(String::as_str).collect::<Vec<_>>())?.status.success() {
+        return Ok(false);
+    }
+    // Commita apenas esses caminhos, sem levar o que o usuário já tinha no índice
+    let commit = with_paths(&["commit", "--quiet", "-m", message]);
+    run(dir, &commit.iter().map(String::as_str).collect::<Vec<_>>())?;
+    Ok(true)
+}
+
+fn is_tracked(dir: &Path, path: &Path) -> bool {
+    git(dir, &["ls-files", "--error-unmatch", "--", &path.to_string_lossy()]).is_ok_and(|o| o.status.success())
+}
+
+pub enum PullOutcome {
+    UpToDate,
+    Updated,
+    // Merge em andamento; arquivos que conflitaram
+    Conflict(Vec<String>),
+}
+
+// Com o terminal liberado, para o git poder pedir senha ou passphrase
+fn interactive(dir: &Path, args: &[&str]) -> Result<bool, Box<dyn std::error::Error>> {
+    let status = Command::new("git").arg("-C").arg(dir).args(args).status()?;
+    Ok(status.success())
+}
+
+fn head(dir: &Path) -> Option<String> {
+    let output = git(dir, &["rev-parse", "HEAD"]).ok().filter(|o| o.status.success())?;
+    Some(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+fn require_remote(dir: &Path) -> Result<(), Box<dyn std::error::Error>> {
+    let output = run(dir, &["remote"])?;
+    if output.stdout.iter().all(u8::is_ascii_whitespace) {
+        return Err("o repositório não tem remoto configurado".into());
+    }
+    Ok(())
+}
+
+pub fn unmerged_files(dir: &Path) -> Vec<String> {
+    git(dir, &["diff", "--name-only", "--diff-filter=U"])
+        .map(|o| String::from_utf8_lossy(&o.stdout).lines().map(str::to_string).collect())
+        .unwrap_or_default()
+}
+
+pub fn pull(dir: &Path) -> Result<PullOutcome, Box<dyn std::error::Error>> {
+    require_remote(dir)?;
+    let before = head(dir);
+    if interactive(dir, &["pull", "--no-rebase", "--no-edit"])? {
+        return Ok(if head(dir) == before { PullOutcome::UpToDate } else { PullOutcome::Updated });
+    }
+    let conflicts = unmerged_files(dir);
+    if conflicts.is_empty() {
+        return Err("git pull falhou; veja a saída acima ou rode-o no terminal".into());
+    }
+    // O merge fica em andamento até o usuário resolver ou desistir
+    Ok(PullOutcome::Conflict(conflicts))
+}
+
+pub fn push(dir: &Path) -> Result<(), Box<dyn std::error::Error>> {
+    require_remote(dir)?;
+    if !interactive(dir, &["push"])? {
+        return Err("git push falhou; se o remoto tem commits novos, faça o pull (U) antes".into());
+    }
+    Ok(())
+}
+
+// Busca o remoto e devolve os arquivos que o push mudaria
+pub fn outgoing(dir: &Path) -> Result<Vec<FileDiff>, Box<dyn std::error::Error>> {
+    require_remote(dir)?;
+    run(dir, &["rev-parse", "--abbrev-ref", "@{u}"]).map_err(|_| "a branch atual não tem upstream; rode `git push -u` uma vez no terminal")?;
+    if !interactive(dir, &["fetch", "--quiet"])? {
+        return Err("git fetch falhou; veja a saída acima ou rode-o no terminal".into());
+    }
+    let names = run(dir, &["diff", "--name-only", "@{u}", "HEAD"])?;
+    let show = |rev: &str, path: &str| {
+        git(dir, &["show", &format!("{}:{}", rev, path)])
+            .ok()
+            .filter(|o| o.status.success())
+            .map(|o| String::from_utf8_lossy(&o.stdout).to_string())
+    };
+    Ok(String::from_utf8_lossy(&names.stdout)
+        .lines()
+        .map(|path| FileDiff {
+            path: PathBuf::from(path),
+            remote: show("@{u}", path),
+            local: show("HEAD", path),
+            excluded: false,
+        })
+        .collect())
+}
+
+// Versão de um arquivo em conflito: 1 = base, 2 = local, 3 = remota
+pub fn stage(dir: &Path, number: u8, path: &str) -> Option<String> {
+    let output = git(dir, &["show", &format!(":{}:{}", number, path)]).ok().filter(|o| o.status.success())?;
+    Some(String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+// Não deixa marcadores de conflito nos arquivos que o ssh lê
+pub fn abort_merge(dir: &Path) -> Result<(), Box<dyn std::error::Error>> {
+    run(dir, &["merge", "--abort"])?;
+    Ok(())
+}
+
+// Grava a resolução e conclui o merge com a mensagem padrão do git
+pub fn conclude_merge(dir: &Path, paths: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+    let mut add = vec!["add", "--"];
+    add.extend(paths.iter().map(String::as_str));
+    run(dir, &add)?;
+    run(dir, &["commit", "--no-edit", "--quiet"])?;
+    Ok(())
+}
@@ -0,0 +1,230 @@
+// Atalhos de cada tela, para a ajuda (`?` ou F1); cada par é (teclas, ação)
+pub struct HelpSection {
+    pub title: &'static str,
+    pub keys: &'static [(&'static str, &'static str)],
+}
+
+pub const LIST: usize = 0;
+pub const TABLE: usize = 1;
+pub const QUICK_ACTIONS: usize = 2;
+pub const SEARCH: usize = 3;
+pub const FORM: usize = 4;
+pub const INCLUDES: usize = 5;
+pub const TUNNELS: usize = 6;
+pub const JUMP_CHAIN: usize = 7;
+pub const TOPOLOGY: usize = 8;
+pub const HISTORY: usize = 9;
+pub const TRASH: usize = 10;
+pub const AUDIT: usize = 11;
+pub const KEY_MAP: usize = 12;
+pub const ROTATION: usize = 13;
+pub const SYNC_DIFF: usize = 14;
+pub const CONFLICTS: usize = 15;
+pub const SMART_GROUPS: usize = 16;
+
+pub const SECTIONS: &[HelpSection] = &[
+    HelpSection {
+        title: "Host list",
+        keys: &[
+            ("↑/↓", "Move between hosts and section separators"),
+            ("[ / ]  PgUp/PgDn", "Jump to the previous/next section"),
+            ("Enter", "Connect (on a separator: fold/unfold the section)"),
+            ("← / →", "Fold the current section / unfold the selected one"),
+            ("Space", "Quick actions menu (on a separator: fold/unfold)"),
+            ("1-9", "Connect to the host with that index"),
+            ("/", "Fuzzy search"),
+            ("s", "Toggle folder order / most recently used first"),
+            ("L", "Toggle list / table view"),
+            ("v", "Toggle details / raw block from the file"),
+            ("H", "Toggle privacy mode"),
+            ("a / e / d", "Add / edit / delete host (deleted hosts go to the trash)"),
+            ("o", "Open the host's file in $EDITOR"),
+            ("O", "Connect with extra ssh arguments"),
+            ("E", "Open a remote directory in VSCode/Neovim"),
+            ("p / P", "Check this host / check all hosts in the background"),
+            ("m", "Cycle the check method (global, tcp, icmp, both)"),
+            ("c", "Set the health-check command"),
+            ("C", "Set a console command instead of ssh (telnet, serial)"),
+            ("g", "Open the graphical session (RDP/VNC)"),
+            ("h", "Connection history and recorded sessions"),
+            ("k", "Switch StrictHostKeyChecking no to accept-new"),
+            ("K", "Key map and key rotation"),
+            ("V", "Security audit"),
+            ("f", "Open an ad-hoc tunnel"),
+            ("t", "Active tunnels"),
+            ("b", "Open a forwarded HTTP(S) port in the browser"),
+            ("J", "Build a jump chain"),
+            ("G", "Bastion topology"),
+            ("I", "Includes and folders"),
+            ("F", "Smart groups (saved searches)"),
+            ("D", "Trash"),
+            ("N / T / M", "Edit notes / tags / MAC address"),
+            ("Q", "Share as QR code"),
+            ("y / Y", "Copy the block / the effective options as a snippet"),
+            ("i", "Import a host from the clipboard (an existing name opens the conflict chooser)"),
+            ("A / W", "Shell alias for the host / write aliases.sh"),
+            ("X", "Export check results"),
+            ("R", "Sync the workdir to the host's ~/.ssh"),
+            ("U / S", "git pull / git push the workdir"),
+            ("Mouse", "Wheel: move, click: select, double-click: connect"),
+            ("Esc", "Cancel the running connectivity sweep"),
+            ("? / F1", "This help"),
+            ("q", "Quit"),
+        ],
+    },
+    HelpSection {
+        title: "Table view",
+        keys: &[
+            ("↑/↓", "Move between hosts"),
+            ("< / >", "Sort by the previous/next column"),
+            ("!", "Reverse the sort order"),
+            ("L", "Back to the list"),
+        ],
+    },
+    HelpSection {
+        title: "Quick actions",
+        keys: &[
+            ("c / o / a", "Connect / with options / recording with asciinema"),
+            ("s / r", "SFTP / run a command"),
+            ("p / e / u / d", "Check / edit / duplicate / delete"),
+            ("y", "Copy the ssh command"),
+            ("↑/↓ Enter", "Pick from the menu"),
+            ("Esc", "Close"),
+        ],
+    },
+    HelpSection {
+        title: "Search",
+        keys: &[
+            ("type", "Filter by name, address, user, options or comments"),
+            ("user: port: tag:", "Filter operators (also folder: and host:)"),
+            ("↑/↓", "Move between results"),
+            ("Enter", "Select the host (without results: the first suggestion)"),
+            ("Tab", "Use the first suggestion"),
+            ("Alt+1-9", "Connect to one of the first nine results"),
+            ("Ctrl+S", "Save the query as a smart group"),
+            ("Esc", "Cancel"),
+        ],
+    },
+    HelpSection {
+        title: "Host form",
+        keys: &[
+            ("Tab / Shift+Tab", "Next / previous field"),
+            ("Ctrl+T", "Apply a template"),
+            ("↓ on IdentityFile", "Pick a key from ~/.ssh"),
+            ("↑/↓ Ctrl+N Ctrl+D", "LocalForward: select / add / remove"),
+            ("Space / ← →", "Cycle ForwardAgent, Compression, StrictHostKeyChecking"),
+            ("Enter", "Confirm"),
+            ("Esc", "Cancel"),
+        ],
+    },
+    HelpSection {
+        title: "Includes",
+        keys: &[
+            ("↑/↓", "Move between files"),
+            ("Enter", "Go to the file's hosts"),
+            ("n", "New folder"),
+            ("r", "Rename the folder/file"),
+            ("m", "Merge: mark the source, then m on the target"),
+            ("Esc", "Back"),
+        ],
+    },
+    HelpSection {
+        title: "Tunnels",
+        keys: &[
+            ("↑/↓", "Move between tunnels"),
+            ("s", "Make persistent (systemd)"),
+            ("x", "Stop the tunnel"),
+            ("Esc", "Back"),
+        ],
+    },
+    HelpSection {
+        title: "Jump chain",
+        keys: &[
+            ("↑/↓ Enter", "Add a hop"),
+            ("Backspace", "Remove the last hop"),
+            ("c", "Connect through the chain"),
+            ("s", "Save as ProxyJump"),
+            ("Esc", "Back"),
+        ],
+    },
+    HelpSection {
+        title: "Topology",
+        keys: &[
+            ("↑/↓", "Move between hosts"),
+            ("Enter", "Go to the host"),
+            ("Esc", "Back"),
+        ],
+    },
+    HelpSection {
+        title: "History",
+        keys: &[
+            ("↑/↓", "Move between recordings"),
+            ("Enter / p", "Play the recording"),
+            ("x", "Delete the recording"),
+            ("Esc", "Back"),
+        ],
+    },
+    HelpSection {
+        title: "Trash",
+        keys: &[
+            ("↑/↓", "Move between deleted hosts"),
+            ("Enter / r", "Restore"),
+            ("x", "Delete for good"),
+            ("Esc", "Back"),
+        ],
+    },
+    HelpSection {
+        title: "Audit",
+        keys: &[
+            ("↑/↓", "Move between findings"),
+            ("Enter", "Go to the host"),
+            ("x", "Export the report (.md/.json)"),
+            ("Esc", "Back"),
+        ],
+    },
+    HelpSection {
+        title: "Key map",
+        keys: &[
+            ("↑/↓", "Move between keys"),
+            ("r", "Rotate the key (or resume the rotation)"),
+            ("Esc", "Back"),
+        ],
+    },
+    HelpSection {
+        title: "Key rotation",
+        keys: &[
+            ("Enter", "Run the current step (retries failures)"),
+            ("s", "Skip pushing / removing the old key"),
+            ("x", "Abort the rotation"),
+            ("Esc", "Back (resume later)"),
+        ],
+    },
+    HelpSection {
+        title: "Sync diff",
+        keys: &[
+            ("↑/↓", "Select a file"),
+            ("Space", "Exclude/include the file"),
+            ("PgUp/PgDn", "Scroll"),
+            ("Enter", "Sync"),
+            ("Esc", "Cancel"),
+        ],
+    },
+    HelpSection {
+        title: "Merge conflicts",
+        keys: &[
+            ("↑/↓", "Select a host"),
+            ("l / r / m", "Keep local / take remote (or imported) / merge fields"),
+            ("Enter", "Apply all and finish the merge (or the import)"),
+            ("Esc", "Abort the merge (or cancel the import)"),
+        ],
+    },
+    HelpSection {
+        title: "Smart groups",
+        keys: &[
+            ("↑/↓", "Move between groups"),
+            ("Enter", "Apply the group's query"),
+            ("d", "Delete the group"),
+            ("Esc", "Close"),
+        ],
+    },
+];
@@ -0,0 +1,67 @@
+use ratatui::{
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TokenKind {
+    Whitespace,
+    Comment,
+    HostKeyword,
+    HostPattern,
+    Directive,
+    Value,
+}
+
+pub fn tokenize(line: &str) -> Vec<(TokenKind, &str)> {
+    let mut tokens = Vec::new();
+
+    let rest = line.trim_start();
+    let indent = &line[..line.len() - rest.len()];
+    if !indent.is_empty() {
+        tokens.push((TokenKind::Whitespace, indent));
+    }
+    if rest.is_empty() {
+        return tokens;
+    }
+    if rest.starts_with('#') {
+        tokens.push((TokenKind::Comment, rest));
+        return tokens;
+    }
+
+    // Diretiva termina em espaço ou '=' (ambos são separadores válidos)
+    let key_end = rest.find(|c: char| c.is_whitespace() || c == '=').unwrap_or(rest.len());
+    let (key, after_key) = rest.split_at(key_end);
+    let value = after_key.trim_start_matches(|c: char| c.is_whitespace() || c == '=');
+    let separator = &after_key[..after_key.len() - value.len()];
+
+    let is_header = key.eq_ignore_ascii_case("host") || key.eq_ignore_ascii_case("match");
+    tokens.push((if is_header { TokenKind::HostKeyword } else { TokenKind::Directive }, key));
+    if !separator.is_empty() {
+        tokens.push((TokenKind::Whitespace, separator));
+    }
+    if !value.is_empty() {
+        tokens.push((if is_header { TokenKind::HostPattern } else { TokenKind::Value }, value));
+    }
+    tokens
+}
+
+pub fn style_for(kind: TokenKind) -> Style {
+    match kind {
+        TokenKind::Whitespace => Style::default(),
+        TokenKind::Comment => Style::default().fg(Color::DarkGray).add_modifier(Modifier::ITALIC),
+        TokenKind::HostKeyword => Style::default().fg(Color::Magenta).add_modifier(Modifier::BOLD),
+        TokenKind::HostPattern => Style::default().fg(Color::Green).add_modifier(Modifier::BOLD),
+        TokenKind::Directive => Style::default().fg(Color::Yellow),
+        TokenKind::Value => Style::default().fg(Color::Cyan),
+    }
+}
+
+pub fn highlight_line(line: &str) -> Line<'static> {
+    Line::from(
+        tokenize(line)
+            .into_iter()
+            .map(|(kind, text)| Span::styled(text.to_string(), style_for(kind)))
+            .collect::<Vec<_>>(),
+    )
+}
@@ -0,0 +1,34 @@
+use std::collections::HashMap;
+
+use crate::ssh_config::SshHost;
+
+// Índice montado uma vez a cada carga, para não varrer a lista de hosts a cada consulta
+#[derive(Debug, Default)]
+pub struct HostIndex {
+    by_name: HashMap<String, usize>,
+    first_host: Option<usize>,
+}
+
+impl HostIndex {
+    pub fn build(hosts: &[SshHost]) -> Self {
+        let mut by_name = HashMap::with_capacity(hosts.len());
+        let mut first_host = None;
+        for (i, host) in hosts.iter().enumerate() {
+            if host.is_separator {
+                continue;
+            }
+            first_host.get_or_insert(i);
+            // Nomes repetidos: vale o primeiro bloco, como no ssh
+            by_name.entry(host.name.clone()).or_insert(i);
+        }
+        Self { by_name, first_host }
+    }
+
+    pub fn position(&self, name: &str) -> Option<usize> {
+        self.by_name.get(name).copied()
+    }
+
+    pub fn first_host(&self) -> Option<usize> {
+        self.first_host
+    }
+}
@@ -0,0 +1,219 @@
+use std::path::PathBuf;
+
+use serde::Serialize;
+
+use crate::config::AppConfig;
+use crate::db::{self, HostDb, HostRecord};
+use crate::effective;
+use crate::export;
+use crate::folder;
+use crate::git;
+use crate::metadata::MetadataStore;
+use crate::search;
+use crate::ssh_config::{SshConfig, SshHost};
+use crate::trash::{Trash, TrashEntry};
+
+// Visão dos hosts para os modos sem interface (API HTTP, JSON-RPC)
+#[derive(Debug, Clone, Serialize)]
+pub struct HostSummary {
+    pub name: String,
+    pub folder: Option<String>,
+    pub hostname: Option<String>,
+    pub user: Option<String>,
+    pub port: Option<u16>,
+    pub description: Option<String>,
+    pub tags: Vec<String>,
+}
+
+impl HostSummary {
+    // Curingas como `Host *` não são destinos de conexão
+    pub fn is_pattern(&self) -> bool {
+        self.name.split_whitespace().all(|p| effective::is_wildcard(p) || p.starts_with('!'))
+    }
+
+    // nome, hostname, usuário e tags separados por tab, para fzf/rofi/dmenu
+    pub fn plain_line(&self) -> String {
+        let clean = |s: &str| s.replace(['\t', '\n', '\r'], " ");
+        [
+            clean(&self.name),
+            clean(self.hostname.as_deref().unwrap_or_default()),
+            clean(self.user.as_deref().unwrap_or_default()),
+            clean(&self.tags.join(",")),
+        ]
+        .join("\t")
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct HostDetails {
+    #[serde(flatten)]
+    pub summary: HostSummary,
+    pub file: Option<PathBuf>,
+    pub line: usize,
+    pub notes: Option<String>,
+    // Diretivas do próprio bloco e as efetivas (com `Host *` etc.), na ordem do ssh
+    pub options: Vec<(String, String)>,
+    pub effective: Vec<(String, String)>,
+    // Comando para conectar sem depender do ssh_config, e o atalho pelo alias
+    pub command: Vec<String>,
+    pub alias_command: Vec<String>,
+}
+
+pub struct Inventory {
+    pub app_config: AppConfig,
+    hosts: Vec<SshHost>,
+    metadata: MetadataStore,
+}
+
+impl Inventory {
+    pub fn load(app_config: AppConfig) -> Result<Self, Box<dyn std::error::Error>> {
+        let mut inventory = Self { app_config, hosts: Vec::new(), metadata: MetadataStore::default() };
+        inventory.reload()?;
+        Ok(inventory)
+    }
+
+    // Relê o ssh_config e os metadados; chamado a cada requisição
+    pub fn reload(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+        let mut config = SshConfig::load_from_workdir(&self.app_config.get_workdir())?;
+        folder::arrange_sections(&mut config, &self.app_config.section_order);
+        self.hosts = config.hosts;
+        self.metadata = MetadataStore::load();
+        Ok(())
+    }
+
+    pub fn host(&self, name: &str) -> Option<&SshHost> {
+        self.position(name).map(|i| &self.hosts[i])
+    }
+
+    fn position(&self, name: &str) -> Option<usize> {
+        self.hosts.iter().position(|h| !h.is_separator && h.name == name)
+    }
+
+    fn summary(&self, host: &SshHost) -> HostSummary {
+        HostSummary {
+            name: host.name.clone(),
+            folder: host.source_dir.clone(),
+            hostname: host.hostname.clone(),
+            user: host.user.clone(),
+            port: host.port,
+            description: host.description.clone(),
+            tags: self.metadata.get(&host.name).tags,
+        }
+    }
+
+    // Todos os hosts na ordem do arquivo, ou os que casam com `query` por relevância
+    pub fn summaries(&self, query: Option<&str>) -> Vec<HostSummary> {
+        match query.filter(|q| !q.trim().is_empty()) {
+            Some(query) => {
+                let haystack = search::haystack(&self.hosts, &self.metadata, self.app_config.search_frecency_weight);
+                search::rank(&haystack, query, self.app_config.matcher)
+                    .into_iter()
+                    .map(|i| self.summary(&self.hosts[i]))
+                    .collect()
+            }
+            None => self.hosts.iter().filter(|h| !h.is_separator).map(|h| self.summary(h)).collect(),
+        }
+    }
+
+    pub fn details(&self, name: &str) -> Option<HostDetails> {
+        let index = self.position(name)?;
+        let host = &self.hosts[index];
+        let (options, _) = effective::resolve(&self.hosts, index);
+        let effective: Vec<(String, String)> = options.into_iter().map(|o| (o.key, o.value)).collect();
+        let alias = effective::host_alias(host);
+        Some(HostDetails {
+            summary: self.summary(host),
+            file: host.source_file.clone(),
+            line: host.line,
+            notes: self.metadata.get(&host.name).notes,
+            options: effective::host_options(host),
+            command: export::ssh_args(alias, &effective),
+            alias_command: vec!["ssh".to_string(), alias.to_string()],
+            effective,
+        })
+    }
+
+    // Grava um host novo na pasta indicada, como o formulário da interface faz
+    pub fn add_host(&mut self, host_db: Option<&HostDb>, record: &HostRecord) -> Result<(), Box<dyn std::error::Error>> {
+        if record.name.trim().is_empty() || record.folder.trim().is_empty() {
+            return Err("nome e pasta são obrigatórios".into());
+        }
+        validate(record)?;
+        if self.position(&record.name).is_some() {
+            return Err(format!("o host {} já existe", record.name).into());
+        }
+        let workdir = self.app_config.get_workdir();
+        let main_config = self.app_config.get_main_config_path();
+        let mut paths = Vec::new();
+        let path = workdir.join(&record.folder).join("config");
+        match host_db {
+            Some(host_db) if db::manages(&path) => {
+                host_db.insert(record, None)?;
+                for path in host_db.regenerate(&workdir)? {
+                    if SshConfig::add_include(&main_config, &path)? {
+                        paths.push(main_config.clone());
+                    }
+                    paths.push(path);
+                }
+            }
+            _ => {
+                let is_new_file = !path.exists();
+                SshConfig::append_host_block(&path, &record.name, record.description.as_deref(), &record.directives)?;
+                if is_new_file && SshConfig::add_include(&main_config, &path)? {
+                    paths.push(main_config);
+                }
+                paths.push(path);
+            }
+        }
+        self.commit(format!("lazysshrs: add host {}", record.name), paths);
+        self.reload()
+    }
+
+    pub fn remove_host(&mut self, host_db: Option<&HostDb>, name: &str) -> Result<(), Box<dyn std::error::Error>> {
+        let index = self.position(name).ok_or_else(|| format!("o host {} não existe", name))?;
+        let path = self.hosts[index].source_file.clone().ok_or("host sem arquivo de origem")?;
+        let generated = std::fs::read_to_string(&path).is_ok_and(|c| c.starts_with(db::GENERATED_HEADER));
+        Trash::push(TrashEntry::new(&self.hosts[index], path.clone(), generated && host_db.is_some()))?;
+        match host_db {
+            Some(host_db) if generated => {
+                host_db.delete(self.hosts[index].source_dir.as_deref().unwrap_or_default(), name)?;
+                host_db.regenerate(&self.app_config.get_workdir())?;
+            }
+            _ => SshConfig::remove_host_block(&path, name, self.hosts[index].line)?,
+        }
+        self.commit(format!("lazysshrs: remove host {}", name), vec![path]);
+        self.reload()
+    }
+
+    // Mesmo autocommit da interface; falhas do git não desfazem a gravação
+    fn commit(&self, message: String, paths: Vec<PathBuf>) {
+        let workdir = self.app_config.get_workdir();
+        if self.app_config.git_autocommit && git::status(&workdir).is_some() {
+            let _ = git::commit(&workdir, &paths, &message);
+        }
+    }
+}
+
+// O que vem da API ou da linha de comando vira texto do ssh_config: nomes e
+// chaves sem espaços nem caracteres de controle, valores sem quebra de linha
+// (que abririam outra diretiva ou outro Host) e a pasta dentro do workdir
+pub fn validate(record: &HostRecord) -> Result<(), Box<dyn std::error::Error>> {
+    let is_word = |s: &str| !s.is_empty() && !s.chars().any(|c| c.is_whitespace() || c.is_control());
+    if !is_word(&record.name) {
+        return Err(format!("nome de host inválido: {:?}", record.name).into());
+    }
+    for (key, value) in &record.directives {
+        if !is_word(key) {
+            return Err(format!("diretiva inválida: {:?}", key).into());
+        }
+        if value.contains(['\n', '\r']) {
+            return Err(format!("o valor de {} não pode ter quebra de linha", key).into());
+        }
+    }
+    let folder = std::path::Path::new(&record.folder);
+    let inside = folder.components().all(|c| matches!(c, std::path::Component::Normal(_)));
+    if !inside || record.folder.chars().any(|c| c.is_control()) {
+        return Err(format!("pasta inválida: {:?}; use um caminho relativo ao workdir, sem `..`", record.folder).into());
+    }
+    Ok(())
+}
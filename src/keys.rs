@@ -0,0 +1,164 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::ErrorKind;
+use std::path::PathBuf;
+
+use crate::effective;
+use crate::ssh_config::SshHost;
+
+// Caminho de um IdentityFile como o ssh o abre; `None` quando depende da
+// conexão (%h, %r, variáveis) ou do diretório atual
+pub fn identity_path(value: &str) -> Option<PathBuf> {
+    let home = home::home_dir()?;
+    let value = value.replace("%d", &home.to_string_lossy()).replace("%%", "%");
+    if value.contains(['%', '$']) {
+        return None;
+    }
+    let path = match value.strip_prefix("~/") {
+        Some(rest) => home.join(rest),
+        None => PathBuf::from(value),
+    };
+    path.is_absolute().then_some(path)
+}
+
+// Por que o arquivo de chave não serve, se não servir
+pub fn identity_problem(value: &str) -> Option<&'static str> {
+    let path = identity_path(value)?;
+    if path.is_dir() {
+        return Some("é um diretório");
+    }
+    match fs::File::open(&path) {
+        Ok(_) => None,
+        Err(e) if e.kind() == ErrorKind::NotFound => Some("não existe"),
+        Err(e) if e.kind() == ErrorKind::PermissionDenied => Some("sem permissão de leitura"),
+        Err(_) => Some("não pode ser lido"),
+    }
+}
+
+// Par de chaves em `~/.ssh`: a privada e o tipo e comentário tirados do `.pub`
+#[derive(Debug, Clone)]
+pub struct KeyPair {
+    pub path: String,
+    pub key_type: String,
+    pub comment: String,
+}
+
+// Chaves com `.pub` e privada lado a lado, como `~/.ssh/<nome>` para o IdentityFile
+pub fn find_keypairs() -> Vec<KeyPair> {
+    let Some(ssh_dir) = home::home_dir().map(|h| h.join(".ssh")) else {
+        return Vec::new();
+    };
+    let Ok(entries) = fs::read_dir(&ssh_dir) else {
+        return Vec::new();
+    };
+    let mut pairs: Vec<KeyPair> = entries.flatten()
+        .filter_map(|entry| {
+            let public = entry.path();
+            let name = public.file_name()?.to_str()?.strip_suffix(".pub")?.to_string();
+            if !ssh_dir.join(&name).is_file() {
+                return None;
+            }
+            let content = fs::read_to_string(&public).ok()?;
+            let mut parts = content.split_whitespace();
+            let key_type = parts.next()?;
+            let comment = parts.skip(1).collect::<Vec<_>>().join(" ");
+            Some(KeyPair {
+                path: format!("~/.ssh/{}", name),
+                key_type: key_type.strip_prefix("ssh-").unwrap_or(key_type).to_string(),
+                comment,
+            })
+        })
+        .collect();
+    pairs.sort_by(|a, b| a.path.cmp(&b.path));
+    pairs
+}
+
+// `~/.ssh/id` e `/home/eu/.ssh/id` são a mesma chave
+pub fn same_key(a: &str, b: &str) -> bool {
+    a == b || identity_path(a).is_some_and(|p| identity_path(b) == Some(p))
+}
+
+// IdentityFile de cada bloco, como escritos
+pub fn identity_files(host: &SshHost) -> Vec<&str> {
+    host.identity_files.iter().map(String::as_str).collect()
+}
+
+// Chaves com problema, pelo valor escrito no ssh_config
+pub fn check_identities(hosts: &[SshHost]) -> BTreeMap<String, &'static str> {
+    let mut problems = BTreeMap::new();
+    for value in hosts.iter().filter(|h| !h.is_separator).flat_map(identity_files) {
+        if !problems.contains_key(value) {
+            if let Some(problem) = identity_problem(value) {
+                problems.insert(value.to_string(), problem);
+            }
+        }
+    }
+    problems
+}
+
+// Um aviso por chave com problema, com os blocos que a usam
+pub fn identity_warnings(hosts: &[SshHost]) -> Vec<String> {
+    check_identities(hosts).into_iter()
+        .map(|(value, problem)| {
+            let users: Vec<&str> = hosts.iter()
+                .filter(|h| !h.is_separator && identity_files(h).contains(&value.as_str()))
+                .map(|h| h.name.as_str())
+                .collect();
+            format!("IdentityFile {} {} (Host {})", value, problem, users.join(", "))
+        })
+        .collect()
+}
+
+// Hosts que usam uma chave; `via` é o bloco com curinga que a define, se não
+// for o do próprio host
+#[derive(Debug, Clone)]
+pub struct KeyUsage {
+    // `None` agrupa os hosts sem IdentityFile (agente ou chaves padrão do ssh)
+    pub value: Option<String>,
+    pub problem: Option<&'static str>,
+    pub hosts: Vec<(usize, Option<usize>)>,
+}
+
+// Inverte a configuração: para cada chave, os hosts concretos que a usam
+// (inclusive por `Host *` e outros padrões), os que ficam com o agente e, no
+// fim, as chaves de `~/.ssh` sem uso
+pub fn key_usage(hosts: &[SshHost]) -> Vec<KeyUsage> {
+    let mut usage: Vec<KeyUsage> = Vec::new();
+    let mut default_keys = KeyUsage { value: None, problem: None, hosts: Vec::new() };
+    for (index, host) in hosts.iter().enumerate() {
+        if host.is_separator || host.is_pattern {
+            continue;
+        }
+        let (options, _) = effective::resolve(hosts, index);
+        let identities: Vec<_> = options.into_iter().filter(|o| o.key == "identityfile").collect();
+        if identities.is_empty() {
+            default_keys.hosts.push((index, None));
+        }
+        for option in identities {
+            let via = (option.source != index).then_some(option.source);
+            match usage.iter_mut().find(|u| u.value.as_deref().is_some_and(|v| same_key(v, &option.value))) {
+                Some(entry) => {
+                    // A mesma chave repetida em outro bloco que casa com o host
+                    if !entry.hosts.iter().any(|&(i, _)| i == index) {
+                        entry.hosts.push((index, via));
+                    }
+                }
+                None => usage.push(KeyUsage {
+                    problem: identity_problem(&option.value),
+                    value: Some(option.value),
+                    hosts: vec![(index, via)],
+                }),
+            }
+        }
+    }
+    usage.sort_by(|a, b| b.hosts.len().cmp(&a.hosts.len()).then_with(|| a.value.cmp(&b.value)));
+    if !default_keys.hosts.is_empty() {
+        usage.push(default_keys);
+    }
+    for pair in find_keypairs() {
+        if !usage.iter().any(|u| u.value.as_deref().is_some_and(|v| same_key(v, &pair.path))) {
+            usage.push(KeyUsage { value: Some(pair.path), problem: None, hosts: Vec::new() });
+        }
+    }
+    usage
+}
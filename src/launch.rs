@@ -0,0 +1,247 @@
+use std::process::{Command, Stdio};
+
+// Abre uma URL no navegador padrão do sistema
+pub fn open_url(url: &str) -> Result<(), Box<dyn std::error::Error>> {
+    let mut cmd = if cfg!(target_os = "macos") {
+        Command::new("open")
+    } else if cfg!(target_os = "windows") {
+        let mut cmd = Command::new("cmd");
+        cmd.args(["/C", "start", ""]);
+        cmd
+    } else {
+        Command::new("xdg-open")
+    };
+    cmd.arg(url)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()?;
+    Ok(())
+}
+
+// Ferramentas de área de transferência conhecidas, na ordem de preferência
+fn clipboard_tools(copy: bool) -> Vec<(&'static str, &'static [&'static str])> {
+    if cfg!(target_os = "macos") {
+        return vec![if copy { ("pbcopy", &[]) } else { ("pbpaste", &[]) }];
+    }
+    if cfg!(target_os = "windows") {
+        return vec![if copy { ("clip", &[]) } else { ("powershell", &["-NoProfile", "-Command", "Get-Clipboard"]) }];
+    }
+    let wayland = std::env::var_os("WAYLAND_DISPLAY").is_some();
+    let mut tools: Vec<(&'static str, &'static [&'static str])> = if copy {
+        vec![("xclip", &["-selection", "clipboard"]), ("xsel", &["--clipboard", "--input"])]
+    } else {
+        vec![("xclip", &["-selection", "clipboard", "-o"]), ("xsel", &["--clipboard", "--output"])]
+    };
+    if wayland {
+        tools.insert(0, if copy { ("wl-copy", &[]) } else { ("wl-paste", &["--no-newline"]) });
+    }
+    tools
+}
+
+// Copia para a área de transferência; sem ferramenta disponível, usa OSC 52,
+// que também funciona dentro de uma sessão SSH. Devolve o meio usado
+pub fn copy_to_clipboard(text: &str) -> Result<&'static str, Box<dyn std::error::Error>> {
+    use std::io::Write;
+
+    for (program, args) in clipboard_tools(true) {
+        let Ok(mut child) = Command::new(program)
+            .args(args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+        else {
+            continue;
+        };
+        if let Some(mut stdin) = child.stdin.take() {
+            stdin.write_all(text.as_bytes())?;
+        }
+        if child.wait()?.success() {
+            return Ok(program);
+        }
+    }
+    let mut stdout = std::io::stdout();
+    write!(stdout, "\x1b]52;c;{}\x07", base64(text.as_bytes()))?;
+    stdout.flush()?;
+    Ok("OSC 52")
+}
+
+pub fn read_clipboard() -> Result<String, Box<dyn std::error::Error>> {
+    for (program, args) in clipboard_tools(false) {
+        let Ok(output) = Command::new(program).args(args).stdin(Stdio::null()).stderr(Stdio::null()).output() else {
+            continue;
+        };
+        if output.status.success() {
+            return Ok(String::from_utf8_lossy(&output.stdout).to_string());
+        }
+    }
+    Err("nenhuma ferramenta de área de transferência encontrada (wl-paste, xclip, xsel, pbpaste)".into())
+}
+
+fn base64(data: &[u8]) -> String {
+    const ALPHABET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut out = String::new();
+    for chunk in data.chunks(3) {
+        let n = chunk.iter().enumerate().fold(0u32, |n, (i, &b)| n | (b as u32) << (16 - 8 * i));
+        for i in 0..4 {
+            if i <= chunk.len() {
+                out.push(ALPHABET[(n >> (18 - 6 * i) & 0x3f) as usize] as char);
+            } else {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
+
+// Como abrir o editor remoto: programa, argumentos e se é gráfico
+// (roda solto) ou de terminal (roda com a TUI suspensa)
+pub struct RemoteEditor {
+    pub program: String,
+    pub args: Vec<String>,
+    pub graphical: bool,
+}
+
+// `~/x` e `x` são relativos ao home remoto; `/x` é absoluto. Nas URLs do
+// netrw e do oil o absoluto leva duas barras: scp://host//srv/app/
+fn remote_url(scheme: &str, alias: &str, dir: &str) -> String {
+    let dir = dir.trim().trim_end_matches('/');
+    let dir = dir.strip_prefix('~').map(|d| d.trim_start_matches('/')).unwrap_or(dir);
+    let slash = if dir.is_empty() { "" } else { "/" };
+    format!("{}://{}/{}{}", scheme, alias, dir, slash)
+}
+
+pub fn remote_editor(editor: &str, alias: &str, dir: &str) -> RemoteEditor {
+    let dir = dir.trim();
+    match editor.trim() {
+        // O VSCode exige caminho absoluto; sem diretório abre a janela no host
+        "code" | "code-insiders" | "codium" => {
+            let mut args = vec!["--remote".to_string(), format!("ssh-remote+{}", alias)];
+            if !dir.is_empty() {
+                args.push(dir.to_string());
+            }
+            RemoteEditor { program: editor.trim().to_string(), args, graphical: true }
+        }
+        "nvim" => RemoteEditor { program: "nvim".to_string(), args: vec![remote_url("scp", alias, dir)], graphical: false },
+        "nvim-oil" => RemoteEditor { program: "nvim".to_string(), args: vec![remote_url("oil-ssh", alias, dir)], graphical: false },
+        // Comando próprio, com {host} e {path} substituídos em cada argumento
+        custom => {
+            let mut parts = custom.split_whitespace().map(|p| p.replace("{host}", alias).replace("{path}", dir));
+            let program = parts.next().unwrap_or_default();
+            RemoteEditor { program, args: parts.collect(), graphical: false }
+        }
+    }
+}
+
+// Acesso gráfico de um host, dos metadados: `rdp://host:porta`, `vnc://host:porta`
+// ou só `rdp`/`vnc` (HostName e porta padrão)
+#[derive(Debug, Clone, PartialEq)]
+pub struct Desktop {
+    pub protocol: &'static str,
+    pub host: String,
+    pub port: u16,
+}
+
+impl Desktop {
+    pub fn parse(value: &str, hostname: &str) -> Option<Self> {
+        let value = value.trim().replace("{hostname}", hostname);
+        let (scheme, rest) = value.split_once("://").unwrap_or((&value, ""));
+        let (protocol, default_port) = match scheme.to_lowercase().as_str() {
+            "rdp" => ("rdp", 3389),
+            "vnc" => ("vnc", 5900),
+            _ => return None,
+        };
+        let rest = rest.trim_end_matches('/');
+        let (host, port) = match rest.rsplit_once(':') {
+            Some((host, port)) => (host, port.parse().ok()?),
+            None => (rest, default_port),
+        };
+        let host = if host.is_empty() { hostname } else { host };
+        Some(Self { protocol, host: host.to_string(), port })
+    }
+
+    pub fn uri(&self) -> String {
+        format!("{}://{}:{}", self.protocol, self.host, self.port)
+    }
+
+    // Cliente configurado, com {host}, {port} e {uri} substituídos em cada argumento
+    pub fn command(&self, template: &str) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+        let port = self.port.to_string();
+        let uri = self.uri();
+        let args = crate::ssh_config::split_args(template).ok_or_else(|| format!("aspas sem fechar em {}", template))?;
+        Ok(args.iter()
+            .map(|arg| arg.replace("{host}", &self.host).replace("{port}", &port).replace("{uri}", &uri))
+            .collect())
+    }
+}
+
+// Programa gráfico solto, sem prender o terminal
+pub fn spawn_detached(argv: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+    let (program, args) = argv.split_first().ok_or("comando vazio")?;
+    Command::new(program)
+        .args(args)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .map_err(|e| format!("{}: {}", program, e))?;
+    Ok(())
+}
+
+// Terminais que abrem sessões em abas por controle remoto
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TabTerminal {
+    Kitty,
+    WezTerm,
+}
+
+impl TabTerminal {
+    // Pelas variáveis que cada terminal exporta para os seus processos
+    pub fn detect() -> Option<Self> {
+        if std::env::var_os("KITTY_WINDOW_ID").is_some() {
+            Some(TabTerminal::Kitty)
+        } else if std::env::var_os("WEZTERM_PANE").is_some() {
+            Some(TabTerminal::WezTerm)
+        } else {
+            None
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            TabTerminal::Kitty => "kitty",
+            TabTerminal::WezTerm => "WezTerm",
+        }
+    }
+
+    // Abre `argv` numa aba nova com o título `title`
+    pub fn open_tab(self, title: &str, argv: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+        let output = match self {
+            // Exige `allow_remote_control` no kitty.conf
+            TabTerminal::Kitty => Command::new("kitty")
+                .args(["@", "launch", "--type=tab", "--tab-title", title, "--"])
+                .args(argv)
+                .stdin(Stdio::null())
+                .output()?,
+            TabTerminal::WezTerm => Command::new("wezterm")
+                .args(["cli", "spawn", "--"])
+                .args(argv)
+                .stdin(Stdio::null())
+                .output()?,
+        };
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            return Err(format!("{} terminou com código {:?}: {}", self.label(), output.status.code(), stderr.trim()).into());
+        }
+        // O spawn devolve o id do painel; o título é só cosmético (wezterm antigo não tem set-tab-title)
+        if self == TabTerminal::WezTerm {
+            let pane = String::from_utf8_lossy(&output.stdout).trim().to_string();
+            let _ = Command::new("wezterm")
+                .args(["cli", "set-tab-title", "--pane-id", &pane, title])
+                .stdin(Stdio::null())
+                .output();
+        }
+        Ok(())
+    }
+}
@@ -3,15 +3,262 @@ mod tui;
 mod form;
 mod config;
 mod connectivity;
+mod topology;
+mod highlight;
+mod effective;
+mod folder;
+mod forward;
+mod tunnel;
+mod systemd;
+mod launch;
+mod metadata;
+mod state;
+mod sweep;
+mod export;
+mod index;
+mod search;
+mod db;
+mod crypto;
+mod git;
+mod sync;
+mod diff;
+mod conflict;
+mod share;
+mod inventory;
+mod serve;
+mod rpc;
+mod table;
+mod privacy;
+mod theme;
+mod actions;
+mod trash;
+mod audit;
+mod keys;
+mod rotation;
+mod recording;
+mod backup;
+mod help;
 
+use inventory::Inventory;
 use ssh_config::SshConfig;
 use tui::App;
 use config::AppConfig;
+use connectivity::ConnectivityTest;
+use db::{HostDb, HostRecord};
+use metadata::MetadataStore;
+use state::SavedState;
+use trash::Trash;
+use rotation::Rotation;
+
+const USAGE: &str = "Usage: lazysshrs [COMMAND]
+
+Commands:
+  list [--plain | --json]       Print the hosts (tab-separated or JSON)
+  add --host NAME --folder DIR  Add a host: [--hostname H] [--user U] [--port N]
+      [--identity-file F] [--proxy-jump J] [--description D] [-o Key=Value]...
+  connect QUERY                 Connect to the best match for QUERY
+  pick                          Fuzzy-search a host and print its alias
+  serve [--port N] [--write]    Local HTTP/JSON API
+  rpc                           JSON-RPC on stdin/stdout";
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.iter().any(|a| a == "-h" || a == "--help") {
+        println!("{}", USAGE);
+        return Ok(());
+    }
     let app_config = AppConfig::load()?;
-    let ssh_config = SshConfig::load_from_workdir(&app_config.get_workdir())?;
-    let mut app = App::new(ssh_config, app_config);
+    backup::configure(app_config.config_backups);
+    // Arquivos já cifrados pedem a senha mesmo com a cifragem desligada,
+    // para poderem ser lidos e regravados em texto
+    let store_files = [MetadataStore::path(), SavedState::path(), Trash::path(), Rotation::path()];
+    if app_config.encrypt_state || store_files.iter().any(|path| crypto::is_encrypted(path)) {
+        crypto::unlock(&store_files, app_config.encrypt_state)?;
+    }
+    let mut ssh_config = SshConfig::load_from_workdir(&app_config.get_workdir())?;
+    let mut host_db = HostDb::open_configured(&app_config)?;
+    if let Some(db) = host_db.as_mut() {
+        // Banco novo começa com os hosts que já estão nas pastas
+        if db.is_empty()? {
+            let skipped = db.import(&ssh_config.hosts, &app_config.get_workdir())?;
+            // Os includes importados foram regerados
+            let warnings = std::mem::take(&mut ssh_config.warnings);
+            ssh_config = SshConfig::load_from_workdir(&app_config.get_workdir())?;
+            ssh_config.warnings = warnings;
+            for path in skipped {
+                ssh_config.warnings.push(format!(
+                    "{} ficou fora do banco de hosts: tem conteúdo além de blocos Host (comentários soltos, Match, Include, nomes repetidos) e continua como include comum",
+                    path.display()
+                ));
+            }
+        }
+    }
+    // Na interface os avisos aparecem em um popup
+    if !args.is_empty() {
+        for warning in &ssh_config.warnings {
+            eprintln!("lazysshrs: {}", warning);
+        }
+    }
+
+    match args.first().map(String::as_str) {
+        None => {}
+        Some("pick") => return pick_command(ssh_config, app_config, host_db),
+        Some("serve") => return serve_command(app_config, host_db, &args[1..]),
+        Some("rpc") => return rpc::run(Inventory::load(app_config)?),
+        Some("list") => return list_command(app_config, &args[1..]),
+        Some("add") => return add_command(app_config, host_db, &args[1..]),
+        Some("connect") => return connect_command(app_config, host_db, &args[1..]),
+        Some(other) => usage_error(&format!("comando desconhecido: {}", other)),
+    }
+    let mut app = App::new(ssh_config, app_config, host_db);
     app.run()?;
     Ok(())
 }
+
+// Só a alias vai para o stdout (`ssh $(lazysshrs pick)`); a interface é
+// desenhada no stderr. Cancelar sai com código 1, como o `fzf`
+fn pick_command(ssh_config: SshConfig, app_config: AppConfig, host_db: Option<HostDb>) -> Result<(), Box<dyn std::error::Error>> {
+    let mut app = App::new(ssh_config, app_config, host_db);
+    match app.pick()? {
+        Some(alias) => println!("{}", alias),
+        None => std::process::exit(1),
+    }
+    Ok(())
+}
+
+fn usage_error(message: &str) -> ! {
+    eprintln!("lazysshrs: {}\n{}", message, USAGE);
+    std::process::exit(2);
+}
+
+fn serve_command(app_config: AppConfig, host_db: Option<HostDb>, args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+    let mut port = app_config.serve_port;
+    let mut write = false;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--write" => write = true,
+            "--port" => match args.next().and_then(|p| p.parse().ok()) {
+                Some(value) => port = value,
+                None => usage_error("--port precisa de um número"),
+            },
+            other => usage_error(&format!("opção desconhecida: {}", other)),
+        }
+    }
+    serve::run(Inventory::load(app_config)?, host_db, port, write)
+}
+
+// Uma linha por host, sem a interface; `--plain` (padrão) é o formato para fzf
+// e `--json`, uma lista com nome, pasta, hostname, usuário, porta, descrição e tags
+fn list_command(app_config: AppConfig, args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+    use std::io::Write;
+
+    let mut json = false;
+    for arg in args {
+        match arg.as_str() {
+            "--plain" => json = false,
+            "--json" => json = true,
+            other => usage_error(&format!("opção desconhecida: {}", other)),
+        }
+    }
+    let inventory = Inventory::load(app_config)?;
+    let hosts: Vec<_> = inventory.summaries(None).into_iter().filter(|h| !h.is_pattern()).collect();
+    if json {
+        println!("{}", serde_json::to_string_pretty(&hosts)?);
+        return Ok(());
+    }
+    let mut stdout = std::io::stdout().lock();
+    for host in &hosts {
+        // O leitor (fzf, head) pode fechar o pipe antes do fim
+        if let Err(e) = writeln!(stdout, "{}", host.plain_line()) {
+            if e.kind() == std::io::ErrorKind::BrokenPipe {
+                return Ok(());
+            }
+            return Err(e.into());
+        }
+    }
+    Ok(())
+}
+
+// Grava um host como o formulário: diretivas na mesma ordem e `-o` para as demais
+fn add_command(app_config: AppConfig, host_db: Option<HostDb>, args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+    let mut record = HostRecord::default();
+    let mut fields: Vec<(&str, Option<String>)> = ["HostName", "User", "Port", "IdentityFile", "ProxyJump"]
+        .map(|key| (key, None))
+        .to_vec();
+    let mut options = Vec::new();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let Some(value) = args.next().cloned() else {
+            usage_error(&format!("{} precisa de um valor", arg));
+        };
+        let field = match arg.as_str() {
+            "--host" => {
+                record.name = value;
+                continue;
+            }
+            "--folder" => {
+                record.folder = value;
+                continue;
+            }
+            "--description" => {
+                record.description = Some(value).filter(|d| !d.trim().is_empty());
+                continue;
+            }
+            "-o" | "--option" => {
+                // `Key=Value` ou `Key Value`, como no `ssh -o`
+                match value.split_once(['=', ' ']) {
+                    Some((key, value)) if !key.trim().is_empty() => options.push((key.trim().to_string(), value.trim().to_string())),
+                    _ => usage_error(&format!("opção inválida: {} (use Key=Value)", value)),
+                }
+                continue;
+            }
+            "--hostname" => 0,
+            "--user" => 1,
+            "--port" if value.parse::<u16>().is_err() => usage_error("--port precisa de um número"),
+            "--port" => 2,
+            "--identity-file" => 3,
+            "--proxy-jump" => 4,
+            other => usage_error(&format!("opção desconhecida: {}", other)),
+        };
+        fields[field].1 = Some(value);
+    }
+    if record.name.is_empty() || record.folder.is_empty() {
+        usage_error("add precisa de --host e --folder");
+    }
+    record.directives = fields.into_iter()
+        .filter_map(|(key, value)| Some((key.to_string(), value?)))
+        .chain(options)
+        .collect();
+    let mut inventory = Inventory::load(app_config)?;
+    inventory.add_host(host_db.as_ref(), &record)?;
+    eprintln!("lazysshrs: host {} adicionado em {}", record.name, record.folder);
+    Ok(())
+}
+
+// Conecta ao host com o nome exato ou, senão, ao primeiro resultado da busca
+fn connect_command(app_config: AppConfig, host_db: Option<HostDb>, args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+    let query = args.join(" ");
+    if query.trim().is_empty() {
+        usage_error("connect precisa de um host ou de um termo de busca");
+    }
+    let record = app_config.record_sessions;
+    let inventory = Inventory::load(app_config)?;
+    let name = inventory.summaries(None).into_iter()
+        .filter(|h| !h.is_pattern())
+        .find(|h| h.name == query)
+        .or_else(|| inventory.summaries(Some(&query)).into_iter().find(|h| !h.is_pattern()))
+        .map(|h| h.name)
+        .ok_or_else(|| format!("nenhum host casa com {}", query))?;
+    let mut metadata = MetadataStore::load();
+    // Mesmo programa da interface: console alternativo, post_login e gravação
+    let host = inventory.host(&name).ok_or_else(|| format!("nenhum host casa com {}", query))?;
+    let argv = ConnectivityTest::session_argv(host, &metadata.get(&name), &[])?;
+    eprintln!("lazysshrs: conectando a {}", name);
+    ConnectivityTest::run_session(&name, &argv, record)?;
+    // Como na interface, só a sessão que terminou sem erro entra no histórico
+    if let Some(host_db) = &host_db {
+        host_db.record_connection(&name)?;
+    }
+    metadata.record_connection(&name)
+}
@@ -0,0 +1,166 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+use serde::{Deserialize, Serialize};
+
+use crate::config::AppConfig;
+use crate::crypto;
+use crate::connectivity::ReachMethod;
+use crate::state;
+
+// Conexões guardadas por host; as mais antigas saem primeiro
+const MAX_CONNECTIONS: usize = 1000;
+
+// Dados por host que não pertencem ao ssh_config
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HostMeta {
+    pub reachability: Option<ReachMethod>,
+    // Comando de verificação próprio; `{host}` e `{hostname}` são substituídos
+    pub check_command: Option<String>,
+    pub notes: Option<String>,
+    pub tags: Vec<String>,
+    // Endereço MAC, para Wake-on-LAN e inventário
+    pub mac: Option<String>,
+    // Último diretório aberto no editor remoto (`E`)
+    pub remote_dir: Option<String>,
+    // Comando depois do login (`sudo -i`, `tmux attach || tmux`), com `ssh -t`
+    pub post_login: Option<String>,
+    // Conexão no lugar do ssh (telnet, console serial); `{host}` e `{hostname}` são substituídos
+    pub console: Option<String>,
+    // Acesso gráfico: `rdp://host:porta` ou `vnc://host:porta`
+    pub desktop: Option<String>,
+    // Sessões que terminaram sem erro, mais recentes primeiro; alimentam a
+    // ordem "recentes", a frecência da busca e as estatísticas
+    pub connections: Vec<u64>,
+}
+
+impl HostMeta {
+    pub fn is_empty(&self) -> bool {
+        *self == HostMeta::default()
+    }
+
+    pub fn last_connected(&self) -> Option<u64> {
+        self.connections.first().copied()
+    }
+
+    // Uso frequente e recente: 0 para hosts nunca usados, até 1.5 para os diários
+    pub fn frecency(&self, now: u64) -> f64 {
+        let Some(last) = self.last_connected() else {
+            return 0.0;
+        };
+        let days = now.saturating_sub(last) as f64 / 86_400.0;
+        let recency = match days {
+            d if d < 1.0 => 1.0,
+            d if d < 7.0 => 0.7,
+            d if d < 30.0 => 0.4,
+            _ => 0.2,
+        };
+        ((self.connections.len() as f64).ln_1p() * recency / 3.0).min(1.5)
+    }
+}
+
+// Metadados de todos os hosts, em `<state dir>/hosts.toml`
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MetadataStore {
+    pub hosts: BTreeMap<String, HostMeta>,
+    // Arquivo que existe mas não foi lido; com ele, `save` recusa gravar por cima
+    #[serde(skip)]
+    load_error: Option<String>,
+}
+
+impl MetadataStore {
+    pub fn path() -> PathBuf {
+        AppConfig::get_state_dir().join("hosts.toml")
+    }
+
+    // Um arquivo ilegível (senha errada, TOML quebrado) deixa o store vazio e
+    // bloqueado para gravação, para não apagar notas, tags e histórico
+    pub fn load() -> Self {
+        let path = Self::path();
+        if !path.exists() {
+            return Self::default();
+        }
+        let error = match crypto::read_to_string(&path).map(|content| toml::from_str::<Self>(&content)) {
+            Some(Ok(store)) => return store,
+            Some(Err(e)) => e.to_string(),
+            None => "não foi possível ler ou decifrar o arquivo".to_string(),
+        };
+        Self {
+            load_error: Some(format!("{} não foi carregado ({}); os metadados não serão gravados até o arquivo ser corrigido", path.display(), error.lines().next().unwrap_or_default())),
+            ..Self::default()
+        }
+    }
+
+    pub fn load_error(&self) -> Option<&str> {
+        self.load_error.as_deref()
+    }
+
+    pub fn save(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+        if let Some(error) = &self.load_error {
+            return Err(error.clone().into());
+        }
+        self.hosts.retain(|_, meta| !meta.is_empty());
+        let path = Self::path();
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        crypto::write(&path, &toml::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    // Bônus de busca em pontos percentuais, com `weight` = search_frecency_weight
+    pub fn search_bonus(&self, host: &str, weight: u32) -> i64 {
+        let now = state::now();
+        self.hosts.get(host).map(|meta| (meta.frecency(now) * weight as f64) as i64).unwrap_or(0)
+    }
+
+    pub fn get(&self, host: &str) -> HostMeta {
+        self.hosts.get(host).cloned().unwrap_or_default()
+    }
+
+    // Altera os metadados de um host e grava o arquivo
+    pub fn update(&mut self, host: &str, change: impl FnOnce(&mut HostMeta)) -> Result<(), Box<dyn std::error::Error>> {
+        change(self.hosts.entry(host.to_string()).or_default());
+        self.save()
+    }
+
+    // Só depois de uma sessão que terminou sem erro
+    pub fn record_connection(&mut self, host: &str) -> Result<(), Box<dyn std::error::Error>> {
+        self.update(host, |meta| {
+            meta.connections.insert(0, state::now());
+            meta.connections.truncate(MAX_CONNECTIONS);
+        })
+    }
+
+    // Mantém os metadados quando o host é renomeado
+    pub fn rename(&mut self, old: &str, new: &str) -> Result<(), Box<dyn std::error::Error>> {
+        if old == new {
+            return Ok(());
+        }
+        if let Some(meta) = self.hosts.remove(old) {
+            self.hosts.insert(new.to_string(), meta);
+            self.save()?;
+        }
+        Ok(())
+    }
+}
+
+// Tags digitadas separadas por vírgula ou espaço, sem repetição
+pub fn parse_tags(input: &str) -> Vec<String> {
+    let mut tags: Vec<String> = Vec::new();
+    for tag in input.split([',', ' ']).map(str::trim).filter(|t| !t.is_empty()) {
+        if !tags.iter().any(|t| t == tag) {
+            tags.push(tag.to_string());
+        }
+    }
+    tags
+}
+
+// Aceita `aa:bb:cc:dd:ee:ff` ou com hífens; devolve o formato com dois-pontos
+pub fn normalize_mac(input: &str) -> Option<String> {
+    let parts: Vec<&str> = input.trim().split([':', '-']).collect();
+    let valid = parts.len() == 6 && parts.iter().all(|p| p.len() == 2 && p.chars().all(|c| c.is_ascii_hexdigit()));
+    valid.then(|| parts.join(":").to_lowercase())
+}
@@ -0,0 +1,39 @@
+use crate::highlight::{self, TokenKind};
+
+// Diretivas cujo valor revela a infraestrutura (endereços, saltos, caminhos de chaves)
+const SENSITIVE: [&str; 12] = [
+    "hostname",
+    "proxyjump",
+    "identityfile",
+    "certificatefile",
+    "identityagent",
+    "hostkeyalias",
+    "localforward",
+    "remoteforward",
+    "proxycommand",
+    "userknownhostsfile",
+    "globalknownhostsfile",
+    "controlpath",
+];
+
+pub const MASK: &str = "••••••";
+
+pub fn is_sensitive(key: &str) -> bool {
+    SENSITIVE.iter().any(|k| k.eq_ignore_ascii_case(key))
+}
+
+pub fn mask_value(key: &str, value: &str) -> String {
+    if is_sensitive(key) { MASK.to_string() } else { value.to_string() }
+}
+
+// Linha do ssh_config com o valor das diretivas sensíveis escondido
+pub fn mask_line(line: &str) -> String {
+    let tokens = highlight::tokenize(line);
+    let key = tokens.iter().find(|(kind, _)| *kind == TokenKind::Directive).map(|(_, text)| *text);
+    match key {
+        Some(key) if is_sensitive(key) => tokens.iter()
+            .map(|(kind, text)| if *kind == TokenKind::Value { MASK } else { text })
+            .collect(),
+        _ => line.to_string(),
+    }
+}
@@ -0,0 +1,92 @@
+use std::fs;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use crate::config::AppConfig;
+use crate::export;
+use crate::state;
+
+// Sessão gravada com asciinema: `<state dir>/recordings/<host>_<AAAAMMDDTHHMMSSZ>.cast`
+#[derive(Debug, Clone)]
+pub struct Recording {
+    pub path: PathBuf,
+    pub started: String,
+    pub size: u64,
+}
+
+pub fn dir() -> PathBuf {
+    AppConfig::get_state_dir().join("recordings")
+}
+
+// Nome do host só com caracteres seguros para arquivo (`Host web prod` vira `web-prod`)
+fn file_stem(host: &str) -> String {
+    host.chars()
+        .map(|c| if c.is_ascii_alphanumeric() || "._-".contains(c) { c } else { '-' })
+        .collect()
+}
+
+pub fn new_path(host: &str) -> PathBuf {
+    let stamp = state::format_timestamp(state::now()).replace(['-', ':'], "");
+    dir().join(format!("{}_{}.cast", file_stem(host), stamp))
+}
+
+// Gravações de um host, as mais recentes primeiro
+pub fn list(host: &str) -> Vec<Recording> {
+    let Ok(entries) = fs::read_dir(dir()) else {
+        return Vec::new();
+    };
+    let stem = file_stem(host);
+    let mut recordings: Vec<Recording> = entries.flatten()
+        .filter_map(|entry| {
+            let path = entry.path();
+            let name = path.file_name()?.to_str()?.strip_suffix(".cast")?;
+            // O carimbo vem depois do último `_`; o nome do host pode ter outros
+            let (owner, stamp) = name.rsplit_once('_')?;
+            if owner != stem || stamp.len() != 16 || !stamp.is_ascii() {
+                return None;
+            }
+            let started = format!(
+                "{}-{}-{} {}:{}:{}",
+                &stamp[0..4], &stamp[4..6], &stamp[6..8], &stamp[9..11], &stamp[11..13], &stamp[13..15]
+            );
+            let size = entry.metadata().map(|m| m.len()).unwrap_or(0);
+            Some(Recording { path, started, size })
+        })
+        .collect();
+    recordings.sort_by(|a, b| b.started.cmp(&a.started));
+    recordings
+}
+
+fn run(command: &mut Command) -> Result<(), Box<dyn std::error::Error>> {
+    let status = command.status().map_err(|e| match e.kind() {
+        ErrorKind::NotFound => "asciinema não encontrado no PATH".to_string(),
+        _ => e.to_string(),
+    })?;
+    if !status.success() {
+        return Err(format!("asciinema terminou com código {:?}", status.code()).into());
+    }
+    Ok(())
+}
+
+// `argv` é o comando da sessão, com o programa (`ssh`, `telnet`...)
+pub fn record(path: &Path, title: &str, argv: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let command = argv.iter()
+        .map(|arg| export::shell_quote(arg))
+        .collect::<Vec<_>>()
+        .join(" ");
+    run(Command::new("asciinema")
+        .args(["rec", "--quiet", "--title", title, "--command", &command])
+        .arg(path))
+}
+
+pub fn play(path: &Path) -> Result<(), Box<dyn std::error::Error>> {
+    run(Command::new("asciinema").arg("play").arg(path))?;
+    println!("\nEnter para voltar");
+    let mut line = String::new();
+    std::io::stdin().read_line(&mut line)?;
+    Ok(())
+}
@@ -0,0 +1,196 @@
+use std::fs;
+use std::path::PathBuf;
+use std::process::Command;
+use serde::{Deserialize, Serialize};
+
+use crate::config::AppConfig;
+use crate::crypto;
+use crate::effective;
+use crate::keys::{self, KeyUsage};
+use crate::ssh_config::SshHost;
+use crate::state;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum RotationStep {
+    #[default]
+    Generate,
+    Push,
+    Update,
+    RemoveOld,
+    Done,
+}
+
+impl RotationStep {
+    pub const ALL: [RotationStep; 5] = [
+        RotationStep::Generate,
+        RotationStep::Push,
+        RotationStep::Update,
+        RotationStep::RemoveOld,
+        RotationStep::Done,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            RotationStep::Generate => "Generate the new key",
+            RotationStep::Push => "Push it to each host (ssh-copy-id)",
+            RotationStep::Update => "Update IdentityFile entries",
+            RotationStep::RemoveOld => "Remove the old key from authorized_keys (optional)",
+            RotationStep::Done => "Done",
+        }
+    }
+}
+
+// Resultado por host: `None` ainda não tentado
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RotationHost {
+    pub name: String,
+    pub alias: String,
+    pub pushed: Option<bool>,
+    pub removed: Option<bool>,
+}
+
+// Bloco onde o IdentityFile antigo está escrito (pode ser um padrão como `Host *`)
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RotationBlock {
+    pub name: String,
+    pub file: PathBuf,
+    pub updated: bool,
+}
+
+// Rotação em andamento, em `<state dir>/rotation.toml`, para continuar de onde parou
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Rotation {
+    pub old_key: String,
+    pub new_key: String,
+    pub step: RotationStep,
+    pub hosts: Vec<RotationHost>,
+    pub blocks: Vec<RotationBlock>,
+    pub started_at: u64,
+}
+
+impl Rotation {
+    pub fn path() -> PathBuf {
+        AppConfig::get_state_dir().join("rotation.toml")
+    }
+
+    pub fn load() -> Option<Self> {
+        crypto::read_to_string(&Self::path()).and_then(|content| toml::from_str(&content).ok())
+    }
+
+    pub fn save(&self) -> Result<(), Box<dyn std::error::Error>> {
+        let path = Self::path();
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        crypto::write(&path, &toml::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    pub fn clear() -> Result<(), Box<dyn std::error::Error>> {
+        match fs::remove_file(Self::path()) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.into()),
+            _ => Ok(()),
+        }
+    }
+
+    // Plano a partir do mapa de chaves; a nova chave fica ao lado da antiga, com a data
+    pub fn plan(hosts: &[SshHost], usage: &KeyUsage) -> Result<Self, Box<dyn std::error::Error>> {
+        let old_key = usage.value.clone().ok_or("hosts sem IdentityFile não têm chave para trocar")?;
+        if usage.hosts.is_empty() {
+            return Err(format!("nenhum host usa {}", old_key).into());
+        }
+        keys::identity_path(&old_key).ok_or_else(|| format!("o caminho de {} depende da conexão", old_key))?;
+        let now = state::now();
+        let date = state::format_timestamp(now)[..10].replace('-', "");
+        let new_key = format!("{}-{}", old_key, date);
+
+        let mut rotation = Self { old_key, new_key, started_at: now, ..Self::default() };
+        for &(index, via) in &usage.hosts {
+            let host = &hosts[index];
+            rotation.hosts.push(RotationHost {
+                name: host.name.clone(),
+                alias: effective::host_alias(host).to_string(),
+                ..RotationHost::default()
+            });
+            let block = &hosts[via.unwrap_or(index)];
+            let file = block.source_file.clone().unwrap_or_default();
+            if !rotation.blocks.iter().any(|b| b.name == block.name && b.file == file) {
+                rotation.blocks.push(RotationBlock { name: block.name.clone(), file, updated: false });
+            }
+        }
+        Ok(rotation)
+    }
+
+    pub fn new_key_path(&self) -> Option<PathBuf> {
+        keys::identity_path(&self.new_key)
+    }
+
+    // Roda no terminal (fora da interface): o ssh-keygen pergunta a senha da chave
+    pub fn generate(&self) -> Result<(), Box<dyn std::error::Error>> {
+        let path = self.new_key_path().ok_or("caminho da nova chave inválido")?;
+        if path.exists() {
+            return Ok(());
+        }
+        println!("Gerando {} (ed25519)\n", self.new_key);
+        let status = Command::new("ssh-keygen")
+            .args(["-t", "ed25519", "-C"])
+            .arg(format!("lazysshrs rotation of {}", self.old_key))
+            .arg("-f")
+            .arg(&path)
+            .status()?;
+        if !status.success() {
+            return Err(format!("ssh-keygen terminou com código {:?}", status.code()).into());
+        }
+        Ok(())
+    }
+
+    // ssh-copy-id em cada host que ainda não recebeu a chave; autentica com a antiga
+    pub fn push(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+        let public = format!("{}.pub", self.new_key_path().ok_or("caminho da nova chave inválido")?.display());
+        for host in self.hosts.iter_mut().filter(|h| h.pushed != Some(true)) {
+            println!("\n== {} ==", host.name);
+            let status = Command::new("ssh-copy-id").args(["-i", &public]).arg(&host.alias).status();
+            host.pushed = Some(status.is_ok_and(|s| s.success()));
+        }
+        wait_enter()
+    }
+
+    // Tira a chave antiga do authorized_keys, entrando já com a nova
+    pub fn remove_old(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+        let old_public = keys::identity_path(&self.old_key)
+            .map(|p| PathBuf::from(format!("{}.pub", p.display())))
+            .ok_or("caminho da chave antiga inválido")?;
+        let content = fs::read_to_string(&old_public)
+            .map_err(|e| format!("{}: {}", old_public.display(), e))?;
+        let blob = content.split_whitespace().nth(1).ok_or("chave pública antiga ilegível")?.to_string();
+        let new_key = self.new_key_path().ok_or("caminho da nova chave inválido")?;
+        // O blob é base64, seguro entre aspas simples; `cat >` preserva as permissões do arquivo
+        let script = format!(
+            "f=~/.ssh/authorized_keys; test -f $f || exit 0; grep -vF '{}' $f > $f.lazysshrs; cat $f.lazysshrs > $f && rm $f.lazysshrs",
+            blob
+        );
+        for host in self.hosts.iter_mut().filter(|h| h.pushed == Some(true) && h.removed != Some(true)) {
+            println!("\n== {} ==", host.name);
+            let status = Command::new("ssh")
+                .arg("-i")
+                .arg(&new_key)
+                .args(["-o", "IdentitiesOnly=yes"])
+                .arg(&host.alias)
+                .arg(&script)
+                .status();
+            host.removed = Some(status.is_ok_and(|s| s.success()));
+        }
+        wait_enter()
+    }
+}
+
+fn wait_enter() -> Result<(), Box<dyn std::error::Error>> {
+    println!("\nEnter para voltar");
+    let mut line = String::new();
+    std::io::stdin().read_line(&mut line)?;
+    Ok(())
+}
@@ -0,0 +1,137 @@
+use std::io::{self, BufRead, Write};
+
+use serde_json::{json, Value};
+
+use crate::export;
+use crate::inventory::Inventory;
+
+// Códigos de erro do JSON-RPC 2.0
+const PARSE_ERROR: i64 = -32700;
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+const HOST_NOT_FOUND: i64 = -32001;
+
+struct RpcError {
+    code: i64,
+    message: String,
+}
+
+impl RpcError {
+    fn new(code: i64, message: impl ToString) -> Self {
+        Self { code, message: message.to_string() }
+    }
+}
+
+// Enquadramento da entrada: uma mensagem por linha (Neovim) ou cabeçalho
+// Content-Length como no LSP (vscode-jsonrpc); a resposta usa o mesmo
+#[derive(Clone, Copy, PartialEq)]
+enum Framing {
+    Lines,
+    ContentLength,
+}
+
+// JSON-RPC 2.0 em stdin/stdout, para plugins de editor; logs vão para stderr
+pub fn run(mut inventory: Inventory) -> Result<(), Box<dyn std::error::Error>> {
+    let stdin = io::stdin();
+    let mut input = stdin.lock();
+    let mut stdout = io::stdout().lock();
+    while let Some((message, framing)) = read_message(&mut input)? {
+        if message.trim().is_empty() {
+            continue;
+        }
+        if let Some(response) = handle(&message, &mut inventory) {
+            let text = response.to_string();
+            match framing {
+                Framing::Lines => writeln!(stdout, "{}", text)?,
+                Framing::ContentLength => write!(stdout, "Content-Length: {}\r\n\r\n{}", text.len(), text)?,
+            }
+            stdout.flush()?;
+        }
+    }
+    Ok(())
+}
+
+fn read_message(input: &mut impl BufRead) -> Result<Option<(String, Framing)>, Box<dyn std::error::Error>> {
+    let mut line = String::new();
+    if input.read_line(&mut line)? == 0 {
+        return Ok(None);
+    }
+    let Some(length) = header_length(&line) else {
+        return Ok(Some((line, Framing::Lines)));
+    };
+    // Demais cabeçalhos até a linha em branco
+    let mut length = length;
+    loop {
+        line.clear();
+        if input.read_line(&mut line)? == 0 || line.trim().is_empty() {
+            break;
+        }
+        length = header_length(&line).unwrap_or(length);
+    }
+    let mut body = vec![0; length];
+    input.read_exact(&mut body)?;
+    Ok(Some((String::from_utf8_lossy(&body).to_string(), Framing::ContentLength)))
+}
+
+fn header_length(line: &str) -> Option<usize> {
+    let (name, value) = line.split_once(':')?;
+    name.trim().eq_ignore_ascii_case("content-length").then(|| value.trim().parse().ok()).flatten()
+}
+
+// None para notificações (sem `id`), que não têm resposta
+fn handle(message: &str, inventory: &mut Inventory) -> Option<Value> {
+    let request: Value = match serde_json::from_str(message) {
+        Ok(request) => request,
+        Err(e) => return Some(reply(Value::Null, Err(RpcError::new(PARSE_ERROR, e)))),
+    };
+    let id = request.get("id").cloned();
+    let Some(method) = request.get("method").and_then(Value::as_str) else {
+        return Some(reply(id.unwrap_or(Value::Null), Err(RpcError::new(INVALID_REQUEST, "requisição sem method"))));
+    };
+    let params = request.get("params").cloned().unwrap_or(Value::Null);
+    if let Err(e) = inventory.reload() {
+        eprintln!("lazysshrs: erro ao reler o ssh_config: {}", e);
+    }
+    let result = call(method, &params, inventory);
+    id.map(|id| reply(id, result))
+}
+
+fn reply(id: Value, result: Result<Value, RpcError>) -> Value {
+    match result {
+        Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+        Err(e) => json!({ "jsonrpc": "2.0", "id": id, "error": { "code": e.code, "message": e.message } }),
+    }
+}
+
+fn param<'a>(params: &'a Value, key: &str) -> Option<&'a str> {
+    params.get(key).and_then(Value::as_str)
+}
+
+fn host_name(params: &Value) -> Result<&str, RpcError> {
+    param(params, "name").ok_or_else(|| RpcError::new(INVALID_PARAMS, "parâmetro name é obrigatório"))
+}
+
+fn call(method: &str, params: &Value, inventory: &Inventory) -> Result<Value, RpcError> {
+    match method {
+        "hosts/list" => Ok(json!(inventory.summaries(None))),
+        "hosts/search" => {
+            let query = param(params, "query").ok_or_else(|| RpcError::new(INVALID_PARAMS, "parâmetro query é obrigatório"))?;
+            let limit = params.get("limit").and_then(Value::as_u64).map(|l| l as usize).unwrap_or(usize::MAX);
+            Ok(json!(inventory.summaries(Some(query)).into_iter().take(limit).collect::<Vec<_>>()))
+        }
+        "hosts/get" => {
+            let name = host_name(params)?;
+            let details = inventory.details(name).ok_or_else(|| RpcError::new(HOST_NOT_FOUND, format!("host {} não encontrado", name)))?;
+            Ok(json!(details))
+        }
+        // argv para rodar direto e a linha já escapada para um terminal do editor
+        "hosts/connectCommand" => {
+            let name = host_name(params)?;
+            let details = inventory.details(name).ok_or_else(|| RpcError::new(HOST_NOT_FOUND, format!("host {} não encontrado", name)))?;
+            let shell = details.alias_command.iter().map(|a| export::shell_quote(a)).collect::<Vec<_>>().join(" ");
+            Ok(json!({ "argv": details.alias_command, "shell": shell, "standalone": details.command }))
+        }
+        _ => Err(RpcError::new(METHOD_NOT_FOUND, format!("método desconhecido: {}", method))),
+    }
+}
@@ -0,0 +1,490 @@
+use std::cmp::Reverse;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+use fuzzy_matcher::skim::SkimMatcherV2;
+use fuzzy_matcher::FuzzyMatcher;
+use nucleo_matcher::pattern::{CaseMatching, Normalization, Pattern};
+use nucleo_matcher::Utf32Str;
+use serde::{Deserialize, Serialize};
+
+use crate::metadata::MetadataStore;
+use crate::ssh_config::SshHost;
+
+// Algoritmo de busca, escolhido por `matcher` no config
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MatcherKind {
+    #[default]
+    Skim,
+    Substring,
+    Nucleo,
+}
+
+// Pontua um nome contra a consulta; None = não casa. Uma consulta que estende
+// outra só pode casar com um subconjunto dos nomes (o cache do worker depende disso)
+pub trait Matcher: Send {
+    fn score(&mut self, candidate: &str, query: &str) -> Option<i64>;
+    // Posições (em caracteres) que casaram, para destacar nos resultados
+    fn indices(&mut self, candidate: &str, query: &str) -> Vec<usize>;
+}
+
+struct SkimBackend(SkimMatcherV2);
+
+impl Matcher for SkimBackend {
+    fn score(&mut self, candidate: &str, query: &str) -> Option<i64> {
+        self.0.fuzzy_match(candidate, query)
+    }
+
+    fn indices(&mut self, candidate: &str, query: &str) -> Vec<usize> {
+        self.0.fuzzy_indices(candidate, query).map(|(_, indices)| indices).unwrap_or_default()
+    }
+}
+
+// Trecho contínuo, sem diferenciar maiúsculas; começo do nome e nomes curtos primeiro
+struct SubstringBackend;
+
+impl Matcher for SubstringBackend {
+    fn score(&mut self, candidate: &str, query: &str) -> Option<i64> {
+        let position = candidate.to_lowercase().find(&query.to_lowercase())?;
+        Some(1000 - position as i64 * 10 - candidate.len() as i64)
+    }
+
+    fn indices(&mut self, candidate: &str, query: &str) -> Vec<usize> {
+        // Comparação caractere a caractere: `to_lowercase` pode mudar o tamanho em bytes
+        let candidate: Vec<char> = candidate.chars().flat_map(char::to_lowercase).collect();
+        let query: Vec<char> = query.chars().flat_map(char::to_lowercase).collect();
+        if query.is_empty() || query.len() > candidate.len() {
+            return Vec::new();
+        }
+        (0..=candidate.len() - query.len())
+            .find(|&start| candidate[start..start + query.len()] == query[..])
+            .map(|start| (start..start + query.len()).collect())
+            .unwrap_or_default()
+    }
+}
+
+// O padrão é reaproveitado enquanto a consulta não muda
+struct NucleoBackend {
+    matcher: nucleo_matcher::Matcher,
+    pattern: Option<(String, Pattern)>,
+    buffer: Vec<char>,
+}
+
+impl NucleoBackend {
+    fn parse(&mut self, query: &str) {
+        if self.pattern.as_ref().map(|(q, _)| q.as_str()) != Some(query) {
+            self.pattern = Some((query.to_string(), Pattern::parse(query, CaseMatching::Smart, Normalization::Smart)));
+        }
+    }
+}
+
+impl Matcher for NucleoBackend {
+    fn score(&mut self, candidate: &str, query: &str) -> Option<i64> {
+        self.parse(query);
+        let (_, pattern) = self.pattern.as_ref()?;
+        let score = pattern.score(Utf32Str::new(candidate, &mut self.buffer), &mut self.matcher)?;
+        Some(score as i64)
+    }
+
+    fn indices(&mut self, candidate: &str, query: &str) -> Vec<usize> {
+        self.parse(query);
+        let mut indices = Vec::new();
+        if let Some((_, pattern)) = &self.pattern {
+            if pattern.indices(Utf32Str::new(candidate, &mut self.buffer), &mut self.matcher, &mut indices).is_none() {
+                indices.clear();
+            }
+        }
+        // Um índice por átomo do padrão, sem ordem: ordena e tira as repetições
+        indices.sort_unstable();
+        indices.dedup();
+        indices.into_iter().map(|i| i as usize).collect()
+    }
+}
+
+pub fn matcher(kind: MatcherKind) -> Box<dyn Matcher> {
+    match kind {
+        MatcherKind::Skim => Box::new(SkimBackend(SkimMatcherV2::default())),
+        MatcherKind::Substring => Box::new(SubstringBackend),
+        MatcherKind::Nucleo => Box::new(NucleoBackend {
+            matcher: nucleo_matcher::Matcher::new(nucleo_matcher::Config::DEFAULT),
+            pattern: None,
+            buffer: Vec::new(),
+        }),
+    }
+}
+
+// Host pesquisável: o nome, os demais campos de texto e os que os operadores filtram
+pub struct Entry {
+    pub index: usize,
+    pub name: String,
+    // Valores de `text_fields`, na mesma ordem
+    fields: Vec<String>,
+    // Bônus de uso em pontos percentuais
+    pub bonus: i64,
+    user: String,
+    hostname: String,
+    port: String,
+    folder: String,
+    tags: Vec<String>,
+}
+
+pub type Haystack = Arc<Vec<Entry>>;
+
+// Campos além do nome que a busca também percorre, como (diretiva, valor):
+// para achar um host pelo IP, pelo usuário ou por um comentário
+pub fn text_fields(host: &SshHost) -> Vec<(String, String)> {
+    let mut fields = Vec::new();
+    let mut push = |key: &str, value: Option<&String>| {
+        if let Some(value) = value.filter(|v| !v.is_empty()) {
+            fields.push((key.to_string(), value.clone()));
+        }
+    };
+    push("HostName", host.hostname.as_ref());
+    push("User", host.user.as_ref());
+    for identity_file in &host.identity_files {
+        push("IdentityFile", Some(identity_file));
+    }
+    for forward in &host.local_forwards {
+        push("LocalForward", Some(forward));
+    }
+    for (key, value) in host.other_options.iter() {
+        push(key, Some(value));
+    }
+    for line in host.description.iter().flat_map(|d| d.lines()) {
+        fields.push(("Description".to_string(), line.to_string()));
+    }
+    fields
+}
+
+pub fn haystack(hosts: &[SshHost], metadata: &MetadataStore, frecency_weight: u32) -> Haystack {
+    Arc::new(
+        hosts.iter().enumerate()
+            .filter(|(_, h)| !h.is_separator)
+            .map(|(i, h)| Entry {
+                index: i,
+                name: h.name.clone(),
+                fields: text_fields(h).into_iter().map(|(_, value)| value).collect(),
+                bonus: metadata.search_bonus(&h.name, frecency_weight),
+                user: h.user.clone().unwrap_or_default().to_lowercase(),
+                hostname: h.hostname.clone().unwrap_or_default().to_lowercase(),
+                port: h.port.unwrap_or(22).to_string(),
+                folder: h.source_dir.clone().unwrap_or_default().to_lowercase(),
+                tags: metadata.get(&h.name).tags.iter().map(|t| t.to_lowercase()).collect(),
+            })
+            .collect(),
+    )
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Field {
+    User,
+    Hostname,
+    Port,
+    Tag,
+    Folder,
+}
+
+// Consulta com operadores: `user:deploy port:2222 tag:prod folder:work texto`.
+// Os filtros se somam (E); o restante é o texto da busca fuzzy
+#[derive(Debug, Clone, PartialEq)]
+pub struct Query {
+    pub text: String,
+    filters: Vec<(Field, String)>,
+}
+
+impl Query {
+    pub fn parse(input: &str) -> Self {
+        let mut text = Vec::new();
+        let mut filters = Vec::new();
+        for word in input.split_whitespace() {
+            let field = word.split_once(':').and_then(|(key, value)| {
+                let field = match key.to_lowercase().as_str() {
+                    "user" | "u" => Field::User,
+                    "host" | "hostname" => Field::Hostname,
+                    "port" | "p" => Field::Port,
+                    "tag" | "t" => Field::Tag,
+                    "folder" | "f" => Field::Folder,
+                    _ => return None,
+                };
+                Some((field, value.to_lowercase()))
+            });
+            match field {
+                // `user:` sozinho (ainda digitando) não filtra nada
+                Some((_, value)) if value.is_empty() => {}
+                Some(filter) => filters.push(filter),
+                None => text.push(word),
+            }
+        }
+        Self { text: text.join(" "), filters }
+    }
+
+    pub fn has_filters(&self) -> bool {
+        !self.filters.is_empty()
+    }
+
+    // Porta e tag exigem o valor exato; os demais campos aceitam um trecho
+    fn accepts(&self, entry: &Entry) -> bool {
+        self.filters.iter().all(|(field, value)| match field {
+            Field::User => entry.user.contains(value.as_str()),
+            Field::Hostname => entry.hostname.contains(value.as_str()),
+            Field::Port => entry.port == *value,
+            Field::Tag => entry.tags.contains(value),
+            Field::Folder => entry.folder.contains(value.as_str()),
+        })
+    }
+
+    // Só com filtros, a ordem é a do uso (depois a do arquivo). Sem casar pelo
+    // nome, vale o melhor dos outros campos (o índice em `text_fields`), com
+    // metade da pontuação: quem casa pelo nome vem antes
+    fn score(&self, matcher: &mut dyn Matcher, entry: &Entry) -> Option<(i64, Option<usize>)> {
+        if !self.accepts(entry) {
+            return None;
+        }
+        if self.text.is_empty() {
+            return Some((entry.bonus, None));
+        }
+        if let Some(score) = matcher.score(&entry.name, &self.text) {
+            return Some((boosted(score, entry.bonus), None));
+        }
+        let mut best: Option<(i64, usize)> = None;
+        for (n, field) in entry.fields.iter().enumerate() {
+            if let Some(score) = matcher.score(field, &self.text) {
+                if best.is_none_or(|(top, _)| score > top) {
+                    best = Some((score, n));
+                }
+            }
+        }
+        best.map(|(score, n)| (boosted(score / 2, entry.bonus), Some(n)))
+    }
+}
+
+// O uso pesa proporcionalmente à pontuação, que tem escala diferente em cada algoritmo
+fn boosted(score: i64, bonus: i64) -> i64 {
+    score + score.abs() * bonus / 100
+}
+
+enum Request {
+    Haystack(Haystack),
+    Query(u64, String),
+}
+
+pub struct SearchResults {
+    pub generation: u64,
+    pub matches: Vec<usize>,
+    // Acertos por outro campo que não o nome: índice do host → índice em `text_fields`
+    pub fields: HashMap<usize, usize>,
+    // Sem acertos: nomes parecidos com a consulta (erro de digitação)
+    pub suggestions: Vec<usize>,
+}
+
+// Busca fuzzy em uma thread própria, para a digitação não travar com listas enormes
+pub struct SearchWorker {
+    sender: Sender<Request>,
+    receiver: Receiver<SearchResults>,
+    generation: u64,
+    received: u64,
+    latest: Arc<AtomicU64>,
+}
+
+impl SearchWorker {
+    pub fn spawn(haystack: Haystack, debounce: Duration, kind: MatcherKind) -> Self {
+        let (sender, requests) = mpsc::channel();
+        let (results, receiver) = mpsc::channel();
+        let latest = Arc::new(AtomicU64::new(0));
+        let worker_latest = Arc::clone(&latest);
+        thread::spawn(move || worker_loop(haystack, requests, results, worker_latest, debounce, matcher(kind)));
+        Self { sender, receiver, generation: 0, received: 0, latest }
+    }
+
+    pub fn set_haystack(&self, haystack: Haystack) {
+        let _ = self.sender.send(Request::Haystack(haystack));
+    }
+
+    pub fn search(&mut self, query: &str) {
+        self.generation += 1;
+        self.latest.store(self.generation, Ordering::Relaxed);
+        let _ = self.sender.send(Request::Query(self.generation, query.to_string()));
+    }
+
+    pub fn is_pending(&self) -> bool {
+        self.received < self.generation
+    }
+
+    // Resultado da consulta mais recente, descartando respostas antigas
+    pub fn poll(&mut self) -> Option<SearchResults> {
+        let mut latest = None;
+        for results in self.receiver.try_iter() {
+            self.received = self.received.max(results.generation);
+            if results.generation == self.generation {
+                latest = Some(results);
+            }
+        }
+        latest
+    }
+
+    // Espera o resultado da consulta atual (ex.: Enter logo após digitar)
+    pub fn wait(&mut self, timeout: Duration) -> Option<SearchResults> {
+        while self.is_pending() {
+            let results = self.receiver.recv_timeout(timeout).ok()?;
+            self.received = self.received.max(results.generation);
+            if results.generation == self.generation {
+                return Some(results);
+            }
+        }
+        None
+    }
+}
+
+// Posição no haystack, pontuação e o campo que casou (`None` = o nome)
+type Scored = (usize, i64, Option<usize>);
+
+fn worker_loop(
+    mut haystack: Haystack,
+    requests: Receiver<Request>,
+    results: Sender<SearchResults>,
+    latest: Arc<AtomicU64>,
+    debounce: Duration,
+    mut matcher: Box<dyn Matcher>,
+) {
+    // Pontuações por consulta; uma consulta mais longa só precisa reavaliar
+    // os acertos do maior prefixo já calculado
+    let mut cache: HashMap<String, Arc<Vec<Scored>>> = HashMap::new();
+    let mut query: Option<(u64, String)> = None;
+
+    while let Ok(request) = requests.recv() {
+        let mut pending = Some(request);
+        loop {
+            match pending.take() {
+                Some(Request::Haystack(new)) => {
+                    haystack = new;
+                    cache.clear();
+                }
+                Some(Request::Query(generation, text)) => query = Some((generation, text)),
+                None => {}
+            }
+            // Debounce: espera a digitação parar antes de calcular
+            match requests.recv_timeout(debounce) {
+                Ok(request) => pending = Some(request),
+                Err(RecvTimeoutError::Timeout) => break,
+                Err(RecvTimeoutError::Disconnected) => return,
+            }
+        }
+        let Some((generation, query)) = query.take() else {
+            continue;
+        };
+
+        let parsed = Query::parse(&query);
+        let scored = match cache.get(&query) {
+            Some(scored) => Arc::clone(scored),
+            None => {
+                // Com operadores, um prefixo não restringe os acertos (`port:22` → `port:222`)
+                let base = (1..query.len())
+                    .rev()
+                    .filter(|&i| query.is_char_boundary(i) && !query.contains(':'))
+                    .find_map(|i| cache.get(&query[..i]));
+                let candidates: Box<dyn Iterator<Item = usize>> = match base {
+                    Some(base) => Box::new(base.iter().map(|&(i, _, _)| i)),
+                    None => Box::new(0..haystack.len()),
+                };
+
+                let mut scored = Vec::new();
+                let mut cancelled = false;
+                for (n, i) in candidates.enumerate() {
+                    // Uma consulta mais nova chegou: abandona a atual
+                    if n % 1024 == 0 && latest.load(Ordering::Relaxed) != generation {
+                        cancelled = true;
+                        break;
+                    }
+                    if let Some((score, field)) = haystack.get(i).and_then(|entry| parsed.score(matcher.as_mut(), entry)) {
+                        scored.push((i, score, field));
+                    }
+                }
+                if cancelled {
+                    continue;
+                }
+
+                // Ordenação estável: empates mantêm a ordem do arquivo
+                scored.sort_by_key(|&(_, score, _)| Reverse(score));
+                let scored = Arc::new(scored);
+                if cache.len() > 256 {
+                    cache.clear();
+                }
+                cache.insert(query.clone(), Arc::clone(&scored));
+                scored
+            }
+        };
+
+        let matches: Vec<usize> = scored.iter().map(|&(i, _, _)| haystack[i].index).collect();
+        let fields = scored.iter().filter_map(|&(i, _, field)| Some((haystack[i].index, field?))).collect();
+        let suggestions = if matches.is_empty() && !parsed.has_filters() { suggest(&haystack, &parsed.text) } else { Vec::new() };
+        if results.send(SearchResults { generation, matches, fields, suggestions }).is_err() {
+            break;
+        }
+    }
+}
+
+// Distância de edição com transposição (Damerau restrita), sem diferenciar maiúsculas
+fn edit_distance(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.to_lowercase().chars().collect();
+    let b: Vec<char> = b.to_lowercase().chars().collect();
+    let mut rows = vec![vec![0; b.len() + 1]; a.len() + 1];
+    for (i, row) in rows.iter_mut().enumerate() {
+        row[0] = i;
+    }
+    rows[0] = (0..=b.len()).collect();
+    for i in 1..=a.len() {
+        for j in 1..=b.len() {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+            let mut best = (rows[i - 1][j] + 1).min(rows[i][j - 1] + 1).min(rows[i - 1][j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                best = best.min(rows[i - 2][j - 2] + 1);
+            }
+            rows[i][j] = best;
+        }
+    }
+    rows[a.len()][b.len()]
+}
+
+// Menor distância entre a consulta e o nome, suas partes (web-01.prod → web, 01, prod)
+// ou o começo do nome do mesmo tamanho da consulta
+fn closeness(name: &str, query: &str) -> usize {
+    let prefix: String = name.chars().take(query.chars().count()).collect();
+    name.split(['-', '.', '_'])
+        .chain([name, prefix.as_str()])
+        .filter(|part| !part.is_empty())
+        .map(|part| edit_distance(part, query))
+        .min()
+        .unwrap_or(usize::MAX)
+}
+
+// "Você quis dizer…": até 5 hosts a poucas edições da consulta, os mais próximos primeiro
+pub fn suggest(haystack: &Haystack, query: &str) -> Vec<usize> {
+    let limit = match query.chars().count() {
+        0..=2 => return Vec::new(),
+        3..=4 => 1,
+        5..=8 => 2,
+        _ => 3,
+    };
+    let mut close: Vec<(usize, usize)> = haystack.iter()
+        .map(|entry| (closeness(&entry.name, query), entry.index))
+        .filter(|&(distance, _)| distance <= limit)
+        .collect();
+    close.sort();
+    close.into_iter().take(5).map(|(_, i)| i).collect()
+}
+
+// Busca síncrona, para os modos sem interface (API, RPC); mesma ordenação do worker
+pub fn rank(haystack: &Haystack, query: &str, kind: MatcherKind) -> Vec<usize> {
+    let mut matcher = matcher(kind);
+    let query = Query::parse(query);
+    let mut scored: Vec<(usize, i64)> = haystack.iter()
+        .filter_map(|entry| query.score(matcher.as_mut(), entry).map(|(score, _)| (entry.index, score)))
+        .collect();
+    scored.sort_by_key(|&(_, score)| Reverse(score));
+    scored.into_iter().map(|(i, _)| i).collect()
+}
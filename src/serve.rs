@@ -0,0 +1,171 @@
+use serde::Deserialize;
+use serde_json::{json, Value};
+use tiny_http::{Header, Method, Request, Response, Server};
+
+use crate::db::{HostDb, HostRecord};
+use crate::inventory::{self, Inventory};
+
+// Corpo do POST /hosts; `options` aceita [["HostName", "x"], ...] ou {"HostName": "x"}
+#[derive(Deserialize)]
+struct NewHost {
+    name: String,
+    folder: String,
+    #[serde(default)]
+    description: Option<String>,
+    #[serde(default)]
+    options: Value,
+}
+
+impl NewHost {
+    fn record(self) -> Result<HostRecord, String> {
+        let text = |v: &Value| match v {
+            Value::String(s) => Some(s.clone()),
+            Value::Number(n) => Some(n.to_string()),
+            _ => None,
+        };
+        let directives = match &self.options {
+            Value::Null => Vec::new(),
+            Value::Object(map) => map.iter().filter_map(|(k, v)| Some((k.clone(), text(v)?))).collect(),
+            Value::Array(pairs) => pairs.iter()
+                .filter_map(|pair| Some((text(pair.get(0)?)?, text(pair.get(1)?)?)))
+                .collect(),
+            _ => return Err("options deve ser um objeto ou uma lista de pares".to_string()),
+        };
+        Ok(HostRecord {
+            name: self.name.trim().to_string(),
+            folder: self.folder.trim().to_string(),
+            description: self.description.filter(|d| !d.trim().is_empty()),
+            directives,
+        })
+    }
+}
+
+struct Reply {
+    status: u16,
+    body: Option<Value>,
+}
+
+impl Reply {
+    fn ok(body: impl serde::Serialize) -> Self {
+        Self { status: 200, body: Some(json!(body)) }
+    }
+
+    fn error(status: u16, message: impl ToString) -> Self {
+        Self { status, body: Some(json!({ "error": message.to_string() })) }
+    }
+}
+
+// Só 127.0.0.1; gravação (POST/DELETE) apenas com `--write`
+pub fn run(mut inventory: Inventory, host_db: Option<HostDb>, port: u16, write: bool) -> Result<(), Box<dyn std::error::Error>> {
+    let address = format!("127.0.0.1:{}", port);
+    let server = Server::http(&address).map_err(|e| format!("não foi possível abrir {}: {}", address, e))?;
+    eprintln!("lazysshrs: API em http://{} ({})", address, if write { "leitura e escrita" } else { "somente leitura" });
+
+    for mut request in server.incoming_requests() {
+        let reply = if !trusted_host(&request, port) {
+            // Protege contra DNS rebinding: páginas web não falam com a API
+            Reply::error(403, "Host não permitido")
+        } else {
+            if let Err(e) = inventory.reload() {
+                eprintln!("lazysshrs: erro ao reler o ssh_config: {}", e);
+            }
+            handle(&mut request, &mut inventory, host_db.as_ref(), write)
+        };
+        eprintln!("{} {} -> {}", request.method(), request.url(), reply.status);
+        respond(request, reply);
+    }
+    Ok(())
+}
+
+fn trusted_host(request: &Request, port: u16) -> bool {
+    request.headers().iter()
+        .find(|h| h.field.equiv("Host"))
+        .is_some_and(|h| [format!("127.0.0.1:{}", port), format!("localhost:{}", port)].contains(&h.value.to_string()))
+}
+
+fn handle(request: &mut Request, inventory: &mut Inventory, host_db: Option<&HostDb>, write: bool) -> Reply {
+    let url = request.url().to_string();
+    let (path, query) = url.split_once('?').unwrap_or((&url, ""));
+    let segments: Vec<String> = path.split('/').filter(|s| !s.is_empty()).map(percent_decode).collect();
+    let segments: Vec<&str> = segments.iter().map(String::as_str).collect();
+
+    match (request.method(), segments.as_slice()) {
+        (Method::Get, ["health"]) => Reply::ok(json!({ "ok": true })),
+        (Method::Get, ["hosts"]) => {
+            let q = query_param(query, "q");
+            Reply::ok(inventory.summaries(q.as_deref()))
+        }
+        (Method::Get, ["hosts", name]) => match inventory.details(name) {
+            Some(details) => Reply::ok(details),
+            None => Reply::error(404, format!("host {} não encontrado", name)),
+        },
+        (Method::Post, ["hosts"]) | (Method::Delete, ["hosts", _]) if !write => {
+            Reply::error(403, "API somente leitura; inicie com `lazysshrs serve --write`")
+        }
+        (Method::Post, ["hosts"]) => {
+            // Exigir JSON força o preflight de CORS em navegadores, que não respondemos
+            let is_json = request.headers().iter()
+                .any(|h| h.field.equiv("Content-Type") && h.value.as_str().starts_with("application/json"));
+            if !is_json {
+                return Reply::error(415, "Content-Type deve ser application/json");
+            }
+            let mut body = String::new();
+            if let Err(e) = request.as_reader().read_to_string(&mut body) {
+                return Reply::error(400, e);
+            }
+            let record = serde_json::from_str::<NewHost>(&body).map_err(|e| e.to_string())
+                .and_then(NewHost::record)
+                .and_then(|record| inventory::validate(&record).map(|_| record).map_err(|e| e.to_string()));
+            let record = match record {
+                Ok(record) => record,
+                Err(e) => return Reply::error(400, e),
+            };
+            match inventory.add_host(host_db, &record) {
+                Ok(()) => Reply { status: 201, body: inventory.details(&record.name).map(|d| json!(d)) },
+                Err(e) => Reply::error(409, e),
+            }
+        }
+        (Method::Delete, ["hosts", name]) => match inventory.remove_host(host_db, name) {
+            Ok(()) => Reply { status: 204, body: None },
+            Err(e) => Reply::error(404, e),
+        },
+        (_, ["health"] | ["hosts"] | ["hosts", _]) => Reply::error(405, "método não permitido"),
+        _ => Reply::error(404, "rota não encontrada"),
+    }
+}
+
+fn respond(request: Request, reply: Reply) {
+    let body = reply.body.map(|b| b.to_string()).unwrap_or_default();
+    let mut response = Response::from_string(body).with_status_code(reply.status);
+    if let Ok(header) = Header::from_bytes("Content-Type", "application/json; charset=utf-8") {
+        response.add_header(header);
+    }
+    let _ = request.respond(response);
+}
+
+fn query_param(query: &str, key: &str) -> Option<String> {
+    query.split('&')
+        .filter_map(|pair| pair.split_once('='))
+        .find(|(k, _)| *k == key)
+        .map(|(_, v)| percent_decode(&v.replace('+', " ")))
+}
+
+fn percent_decode(text: &str) -> String {
+    let bytes = text.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = bytes.get(i + 1..i + 3).and_then(|h| std::str::from_utf8(h).ok()).and_then(|h| u8::from_str_radix(h, 16).ok());
+        match (bytes[i], hex) {
+            (b'%', Some(byte)) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            (byte, _) => {
+                decoded.push(byte);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&decoded).to_string()
+}
@@ -0,0 +1,283 @@
+use qrcode::{Color, QrCode};
+use serde::Serialize;
+
+use crate::ssh_config;
+
+// Dados que vão no QR: só o necessário para conectar, nada de chaves
+#[derive(Serialize)]
+struct SharedHost<'a> {
+    host: &'a str,
+    hostname: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    user: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    port: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    proxy_jump: Option<&'a str>,
+}
+
+fn get<'a>(options: &'a [(String, String)], key: &str) -> Option<&'a str> {
+    options.iter().find(|(k, _)| k == key).map(|(_, v)| v.as_str())
+}
+
+// ssh://[user@]hostname[:port], a partir das opções efetivas do host
+pub fn ssh_uri(alias: &str, options: &[(String, String)]) -> String {
+    let hostname = get(options, "hostname").unwrap_or(alias);
+    let hostname = if hostname.contains(':') { format!("[{}]", hostname) } else { hostname.to_string() };
+    let user = get(options, "user").map(|u| format!("{}@", u)).unwrap_or_default();
+    let port = get(options, "port").filter(|p| *p != "22").map(|p| format!(":{}", p)).unwrap_or_default();
+    format!("ssh://{}{}{}", user, hostname, port)
+}
+
+pub fn json_blob(alias: &str, options: &[(String, String)]) -> String {
+    let shared = SharedHost {
+        host: alias,
+        hostname: get(options, "hostname").unwrap_or(alias),
+        user: get(options, "user"),
+        port: get(options, "port"),
+        proxy_jump: get(options, "proxyjump").filter(|p| !p.eq_ignore_ascii_case("none")),
+    };
+    serde_json::to_string(&shared).unwrap_or_default()
+}
+
+// QR em meios-blocos: cada linha de texto junta duas linhas de módulos.
+// Claro = bloco preenchido, para ler bem em terminais de fundo escuro
+pub fn qr_lines(data: &str) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+    let code = QrCode::new(data.as_bytes())?;
+    let width = code.width();
+    let colors = code.to_colors();
+    let quiet = 2;
+    let size = width + quiet * 2;
+    let light = |x: usize, y: usize| -> bool {
+        if x < quiet || y < quiet || x >= width + quiet || y >= width + quiet {
+            return true;
+        }
+        colors[(y - quiet) * width + (x - quiet)] == Color::Light
+    };
+
+    let mut lines = Vec::new();
+    for y in (0..size).step_by(2) {
+        let line = (0..size)
+            .map(|x| match (light(x, y), y + 1 >= size || light(x, y + 1)) {
+                (true, true) => '█',
+                (true, false) => '▀',
+                (false, true) => '▄',
+                (false, false) => ' ',
+            })
+            .collect();
+        lines.push(line);
+    }
+    Ok(lines)
+}
+
+// Bloco pronto para colar, entre cercas de código
+pub fn snippet(block: &[String]) -> String {
+    format!("```sshconfig\n{}\n```\n", block.join("\n").trim_end())
+}
+
+// Bloco com as opções efetivas (inclusive as herdadas de `Host *` etc.)
+pub fn effective_block(alias: &str, options: &[(String, String)]) -> Vec<String> {
+    let mut lines = vec![format!("Host {}", alias)];
+    lines.extend(options.iter().map(|(key, value)| format!("    {} {}", ssh_config::canonical_key(key), ssh_config::quote(key, value))));
+    lines
+}
+
+// Host recebido de fora (área de transferência), antes de ir para o formulário
+#[derive(Debug, Clone, PartialEq)]
+pub struct ImportedHost {
+    pub alias: String,
+    pub directives: Vec<(String, String)>,
+    // Formato reconhecido, para a mensagem ao usuário
+    pub source: &'static str,
+}
+
+impl ImportedHost {
+    fn new(alias: &str, source: &'static str) -> Self {
+        Self { alias: alias.to_string(), directives: Vec::new(), source }
+    }
+
+    fn push(&mut self, key: &str, value: &str) {
+        let value = value.trim();
+        if !value.is_empty() {
+            self.directives.push((ssh_config::canonical_key(key), value.to_string()));
+        }
+    }
+}
+
+// Reconhece um bloco de ssh_config, uma linha de comando do ssh, uma URI ssh://
+// ou o JSON do QR code; cercas de código e prompts de shell são ignorados
+pub fn parse_import(text: &str) -> Result<ImportedHost, Box<dyn std::error::Error>> {
+    let lines: Vec<&str> = text.lines()
+        .map(str::trim)
+        .filter(|l| !l.is_empty() && !l.starts_with("```"))
+        .collect();
+    let Some(first) = lines.first() else {
+        return Err("a área de transferência está vazia".into());
+    };
+
+    if first.starts_with('{') {
+        return parse_json(&lines.join("\n"));
+    }
+    if lines.iter().any(|l| l.get(..5).is_some_and(|k| k.eq_ignore_ascii_case("host ") || k.eq_ignore_ascii_case("host="))) {
+        return parse_block(&lines);
+    }
+    let command = first.trim_start_matches(['$', '#', '>']).trim_start();
+    if command.starts_with("ssh://") {
+        return parse_uri(command);
+    }
+    if command.split_whitespace().next() == Some("ssh") {
+        return parse_command(command);
+    }
+    Err("formato não reconhecido: esperado um bloco Host, um comando ssh ou uma URI ssh://".into())
+}
+
+// Só o primeiro bloco Host; o que vier depois de outro Host ou Match fica de fora
+fn parse_block(lines: &[&str]) -> Result<ImportedHost, Box<dyn std::error::Error>> {
+    let mut host: Option<ImportedHost> = None;
+    for line in lines.iter().filter(|l| !l.starts_with('#')) {
+        let Some((key, value)) = ssh_config::split_line(line) else {
+            continue;
+        };
+        if key.eq_ignore_ascii_case("host") || key.eq_ignore_ascii_case("match") {
+            if host.is_some() || key.eq_ignore_ascii_case("match") {
+                break;
+            }
+            host = Some(ImportedHost::new(value, "bloco ssh_config"));
+        } else if let Some(host) = &mut host {
+            host.push(key, &ssh_config::unquote(key, value));
+        }
+    }
+    host.filter(|h| !h.alias.is_empty()).ok_or_else(|| "bloco Host sem nome".into())
+}
+
+// ssh://[usuário@]host[:porta]; o caminho, se houver, é ignorado
+fn parse_uri(uri: &str) -> Result<ImportedHost, Box<dyn std::error::Error>> {
+    let rest = uri.trim_start_matches("ssh://");
+    let authority = rest.split('/').next().unwrap_or_default();
+    let (user, address) = match authority.rsplit_once('@') {
+        // Parâmetros depois de ';' (ex.: fingerprint) não viram diretivas
+        Some((user, address)) => (Some(user.split(';').next().unwrap_or(user)), address),
+        None => (None, authority),
+    };
+    let (hostname, port) = split_host_port(address);
+    if hostname.is_empty() {
+        return Err("URI ssh:// sem host".into());
+    }
+    let mut host = ImportedHost::new(alias_for(hostname), "URI ssh://");
+    host.push("hostname", hostname);
+    if let Some(user) = user {
+        host.push("user", user);
+    }
+    if let Some(port) = port.filter(|p| *p != "22") {
+        host.push("port", port);
+    }
+    Ok(host)
+}
+
+fn split_host_port(address: &str) -> (&str, Option<&str>) {
+    if let Some(rest) = address.strip_prefix('[') {
+        let (hostname, after) = rest.split_once(']').unwrap_or((rest, ""));
+        return (hostname, after.strip_prefix(':'));
+    }
+    match address.split_once(':') {
+        Some((hostname, port)) => (hostname, Some(port)),
+        None => (address, None),
+    }
+}
+
+// Nome curto para o alias: o primeiro rótulo do hostname, ou o IP inteiro
+fn alias_for(hostname: &str) -> &str {
+    let is_ip = hostname.contains(':') || hostname.chars().all(|c| c.is_ascii_digit() || c == '.');
+    if is_ip { hostname } else { hostname.split('.').next().unwrap_or(hostname) }
+}
+
+// `ssh [opções] [usuário@]destino [comando]`: o comando remoto é descartado
+fn parse_command(line: &str) -> Result<ImportedHost, Box<dyn std::error::Error>> {
+    // Opções do ssh que recebem argumento
+    const WITH_ARG: &str = "BbcDEeFIiJLlmOoPpQRSWw";
+    let words = ssh_config::split_args(line).ok_or("aspas sem fechar no comando ssh")?;
+    let mut options: Vec<(&str, String)> = Vec::new();
+    let mut destination = None;
+    let mut args = words.iter().skip(1);
+    while let Some(arg) = args.next() {
+        if arg == "--" {
+            destination = args.next();
+            break;
+        }
+        let Some(flags) = arg.strip_prefix('-').filter(|f| !f.is_empty()) else {
+            destination = Some(arg);
+            break;
+        };
+        // Opções podem vir agrupadas (-AC) e o argumento colado (-p2222)
+        for (i, flag) in flags.char_indices() {
+            if WITH_ARG.contains(flag) {
+                let attached = &flags[i + 1..];
+                let value = if attached.is_empty() { args.next().cloned().unwrap_or_default() } else { attached.to_string() };
+                options.push((&flags[i..i + 1], value));
+                break;
+            }
+            options.push((&flags[i..i + 1], String::new()));
+        }
+    }
+    let destination = destination.ok_or("comando ssh sem destino")?;
+    if destination.starts_with("ssh://") {
+        let mut host = parse_uri(destination)?;
+        host.source = "comando ssh";
+        apply_flags(&mut host, &options);
+        return Ok(host);
+    }
+
+    let (user, hostname) = match destination.rsplit_once('@') {
+        Some((user, hostname)) => (Some(user), hostname),
+        None => (None, destination.as_str()),
+    };
+    let mut host = ImportedHost::new(alias_for(hostname), "comando ssh");
+    host.push("hostname", hostname);
+    if let Some(user) = user {
+        host.push("user", user);
+    }
+    apply_flags(&mut host, &options);
+    Ok(host)
+}
+
+fn apply_flags(host: &mut ImportedHost, options: &[(&str, String)]) {
+    for (flag, value) in options {
+        match *flag {
+            "p" if value != "22" && !host.directives.iter().any(|(k, _)| k == "Port") => host.push("port", value),
+            "l" if !host.directives.iter().any(|(k, _)| k == "User") => host.push("user", value),
+            "i" => host.push("identityfile", value),
+            "J" => host.push("proxyjump", value),
+            "L" => host.push("localforward", &value.replacen(':', " ", 1)),
+            "R" => host.push("remoteforward", &value.replacen(':', " ", 1)),
+            "D" => host.push("dynamicforward", value),
+            "A" => host.push("forwardagent", "yes"),
+            "C" => host.push("compression", "yes"),
+            "o" => {
+                if let Some((key, value)) = ssh_config::split_line(value) {
+                    host.push(key, &ssh_config::unquote(key, value));
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+// O inverso de `json_blob`
+fn parse_json(text: &str) -> Result<ImportedHost, Box<dyn std::error::Error>> {
+    let value: serde_json::Value = serde_json::from_str(text)?;
+    let field = |key: &str| match value.get(key) {
+        Some(serde_json::Value::String(s)) => Some(s.clone()),
+        Some(serde_json::Value::Number(n)) => Some(n.to_string()),
+        _ => None,
+    };
+    let hostname = field("hostname").or_else(|| field("host")).ok_or("JSON sem hostname")?;
+    let alias = field("host").unwrap_or_else(|| alias_for(&hostname).to_string());
+    let mut host = ImportedHost::new(&alias, "JSON");
+    host.push("hostname", &hostname);
+    for (key, directive) in [("user", "user"), ("port", "port"), ("proxy_jump", "proxyjump")] {
+        if let Some(value) = field(key) {
+            host.push(directive, &value);
+        }
+    }
+    Ok(host)
+}
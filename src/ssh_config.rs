@@ -1,138 +1,388 @@
-use std::collections::HashMap;
 use std::fs;
 use std::path::{Path, PathBuf};
 
+use crate::backup;
+use crate::effective;
+
+// Níveis de Include aceitos, o mesmo limite do ssh
+const MAX_INCLUDE_DEPTH: usize = 16;
+
 #[derive(Debug, Clone)]
 pub struct SshHost {
     pub name: String,
     pub hostname: Option<String>,
     pub user: Option<String>,
     pub port: Option<u16>,
-    pub identity_file: Option<String>,
-    pub other_options: HashMap<String, String>,
+    // Todos os IdentityFile do bloco, na ordem em que o ssh os tenta
+    pub identity_files: Vec<String>,
+    pub local_forwards: Vec<String>,
+    pub other_options: HostOptions,
+    // Comentários logo acima do `Host`, uma linha por comentário
+    pub description: Option<String>,
     pub is_separator: bool,
+    // Só padrões (`Host *`, `Host *.internal !db`): fornece opções, não é um destino
+    pub is_pattern: bool,
     pub source_dir: Option<String>,
+    pub source_file: Option<PathBuf>,
+    pub line: usize,
+    // Posição na ordem de carga, que o ssh segue mesmo com as seções reordenadas
+    pub load_index: usize,
+}
+
+// Demais opções do bloco na ordem do arquivo, com repetições (vários SendEnv,
+// RemoteForward) e a grafia original do nome
+#[derive(Debug, Clone, Default)]
+pub struct HostOptions(Vec<(String, String)>);
+
+impl HostOptions {
+    // Primeiro valor, como o ssh usa; o nome não diferencia maiúsculas
+    pub fn get(&self, key: &str) -> Option<&String> {
+        self.0.iter().find(|(k, _)| k.eq_ignore_ascii_case(key)).map(|(_, v)| v)
+    }
+
+    pub fn push(&mut self, key: &str, value: &str) {
+        self.0.push((key.to_string(), value.to_string()));
+    }
+
+    // Troca o primeiro valor da opção (ou acrescenta), mantendo a posição
+    pub fn set(&mut self, key: &str, value: &str) {
+        match self.0.iter_mut().find(|(k, _)| k.eq_ignore_ascii_case(key)) {
+            Some(entry) => entry.1 = value.to_string(),
+            None => self.push(&canonical_key(key), value),
+        }
+    }
+
+    // (nome como escrito, valor), na ordem do arquivo
+    pub fn iter(&self) -> impl Iterator<Item = (&String, &String)> {
+        self.0.iter().map(|(k, v)| (k, v))
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ConfigFile {
+    pub path: PathBuf,
+    pub depth: usize,
+    pub host_count: usize,
+    pub first_host: Option<usize>,
+    pub missing: bool,
 }
 
 pub struct SshConfig {
     pub hosts: Vec<SshHost>,
+    pub files: Vec<ConfigFile>,
+    // Problemas contornados na leitura (ex.: linhas que não são UTF-8)
+    pub warnings: Vec<String>,
 }
 
 impl SshConfig {
-    pub fn load() -> Result<Self, Box<dyn std::error::Error>> {
-        let home_dir = home::home_dir().ok_or("Could not find home directory")?;
-        let workdir = home_dir.join(".ssh");
-        Self::load_from_workdir(&workdir)
-    }
-
     pub fn load_from_workdir(workdir: &Path) -> Result<Self, Box<dyn std::error::Error>> {
         let config_path = workdir.join("config");
-        Self::load_file(&config_path)
+        let mut config = Self::load_file(&config_path, 0, &[])?;
+        for (i, host) in config.hosts.iter_mut().enumerate() {
+            host.load_index = i;
+        }
+        // Chaves ausentes só falhariam na conexão, com um erro pouco claro do ssh
+        config.warnings.extend(crate::keys::identity_warnings(&config.hosts));
+        Ok(config)
     }
 
-    fn load_file(path: &Path) -> Result<Self, Box<dyn std::error::Error>> {
-        let content = fs::read_to_string(path)?;
-        let base_dir = path.parent().unwrap_or(Path::new("/"));
-        let source_dir = path.parent().and_then(|p| p.file_name()).and_then(|n| n.to_str()).map(|s| s.to_string());
-        Self::parse(&content, base_dir, source_dir)
+    // Reordena as seções de include (separador e os hosts vindos do include)
+    // pela posição da pasta em `order`; as demais mantêm a ordem dos Include e
+    // os hosts do config principal não saem do lugar
+    pub fn arrange_sections(&mut self, order: &[String]) {
+        if order.is_empty() {
+            return;
+        }
+        let main = self.files.first().map(|f| f.path.clone());
+        let mut chunks: Vec<(bool, Vec<usize>)> = Vec::new();
+        for (i, host) in self.hosts.iter().enumerate() {
+            let from_main = host.source_file == main;
+            match chunks.last_mut() {
+                Some((true, chunk)) if !host.is_separator && !from_main => chunk.push(i),
+                Some((false, chunk)) if from_main => chunk.push(i),
+                _ => chunks.push((host.is_separator, vec![i])),
+            }
+        }
+
+        let rank = |chunk: &Vec<usize>| {
+            let folder = self.hosts[chunk[0]].source_dir.as_deref();
+            order.iter().position(|o| Some(o.as_str()) == folder).unwrap_or(order.len())
+        };
+        let mut sections: Vec<Vec<usize>> = chunks.iter().filter(|(s, _)| *s).map(|(_, c)| c.clone()).collect();
+        sections.sort_by_key(rank);
+        let mut sections = sections.into_iter();
+        let arranged: Vec<usize> = chunks.into_iter()
+            .flat_map(|(is_section, chunk)| if is_section { sections.next().unwrap_or_default() } else { chunk })
+            .collect();
+
+        let mut position = vec![0; self.hosts.len()];
+        for (new, &old) in arranged.iter().enumerate() {
+            position[old] = new;
+        }
+        let mut hosts: Vec<Option<SshHost>> = std::mem::take(&mut self.hosts).into_iter().map(Some).collect();
+        self.hosts = arranged.iter().filter_map(|&i| hosts[i].take()).collect();
+        for file in &mut self.files {
+            file.first_host = file.first_host.map(|i| position[i]);
+        }
+    }
+
+    // Leva os blocos só de padrões (`Host *`...) para uma seção "Defaults" no
+    // fim da lista; a ordem de carga (`load_index`) não muda
+    pub fn gather_defaults(&mut self) {
+        // Sem padrões ou já separados (o separador da seção também é `is_pattern`)
+        if !self.hosts.iter().any(|h| h.is_pattern && !h.is_separator) || self.hosts.iter().any(|h| h.is_pattern && h.is_separator) {
+            return;
+        }
+        let (defaults, hosts): (Vec<usize>, Vec<usize>) = (0..self.hosts.len())
+            .partition(|&i| self.hosts[i].is_pattern && !self.hosts[i].is_separator);
+        let mut position = vec![0; self.hosts.len()];
+        for (new, &old) in hosts.iter().chain(&defaults).enumerate() {
+            // O separador entra antes dos padrões
+            position[old] = if new >= hosts.len() { new + 1 } else { new };
+        }
+        let mut old: Vec<Option<SshHost>> = std::mem::take(&mut self.hosts).into_iter().map(Some).collect();
+        self.hosts = hosts.iter().filter_map(|&i| old[i].take()).collect();
+        self.hosts.push(SshHost {
+            name: "── Defaults ──".to_string(),
+            hostname: None,
+            user: None,
+            port: None,
+            identity_files: Vec::new(),
+            local_forwards: Vec::new(),
+            other_options: HostOptions::default(),
+            description: None,
+            is_separator: true,
+            is_pattern: true,
+            source_dir: None,
+            source_file: None,
+            line: 0,
+            load_index: 0,
+        });
+        self.hosts.extend(defaults.iter().filter_map(|&i| old[i].take()));
+        for file in &mut self.files {
+            file.first_host = file.first_host.map(|i| position[i]);
+        }
     }
 
+    // `chain`: arquivos (canônicos) que estão incluindo este, do principal para baixo
+    fn load_file(path: &Path, depth: usize, chain: &[PathBuf]) -> Result<Self, Box<dyn std::error::Error>> {
+        let (content, latin1_lines) = decode(&fs::read(path)?);
+        let mut config = Self::parse(&content, path, depth, chain)?;
+        if !latin1_lines.is_empty() {
+            let lines: Vec<String> = latin1_lines.iter().map(|l| l.to_string()).collect();
+            config.warnings.insert(0, format!(
+                "{}: linha(s) {} não são UTF-8 e foram lidas como Latin-1",
+                path.display(),
+                lines.join(", "),
+            ));
+        }
+        Ok(config)
+    }
 
+    // Conteúdo normalizado de um arquivo de configuração, para ler e regravar
+    pub fn read_text(path: &Path) -> std::io::Result<String> {
+        Ok(decode(&fs::read(path)?).0)
+    }
 
-    fn parse(content: &str, base_dir: &Path, source_dir: Option<String>) -> Result<Self, Box<dyn std::error::Error>> {
+    fn parse(content: &str, path: &Path, depth: usize, chain: &[PathBuf]) -> Result<Self, Box<dyn std::error::Error>> {
+        let base_dir = path.parent().unwrap_or(Path::new("/"));
+        let mut chain = chain.to_vec();
+        chain.push(path.canonicalize().unwrap_or_else(|_| path.to_path_buf()));
+        let source_dir = path.parent().and_then(|p| p.file_name()).and_then(|n| n.to_str()).map(|s| s.to_string());
         let mut hosts = Vec::new();
+        // O próprio arquivo vem antes dos seus includes na ordem de carga
+        let mut files = vec![ConfigFile {
+            path: path.to_path_buf(),
+            depth,
+            host_count: 0,
+            first_host: None,
+            missing: false,
+        }];
         let mut current_host: Option<SshHost> = None;
+        let mut warnings = Vec::new();
+        // Comentários seguidos desde a última linha em branco ou diretiva
+        let mut comments: Vec<String> = Vec::new();
 
-        for line in content.lines() {
+        for (line_index, line) in content.lines().enumerate() {
             let line = line.trim();
-            if line.is_empty() || line.starts_with('#') {
+            if line.is_empty() {
+                comments.clear();
                 continue;
             }
-
-            let parts: Vec<&str> = line.splitn(2, ' ').collect();
-            if parts.len() != 2 {
+            if let Some(comment) = line.strip_prefix('#') {
+                comments.push(comment.trim().to_string());
                 continue;
             }
+            let description = description_from(&std::mem::take(&mut comments));
 
-            let key = parts[0].to_lowercase();
-            let value = parts[1].trim();
+            let Some((written_key, raw)) = split_line(line) else {
+                continue;
+            };
+            let key = written_key.to_lowercase();
+            let value = &unquote(&key, raw);
 
             match key.as_str() {
                 "include" => {
                     if let Some(host) = current_host.take() {
-                        hosts.push(host);
+                        files[0].host_count += push_block(&mut hosts, host);
                     }
-                    let include_path = Self::resolve_include_path(value, base_dir)?;
-                    if include_path.exists() {
+                    let include_paths = match Self::expand_include(raw, base_dir) {
+                        Ok(paths) => paths,
+                        Err(e) => {
+                            warnings.push(format!("{}:{}: Include {}: {}", path.display(), line_index + 1, raw, e));
+                            Vec::new()
+                        }
+                    };
+                    for include_path in include_paths {
+                        if !include_path.exists() {
+                            files.push(ConfigFile {
+                                path: include_path,
+                                depth: depth + 1,
+                                host_count: 0,
+                                first_host: None,
+                                missing: true,
+                            });
+                            continue;
+                        }
+                        // Como o ssh, que para em 16 níveis; um arquivo que inclui a si
+                        // mesmo (`Include *` no config principal) seria lido sem fim
+                        let location = format!("{}:{}: Include {}", path.display(), line_index + 1, raw);
+                        let canonical = include_path.canonicalize().unwrap_or_else(|_| include_path.clone());
+                        if chain.contains(&canonical) {
+                            warnings.push(format!("{}: {} já está sendo lido (inclusão circular) e foi ignorado", location, include_path.display()));
+                            continue;
+                        }
+                        if depth + 1 > MAX_INCLUDE_DEPTH {
+                            warnings.push(format!("{}: mais de {} níveis de Include; {} foi ignorado", location, MAX_INCLUDE_DEPTH, include_path.display()));
+                            continue;
+                        }
+                        let included_config = match Self::load_file(&include_path, depth + 1, &chain) {
+                            Ok(config) => config,
+                            Err(e) => {
+                                warnings.push(format!("{}: {}: {}", location, include_path.display(), e));
+                                continue;
+                            }
+                        };
+
+                        // Um separador por arquivo, para as ações da seção irem ao
+                        // arquivo certo; arquivo sem hosts só ganha um se for o
+                        // config de uma pasta (`Include *` não lista known_hosts etc.)
                         let dir_name = include_path.parent()
                             .and_then(|p| p.file_name())
                             .and_then(|n| n.to_str())
                             .unwrap_or("unknown")
                             .to_string();
-                        
-                        hosts.push(SshHost {
-                            name: format!("── {} ──", dir_name),
-                            hostname: None,
-                            user: None,
-                            port: None,
-                            identity_file: None,
-                            other_options: HashMap::new(),
-                            is_separator: true,
-                            source_dir: Some(dir_name.clone()),
-                        });
-                        
-                        let included_config = Self::load_file(&include_path)?;
+                        let file_name = include_path.file_name().and_then(|n| n.to_str()).unwrap_or_default();
+                        let has_hosts = included_config.hosts.iter().any(|h| !h.is_separator);
+                        if has_hosts || file_name == "config" {
+                            let label = if file_name == "config" { dir_name.clone() } else { format!("{}/{}", dir_name, file_name) };
+                            hosts.push(SshHost {
+                                name: format!("── {} ──", label),
+                                hostname: None,
+                                user: None,
+                                port: None,
+                                identity_files: Vec::new(),
+                                local_forwards: Vec::new(),
+                                other_options: HostOptions::default(),
+                                description: None,
+                                is_separator: true,
+                                is_pattern: false,
+                                source_dir: Some(dir_name),
+                                source_file: Some(include_path.clone()),
+                                line: 0,
+                                load_index: 0,
+                            });
+                        }
+
+                        let offset = hosts.len();
                         hosts.extend(included_config.hosts);
+                        warnings.extend(included_config.warnings);
+                        files.extend(included_config.files.into_iter().map(|mut file| {
+                            file.first_host = file.first_host.map(|i| i + offset);
+                            file
+                        }));
                     }
                 }
                 "host" => {
                     if let Some(host) = current_host.take() {
-                        hosts.push(host);
+                        files[0].host_count += push_block(&mut hosts, host);
                     }
+                    files[0].first_host.get_or_insert(hosts.len());
                     current_host = Some(SshHost {
                         name: value.to_string(),
                         hostname: None,
                         user: None,
                         port: None,
-                        identity_file: None,
-                        other_options: HashMap::new(),
+                        identity_files: Vec::new(),
+                        local_forwards: Vec::new(),
+                        other_options: HostOptions::default(),
+                        description,
                         is_separator: false,
+                        is_pattern: false,
                         source_dir: source_dir.clone(),
+                        source_file: Some(path.to_path_buf()),
+                        line: line_index + 1,
+                        load_index: 0,
                     });
                 }
-                "hostname" => {
-                    if let Some(ref mut host) = current_host {
-                        host.hostname = Some(value.to_string());
-                    }
-                }
-                "user" => {
+                // O ssh usa o primeiro valor; as repetições ficam nas demais
+                // opções para não sumirem quando o bloco é regravado
+                "hostname" | "user" | "port" => {
                     if let Some(ref mut host) = current_host {
-                        host.user = Some(value.to_string());
+                        match key.as_str() {
+                            "hostname" if host.hostname.is_none() => host.hostname = Some(value.to_string()),
+                            "user" if host.user.is_none() => host.user = Some(value.to_string()),
+                            "port" if host.port.is_none() && value.parse::<u16>().is_ok() => host.port = value.parse().ok(),
+                            _ => host.other_options.push(written_key, value),
+                        }
                     }
                 }
-                "port" => {
+                "identityfile" => {
                     if let Some(ref mut host) = current_host {
-                        host.port = value.parse().ok();
+                        host.identity_files.push(value.to_string());
                     }
                 }
-                "identityfile" => {
+                "localforward" => {
                     if let Some(ref mut host) = current_host {
-                        host.identity_file = Some(value.to_string());
+                        host.local_forwards.push(value.to_string());
                     }
                 }
                 _ => {
                     if let Some(ref mut host) = current_host {
-                        host.other_options.insert(key, value.to_string());
+                        host.other_options.push(written_key, value);
                     }
                 }
             }
         }
 
         if let Some(host) = current_host {
-            hosts.push(host);
+            files[0].host_count += push_block(&mut hosts, host);
         }
 
-        Ok(Self { hosts })
+        Ok(Self { hosts, files, warnings })
+    }
+
+    // Arquivos de uma linha Include: vários padrões separados por espaço, cada
+    // um com curingas como no glob(3) do ssh (`config.d/*`), em ordem alfabética.
+    // Padrões sem curinga entram mesmo sem existir, para aparecerem como ausentes
+    fn expand_include(raw: &str, base_dir: &Path) -> Result<Vec<PathBuf>, Box<dyn std::error::Error>> {
+        let patterns = split_args(raw).ok_or("aspas sem fechar")?;
+        let options = glob::MatchOptions { require_literal_leading_dot: true, ..Default::default() };
+        let mut paths = Vec::new();
+        for pattern in patterns {
+            let path = Self::resolve_include_path(&pattern, base_dir)?;
+            if !pattern.contains(['*', '?', '[']) {
+                paths.push(path);
+                continue;
+            }
+            let mut matched: Vec<PathBuf> = glob::glob_with(&path.to_string_lossy(), options)?
+                .flatten()
+                .filter(|p| p.is_file())
+                .collect();
+            matched.sort();
+            paths.extend(matched);
+        }
+        Ok(paths)
     }
 
     fn resolve_include_path(include_value: &str, base_dir: &Path) -> Result<PathBuf, Box<dyn std::error::Error>> {
@@ -146,4 +396,535 @@ impl SshConfig {
         };
         Ok(path)
     }
-}
\ No newline at end of file
+
+    // Reescreve (ou remove, se `new` for None) os caminhos das linhas Include que
+    // apontam para `old` ou para dentro dele. Devolve os arquivos reescritos
+    pub fn rewrite_includes(config_files: &[PathBuf], old: &Path, new: Option<&Path>) -> Result<Vec<PathBuf>, Box<dyn std::error::Error>> {
+        let mut rewritten = Vec::new();
+        for file in config_files.iter().filter(|f| f.exists()) {
+            let base_dir = file.parent().unwrap_or(Path::new("/"));
+            let content = Self::read_text(file)?;
+            let mut changed = false;
+            let mut new_lines = Vec::new();
+
+            for line in content.lines() {
+                let (key, value) = Self::split_directive(line);
+                // Cada caminho da linha é trocado (ou tirado) sozinho; a linha só
+                // some quando não sobra nenhum
+                let patterns = if key == "include" { split_args(&value) } else { None };
+                let Some(patterns) = patterns else {
+                    new_lines.push(line.to_string());
+                    continue;
+                };
+                let mut kept = Vec::new();
+                let mut line_changed = false;
+                for pattern in patterns {
+                    let resolved = Self::resolve_include_path(&pattern, base_dir)?;
+                    match (resolved.strip_prefix(old), new) {
+                        (Ok(rest), Some(new)) => kept.push(Self::include_value(&new.join(rest), &pattern, base_dir)),
+                        (Ok(_), None) => {}
+                        (Err(_), _) => {
+                            kept.push(pattern);
+                            continue;
+                        }
+                    }
+                    line_changed = true;
+                }
+                if !line_changed {
+                    new_lines.push(line.to_string());
+                    continue;
+                }
+                changed = true;
+                if !kept.is_empty() {
+                    let indent = &line[..line.len() - line.trim_start().len()];
+                    let values: Vec<String> = kept.iter().map(|p| quote("include", p)).collect();
+                    new_lines.push(format!("{}Include {}", indent, values.join(" ")));
+                }
+            }
+
+            if changed {
+                let mut new_content = new_lines.join("\n");
+                new_content.push('\n');
+                backup::write(file, new_content)?;
+                rewritten.push(file.clone());
+            }
+        }
+        Ok(rewritten)
+    }
+
+    // Mantém o estilo do Include original (~, absoluto ou relativo)
+    fn include_value(path: &Path, original: &str, base_dir: &Path) -> String {
+        if original.starts_with('~') {
+            if let Some(rest) = home::home_dir().and_then(|h| path.strip_prefix(h).ok().map(|p| p.to_path_buf())) {
+                return format!("~/{}", rest.display());
+            }
+        } else if !original.starts_with('/') {
+            if let Ok(rest) = path.strip_prefix(base_dir) {
+                return rest.display().to_string();
+            }
+        }
+        path.display().to_string()
+    }
+
+    pub fn set_host_option(path: &Path, host_name: &str, key: &str, value: &str) -> Result<(), Box<dyn std::error::Error>> {
+        let content = Self::read_text(path)?;
+        let mut lines: Vec<String> = content.lines().map(|l| l.to_string()).collect();
+
+        let (start, end) = Self::find_host_block(&lines, host_name)
+            .ok_or_else(|| format!("Host {} não encontrado em {}", host_name, path.display()))?;
+
+        let new_line = format!("    {} {}", key, quote(key, value));
+        let existing = (start + 1..end).find(|&i| Self::split_directive(&lines[i]).0 == key.to_lowercase());
+
+        match existing {
+            Some(i) => lines[i] = new_line,
+            None => {
+                // Inserir após a última linha não vazia do bloco
+                let mut insert_at = end;
+                while insert_at > start + 1 && lines[insert_at - 1].trim().is_empty() {
+                    insert_at -= 1;
+                }
+                lines.insert(insert_at, new_line);
+            }
+        }
+
+        let mut new_content = lines.join("\n");
+        new_content.push('\n');
+        backup::write(path, new_content)?;
+        Ok(())
+    }
+
+    // Troca o valor antigo (o que `is_old` aceita) de uma diretiva do bloco por
+    // `new`, mantendo as outras ocorrências (IdentityFile pode se repetir);
+    // false se o valor não estava lá
+    pub fn replace_host_value(path: &Path, host_name: &str, key: &str, is_old: impl Fn(&str) -> bool, new: &str) -> Result<bool, Box<dyn std::error::Error>> {
+        let content = Self::read_text(path)?;
+        let mut lines: Vec<String> = content.lines().map(|l| l.to_string()).collect();
+
+        let (start, end) = Self::find_host_block(&lines, host_name)
+            .ok_or_else(|| format!("Host {} não encontrado em {}", host_name, path.display()))?;
+        let key_lower = key.to_lowercase();
+        let existing = (start + 1..end).find(|&i| {
+            let (k, v) = Self::split_directive(&lines[i]);
+            k == key_lower && is_old(&unquote(&k, &v))
+        });
+        let Some(i) = existing else {
+            return Ok(false);
+        };
+        let indent: String = lines[i].chars().take_while(|c| c.is_whitespace()).collect();
+        let written_key = split_line(&lines[i]).map(|(k, _)| k.to_string()).unwrap_or_else(|| key.to_string());
+        lines[i] = format!("{}{} {}", indent, written_key, quote(key, new));
+
+        let mut new_content = lines.join("\n");
+        new_content.push('\n');
+        backup::write(path, new_content)?;
+        Ok(true)
+    }
+
+    // Acrescenta um bloco Host no fim do arquivo, criando o diretório se preciso
+    pub fn append_host_block(path: &Path, host_name: &str, description: Option<&str>, directives: &[(String, String)]) -> Result<(), Box<dyn std::error::Error>> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        // Bytes como estão: linhas em Latin-1 acima do bloco não são convertidas
+        let mut content = match fs::read(path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Vec::new(),
+            Err(e) => return Err(e.into()),
+        };
+        let mut block = String::new();
+        if !content.is_empty() {
+            block.push('\n'); // Linha em branco se arquivo não estiver vazio
+        }
+        for comment in description_lines(description) {
+            block.push_str(&format!("{}\n", comment));
+        }
+        block.push_str(&format!("Host {}\n", host_name));
+        for (key, value) in directives {
+            block.push_str(&format!("    {} {}\n", key, quote(key, value)));
+        }
+        content.extend_from_slice(block.as_bytes());
+        backup::write(path, content)
+    }
+
+    // Remove o bloco e os comentários logo acima dele
+    // `line` é a do `Host` (`SshHost::line`), para o caso de o arquivo repetir o nome; 0 pega o primeiro
+    pub fn remove_host_block(path: &Path, host_name: &str, line: usize) -> Result<(), Box<dyn std::error::Error>> {
+        let content = Self::read_text(path)?;
+        let mut lines: Vec<String> = content.lines().map(|l| l.to_string()).collect();
+
+        let (start, end) = Self::find_host_block_at(&lines, host_name, line)
+            .ok_or_else(|| format!("Host {} não encontrado em {}", host_name, path.display()))?;
+        // Os outros padrões da mesma linha continuam com o bloco
+        let remaining: Vec<String> = Self::split_directive(&lines[start]).1
+            .split_whitespace()
+            .filter(|p| !host_name.split_whitespace().any(|n| n == *p))
+            .map(String::from)
+            .collect();
+        if remaining.iter().any(|p| !p.starts_with('!')) {
+            let indent: String = lines[start].chars().take_while(|c| c.is_whitespace()).collect();
+            let written_key = split_line(&lines[start]).map(|(k, _)| k.to_string()).unwrap_or_else(|| "Host".to_string());
+            lines[start] = format!("{}{} {}", indent, written_key, remaining.join(" "));
+            let mut new_content = lines.join("\n");
+            new_content.push('\n');
+            return backup::write(path, new_content);
+        }
+        let mut first = start;
+        while first > 0 && lines[first - 1].trim().starts_with('#') {
+            first -= 1;
+        }
+        lines.drain(first..end);
+        // Não deixa duas linhas em branco onde o bloco estava
+        if first > 0 && lines.get(first).is_some_and(|l| l.trim().is_empty()) && lines[first - 1].trim().is_empty() {
+            lines.remove(first);
+        }
+        while lines.last().is_some_and(|l| l.trim().is_empty()) {
+            lines.pop();
+        }
+
+        let mut new_content = lines.join("\n");
+        if !new_content.is_empty() {
+            new_content.push('\n');
+        }
+        backup::write(path, new_content)?;
+        Ok(())
+    }
+
+    // Garante um `Include` para `new_config_path` no início do config principal
+    // Devolve se o config principal foi alterado
+    pub fn add_include(main_config: &Path, new_config_path: &Path) -> Result<bool, Box<dyn std::error::Error>> {
+        let include_line = format!("Include {}", quote("include", &new_config_path.display().to_string()));
+        let content = if main_config.exists() { Self::read_text(main_config)? } else { String::new() };
+        if content.lines().any(|l| l.trim() == include_line) {
+            return Ok(false);
+        }
+        if let Some(parent) = main_config.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let mut new_content = format!("{}\n", include_line);
+        if !content.is_empty() {
+            new_content.push('\n'); // Linha em branco
+            new_content.push_str(&content);
+        }
+        backup::write(main_config, new_content)?;
+        Ok(true)
+    }
+
+    pub fn read_host_block(path: &Path, host_name: &str) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+        let content = Self::read_text(path)?;
+        let lines: Vec<String> = content.lines().map(|l| l.to_string()).collect();
+
+        let (start, mut end) = Self::find_host_block(&lines, host_name)
+            .ok_or_else(|| format!("Host {} não encontrado em {}", host_name, path.display()))?;
+
+        // Incluir os comentários logo acima do Host
+        let mut first = start;
+        while first > 0 && lines[first - 1].trim().starts_with('#') {
+            first -= 1;
+        }
+        while end > start + 1 && lines[end - 1].trim().is_empty() {
+            end -= 1;
+        }
+        Ok(lines[first..end].to_vec())
+    }
+
+    // Retorna (linha do Host, fim do bloco); o bloco termina no próximo Host/Match/Include
+    fn find_host_block(lines: &[String], host_name: &str) -> Option<(usize, usize)> {
+        Self::find_host_block_at(lines, host_name, 0)
+    }
+
+    // O bloco que começa na linha `line` (1-based), se for desse host; senão o
+    // primeiro com o nome. Um host separado de uma linha com vários padrões
+    // (`web1` de `Host web1 web2`) acha o bloco da linha toda
+    fn find_host_block_at(lines: &[String], host_name: &str, line: usize) -> Option<(usize, usize)> {
+        let is_exact = |l: &String| {
+            let (k, v) = Self::split_directive(l);
+            k == "host" && v == host_name
+        };
+        let is_block = |l: &String| {
+            let (k, v) = Self::split_directive(l);
+            k == "host" && (v == host_name || host_name.split_whitespace().all(|p| v.split_whitespace().any(|q| q == p)))
+        };
+        let start = match line.checked_sub(1).filter(|&i| lines.get(i).is_some_and(is_block)) {
+            Some(start) => start,
+            None => lines.iter().position(is_exact).or_else(|| lines.iter().position(is_block))?,
+        };
+        let mut end = lines[start + 1..].iter()
+            .position(|l| matches!(Self::split_directive(l).0.as_str(), "host" | "match" | "include"))
+            .map(|p| start + 1 + p)
+            .unwrap_or(lines.len());
+        // Comentários colados no próximo `Host` são a descrição dele
+        if end < lines.len() && Self::split_directive(&lines[end]).0 == "host" {
+            while end > start + 1 && lines[end - 1].trim().starts_with('#') {
+                end -= 1;
+            }
+        }
+        Some((start, end))
+    }
+
+    fn split_directive(line: &str) -> (String, String) {
+        let line = line.trim();
+        if line.starts_with('#') {
+            return (String::new(), String::new());
+        }
+        match split_line(line) {
+            Some((key, value)) => (key.to_lowercase(), value.to_string()),
+            None => (line.to_lowercase(), String::new()),
+        }
+    }
+}
+
+// `Host web1 web2 *.internal` vira uma entrada por nome concreto (`web1`,
+// `web2`) e uma com os padrões restantes (`*.internal` e as negações), todas
+// com as mesmas opções e a linha do bloco; retorna quantas entraram
+fn push_block(hosts: &mut Vec<SshHost>, mut host: SshHost) -> usize {
+    host.is_pattern = host.name.split_whitespace().all(effective::is_wildcard);
+    let (concrete, rest): (Vec<&str>, Vec<&str>) = host.name
+        .split_whitespace()
+        .partition(|p| !effective::is_wildcard(p));
+    let has_patterns = rest.iter().any(|p| !p.starts_with('!'));
+    if concrete.len() + usize::from(has_patterns) < 2 {
+        hosts.push(host);
+        return 1;
+    }
+    let mut names: Vec<String> = concrete.iter().map(|p| p.to_string()).collect();
+    if has_patterns {
+        names.push(rest.join(" "));
+    }
+    let count = names.len();
+    for name in names {
+        let is_pattern = name.split_whitespace().all(effective::is_wildcard);
+        hosts.push(SshHost { name, is_pattern, ..host.clone() });
+    }
+    count
+}
+
+// Arquivos vindos do Windows: sem BOM, fim de linha LF e, nas linhas que não
+// são UTF-8 (comentários em Latin-1), cada byte lido como Latin-1. Devolve o
+// texto e os números (a partir de 1) das linhas decodificadas assim
+fn decode(bytes: &[u8]) -> (String, Vec<usize>) {
+    let bytes = bytes.strip_prefix(b"\xEF\xBB\xBF").unwrap_or(bytes);
+    let mut text = String::with_capacity(bytes.len());
+    let mut latin1_lines = Vec::new();
+    for (i, line) in bytes.split(|&b| b == b'\n').enumerate() {
+        if i > 0 {
+            text.push('\n');
+        }
+        let line = line.strip_suffix(b"\r").unwrap_or(line);
+        match std::str::from_utf8(line) {
+            Ok(line) => text.push_str(line),
+            Err(_) => {
+                text.extend(line.iter().map(|&b| b as char));
+                latin1_lines.push(i + 1);
+            }
+        }
+    }
+    (text, latin1_lines)
+}
+
+// Descrição de um host a partir dos comentários acima dele; linhas só de
+// enfeite (`#####`, `# ---`) ficam de fora
+fn description_from(comments: &[String]) -> Option<String> {
+    let lines: Vec<&str> = comments.iter()
+        .map(|c| c.as_str())
+        .filter(|c| c.chars().any(|ch| ch.is_alphanumeric()))
+        .collect();
+    (!lines.is_empty()).then(|| lines.join("\n"))
+}
+
+// Linhas de comentário que gravam `description` acima do `Host`
+pub fn description_lines(description: Option<&str>) -> Vec<String> {
+    description.unwrap_or_default()
+        .lines()
+        .map(str::trim)
+        .filter(|l| !l.is_empty())
+        .map(|l| format!("# {}", l))
+        .collect()
+}
+
+// Diretivas cujo resto da linha é repassado ao shell como está, com aspas
+const VERBATIM: [&str; 4] = ["proxycommand", "localcommand", "remotecommand", "knownhostscommand"];
+
+// Diretivas de um argumento só: um valor com espaços precisa de aspas
+const SINGLE_ARG: [&str; 15] = [
+    "hostname",
+    "user",
+    "port",
+    "identityfile",
+    "certificatefile",
+    "identityagent",
+    "controlpath",
+    "hostkeyalias",
+    "proxyjump",
+    "pkcs11provider",
+    "securitykeyprovider",
+    "xauthlocation",
+    "revokedhostkeys",
+    "bindinterface",
+    "include",
+];
+
+// Diretiva e valor bruto de uma linha: a palavra-chave termina em espaço, tab
+// ou '=', como no OpenSSH (`Port 22`, `Port=22`, `Port = 22`)
+pub fn split_line(line: &str) -> Option<(&str, &str)> {
+    let line = line.trim();
+    let key_end = line.find(|c: char| c.is_whitespace() || c == '=')?;
+    let (key, rest) = line.split_at(key_end);
+    let rest = rest.trim_start();
+    let rest = rest.strip_prefix('=').unwrap_or(rest).trim_start();
+    (!key.is_empty() && !rest.is_empty()).then_some((key, rest))
+}
+
+// Argumentos com as regras de aspas do OpenSSH: aspas simples ou duplas
+// agrupam, e `\` escapa aspas, barra invertida e (fora de aspas) espaço.
+// Outras barras ficam como estão (`C:\Users`). None com aspas sem fechar.
+// É também o tokenizador dos comandos digitados e configurados (ssh, console, cliente)
+pub fn split_args(raw: &str) -> Option<Vec<String>> {
+    let mut args = Vec::new();
+    let mut chars = raw.chars().peekable();
+    loop {
+        while chars.next_if(|c| c.is_whitespace()).is_some() {}
+        if chars.peek().is_none() {
+            return Some(args);
+        }
+        let mut arg = String::new();
+        let mut quote: Option<char> = None;
+        while let Some(c) = chars.next() {
+            match c {
+                '\\' => match chars.peek() {
+                    Some(&next) if next == '"' || next == '\'' || next == '\\' || (quote.is_none() && next == ' ') => {
+                        arg.push(next);
+                        chars.next();
+                    }
+                    _ => arg.push(c),
+                },
+                '"' | '\'' if quote == Some(c) => quote = None,
+                '"' | '\'' if quote.is_none() => quote = Some(c),
+                c if c.is_whitespace() && quote.is_none() => break,
+                c => arg.push(c),
+            }
+        }
+        if quote.is_some() {
+            return None;
+        }
+        args.push(arg);
+    }
+}
+
+// Valor como o ssh o entende: `IdentityFile "C:\my key"` vira `C:\my key`.
+// Com vários argumentos (LocalForward, SendEnv) ou em comandos, fica o texto bruto
+pub fn unquote(key: &str, raw: &str) -> String {
+    if VERBATIM.iter().any(|k| k.eq_ignore_ascii_case(key)) {
+        return raw.to_string();
+    }
+    match split_args(raw) {
+        Some(mut args) if args.len() == 1 => args.remove(0),
+        _ => raw.to_string(),
+    }
+}
+
+// Inverso de `unquote`, para gravar: aspas só quando o valor de um argumento
+// tem espaços e ainda não está entre aspas
+pub fn quote(key: &str, value: &str) -> String {
+    let single = SINGLE_ARG.iter().any(|k| k.eq_ignore_ascii_case(key));
+    if !single || !value.contains(char::is_whitespace) || value.starts_with('"') {
+        return value.to_string();
+    }
+    let mut quoted = String::from("\"");
+    let mut chars = value.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            // Barra antes de aspas ou no fim seria lida como escape
+            '\\' if matches!(chars.peek(), None | Some('"' | '\\')) => quoted.push_str("\\\\"),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+// Grafia usual das diretivas; as desconhecidas ficam como vieram, o que o ssh
+// aceita, já que não diferencia maiúsculas
+pub fn canonical_key(key: &str) -> String {
+    const KNOWN: &[&str] = &[
+        "Host", "Match", "Include", "HostName", "User", "Port", "IdentityFile", "IdentitiesOnly",
+        "IdentityAgent", "CertificateFile", "ProxyJump", "ProxyCommand", "ProxyUseFdpass",
+        "LocalForward", "RemoteForward", "DynamicForward", "ForwardAgent", "ForwardX11",
+        "ForwardX11Trusted", "ExitOnForwardFailure", "ClearAllForwardings", "GatewayPorts",
+        "Compression", "StrictHostKeyChecking", "UserKnownHostsFile", "GlobalKnownHostsFile",
+        "HashKnownHosts", "UpdateHostKeys", "CheckHostIP", "HostKeyAlias", "HostKeyAlgorithms",
+        "KexAlgorithms", "Ciphers", "MACs", "PubkeyAcceptedAlgorithms", "VerifyHostKeyDNS",
+        "VisualHostKey", "ServerAliveInterval", "ServerAliveCountMax", "TCPKeepAlive",
+        "ConnectTimeout", "ConnectionAttempts", "ControlMaster", "ControlPath", "ControlPersist",
+        "AddKeysToAgent", "UseKeychain", "PreferredAuthentications", "PubkeyAuthentication",
+        "PasswordAuthentication", "KbdInteractiveAuthentication", "GSSAPIAuthentication",
+        "HostbasedAuthentication", "BatchMode", "NumberOfPasswordPrompts", "SendEnv", "SetEnv",
+        "RequestTTY", "RemoteCommand", "LocalCommand", "PermitLocalCommand", "KnownHostsCommand",
+        "SessionType", "StdinNull", "ForkAfterAuthentication", "EscapeChar", "LogLevel",
+        "AddressFamily", "BindAddress", "BindInterface", "CanonicalizeHostname", "CanonicalDomains",
+        "IPQoS", "RekeyLimit", "PKCS11Provider", "SecurityKeyProvider", "XAuthLocation",
+        "IgnoreUnknown", "RevokedHostKeys",
+    ];
+    KNOWN.iter().find(|k| k.eq_ignore_ascii_case(key)).map(|k| k.to_string()).unwrap_or_else(|| key.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("lazysshrs-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn quote_round_trips_through_unquote() {
+        for value in ["plain", r"C:\Users\me\my key", "~/.ssh/id rsa", r#"say "hi" there"#, r"ends with \", r"a\ b c"] {
+            let quoted = quote("IdentityFile", value);
+            assert_eq!(unquote("IdentityFile", &quoted), value, "{}", quoted);
+        }
+        // Comandos ficam como escritos
+        assert_eq!(unquote("ProxyCommand", r#""ssh -W %h:%p" jump"#), r#""ssh -W %h:%p" jump"#);
+    }
+
+    #[test]
+    fn split_args_follows_openssh_quoting() {
+        assert_eq!(split_args(r#"a/config "my dir/config" 'c d' e\ f"#).unwrap(), ["a/config", "my dir/config", "c d", "e f"]);
+        assert_eq!(split_args(r"C:\Users\me").unwrap(), [r"C:\Users\me"]);
+        assert!(split_args(r#""unclosed"#).is_none());
+    }
+
+    #[test]
+    fn include_expands_every_pattern_in_order() {
+        let dir = temp_dir("expand");
+        fs::create_dir_all(dir.join("c")).unwrap();
+        for file in ["c/y", "c/x", "c/.hidden"] {
+            fs::write(dir.join(file), "").unwrap();
+        }
+        let paths = SshConfig::expand_include(r#"a/config "my dir/config" c/*"#, &dir).unwrap();
+        assert_eq!(paths, [dir.join("a/config"), dir.join("my dir/config"), dir.join("c/x"), dir.join("c/y")]);
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn rewrite_includes_touches_only_the_matching_path() {
+        let dir = temp_dir("rewrite");
+        let main = dir.join("config");
+        fs::write(&main, "Include a/config \"my dir/config\" c/*\n  Include a/config\nHost x\n").unwrap();
+
+        let rewritten = SshConfig::rewrite_includes(std::slice::from_ref(&main), &dir.join("a"), Some(&dir.join("b"))).unwrap();
+        assert_eq!(rewritten, std::slice::from_ref(&main));
+        assert_eq!(fs::read_to_string(&main).unwrap(), "Include b/config \"my dir/config\" c/*\n  Include b/config\nHost x\n");
+
+        SshConfig::rewrite_includes(std::slice::from_ref(&main), &dir.join("my dir/config"), None).unwrap();
+        assert_eq!(fs::read_to_string(&main).unwrap(), "Include b/config c/*\n  Include b/config\nHost x\n");
+
+        // A linha só some quando não sobra nenhum caminho
+        SshConfig::rewrite_includes(std::slice::from_ref(&main), &dir.join("b/config"), None).unwrap();
+        assert_eq!(fs::read_to_string(&main).unwrap(), "Include c/*\nHost x\n");
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,122 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use serde::{Deserialize, Serialize};
+
+use crate::config::AppConfig;
+use crate::crypto;
+
+// Resultado da última verificação de um host
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CheckResult {
+    pub up: bool,
+    pub latency_ms: Option<u64>,
+    pub checked_at: u64,
+}
+
+impl CheckResult {
+    pub fn new(up: bool, latency: Option<Duration>) -> Self {
+        Self {
+            up,
+            latency_ms: latency.map(|l| l.as_millis() as u64),
+            checked_at: now(),
+        }
+    }
+
+    pub fn age(&self) -> Duration {
+        Duration::from_secs(now().saturating_sub(self.checked_at))
+    }
+
+    // Faixa de latência em barras: rápida, média ou lenta
+    pub fn latency_bars(&self) -> &'static str {
+        match self.latency_ms {
+            _ if !self.up => "   ",
+            None => "   ",
+            Some(0..=50) => "▁▃▅",
+            Some(51..=200) => "▁▃ ",
+            Some(_) => "▁  ",
+        }
+    }
+
+    pub fn is_stale(&self, ttl_secs: u64) -> bool {
+        self.age().as_secs() > ttl_secs
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SavedTunnel {
+    pub host: String,
+    pub forward: String,
+}
+
+// Estado persistido entre execuções, em `<state dir>/state.toml`
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SavedState {
+    // Host selecionado e última busca confirmada ao sair
+    pub selected_host: Option<String>,
+    pub last_search: Option<String>,
+    // Túneis ativos no fim da última sessão
+    pub tunnels: Vec<SavedTunnel>,
+    pub checks: BTreeMap<String, CheckResult>,
+    // Lista ordenada pela última conexão (`s`) em vez de por pasta
+    pub sort_recent: bool,
+}
+
+impl SavedState {
+    pub fn path() -> PathBuf {
+        AppConfig::get_state_dir().join("state.toml")
+    }
+
+    pub fn load() -> Self {
+        crypto::read_to_string(&Self::path())
+            .and_then(|content| toml::from_str(&content).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self) -> Result<(), Box<dyn std::error::Error>> {
+        let path = Self::path();
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        crypto::write(&path, &toml::to_string_pretty(self)?)?;
+        Ok(())
+    }
+}
+
+pub fn now() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0)
+}
+
+// Idade compacta: "just now", "42s ago", "3m ago", "5h ago", "2d ago"
+pub fn format_age(age: Duration) -> String {
+    let secs = age.as_secs();
+    match secs {
+        0..=4 => "just now".to_string(),
+        5..=59 => format!("{}s ago", secs),
+        60..=3599 => format!("{}m ago", secs / 60),
+        3600..=86399 => format!("{}h ago", secs / 3600),
+        _ => format!("{}d ago", secs / 86400),
+    }
+}
+
+// Data/hora UTC em ISO 8601, sem depender de uma crate de datas
+pub fn format_timestamp(secs: u64) -> String {
+    let days = (secs / 86400) as i64;
+    let rem = secs % 86400;
+    // Algoritmo civil_from_days (Howard Hinnant)
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z - era * 146097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+        year, month, day, rem / 3600, (rem % 3600) / 60, rem % 60
+    )
+}
@@ -0,0 +1,175 @@
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::connectivity::{ConnectivityTest, ReachMethod, WELL_KNOWN_PORTS};
+use crate::state::CheckResult;
+
+// O que é preciso para verificar um host fora da thread da interface
+#[derive(Debug, Clone)]
+pub struct CheckTarget {
+    pub name: String,
+    pub hostname: String,
+    pub port: u16,
+    pub method: ReachMethod,
+    pub command: Option<String>,
+}
+
+impl CheckTarget {
+    // Verificação rápida: só a porta SSH, o ping ou o health-check do host
+    pub fn check(&self) -> CheckResult {
+        if let Some(command) = &self.command {
+            let started = Instant::now();
+            let (ok, _) = ConnectivityTest::run_check_command(command, Duration::from_secs(15));
+            return CheckResult::new(ok, Some(started.elapsed()));
+        }
+
+        let mut up = false;
+        let mut latency = None;
+        if self.method != ReachMethod::Tcp {
+            latency = ConnectivityTest::icmp_ping(&self.hostname);
+            up |= latency.is_some();
+        }
+        if self.method != ReachMethod::Icmp {
+            if let Some(result) = ConnectivityTest::scan(&self.hostname, &[self.port]).pop() {
+                up |= result.open;
+                latency = result.latency.or(latency);
+            }
+        }
+        CheckResult::new(up, latency)
+    }
+
+    // Teste completo do `p`: também as portas conhecidas e os serviços que respondem
+    pub fn report(&self) -> (CheckResult, String) {
+        let hostname = &self.hostname;
+        // O health-check próprio do host substitui os testes padrão
+        if let Some(command) = &self.command {
+            let started = Instant::now();
+            let (ok, detail) = ConnectivityTest::run_check_command(command, Duration::from_secs(15));
+            let status = if ok { "OK" } else { "FALHOU" };
+            return (CheckResult::new(ok, Some(started.elapsed())), format!("{}\n{}: {}", command, status, detail));
+        }
+
+        let mut lines = Vec::new();
+        let mut up = false;
+        let mut latency = None;
+        if self.method != ReachMethod::Tcp {
+            let rtt = ConnectivityTest::icmp_ping(hostname);
+            lines.push(match rtt {
+                Some(rtt) => format!("Host {} respondeu ao ping ({} ms)", hostname, rtt.as_millis()),
+                None => format!("Host {} não respondeu ao ping", hostname),
+            });
+            up |= rtt.is_some();
+            latency = latency.or(rtt);
+        }
+
+        if self.method != ReachMethod::Icmp {
+            let ssh_port = self.port;
+            let mut ports = vec![ssh_port];
+            ports.extend(WELL_KNOWN_PORTS.iter().filter(|&&p| p != ssh_port));
+
+            let results = ConnectivityTest::scan(hostname, &ports);
+            for result in &results {
+                if result.port == ssh_port {
+                    lines.push(if result.open {
+                        format!("Host {} respondeu na porta {}", hostname, ssh_port)
+                    } else {
+                        format!("Host {} não respondeu na porta {}", hostname, ssh_port)
+                    });
+                    up |= result.open;
+                    latency = result.latency.or(latency);
+                }
+            }
+            for result in results.iter().filter(|r| r.open) {
+                let service = result.service.map(|s| s.label()).unwrap_or("serviço desconhecido");
+                match &result.banner {
+                    Some(banner) => lines.push(format!("  {:>5}: {} ({})", result.port, service, banner)),
+                    None => lines.push(format!("  {:>5}: {}", result.port, service)),
+                }
+            }
+        }
+        (CheckResult::new(up, latency), lines.join("\n"))
+    }
+}
+
+// Teste de um host (`p`) numa thread; a interface continua respondendo
+pub struct HostTest {
+    pub name: String,
+    started: Instant,
+    receiver: Receiver<(CheckResult, String)>,
+}
+
+impl HostTest {
+    pub fn start(target: CheckTarget) -> Self {
+        let (sender, receiver) = mpsc::channel();
+        let name = target.name.clone();
+        thread::spawn(move || {
+            let _ = sender.send(target.report());
+        });
+        Self { name, started: Instant::now(), receiver }
+    }
+
+    pub fn poll(&self) -> Option<(CheckResult, String)> {
+        self.receiver.try_recv().ok()
+    }
+
+    // Quadro da animação, pelo tempo desde o início
+    pub fn spinner(&self) -> &'static str {
+        const FRAMES: [&str; 4] = ["|", "/", "-", "\\"];
+        FRAMES[(self.started.elapsed().as_millis() / 150) as usize % FRAMES.len()]
+    }
+}
+
+// Varredura em segundo plano com número limitado de verificações simultâneas
+pub struct Sweep {
+    receiver: Receiver<(String, CheckResult)>,
+    cancelled: Arc<AtomicBool>,
+    pub total: usize,
+    pub done: usize,
+}
+
+impl Sweep {
+    pub fn start(targets: Vec<CheckTarget>, concurrency: usize) -> Self {
+        let total = targets.len();
+        let queue = Arc::new(Mutex::new(VecDeque::from(targets)));
+        let cancelled = Arc::new(AtomicBool::new(false));
+        let (sender, receiver) = mpsc::channel();
+
+        for _ in 0..concurrency.max(1).min(total.max(1)) {
+            let queue = Arc::clone(&queue);
+            let cancelled = Arc::clone(&cancelled);
+            let sender = sender.clone();
+            thread::spawn(move || {
+                while !cancelled.load(Ordering::Relaxed) {
+                    let Some(target) = queue.lock().ok().and_then(|mut q| q.pop_front()) else {
+                        break;
+                    };
+                    let result = target.check();
+                    if sender.send((target.name, result)).is_err() {
+                        break;
+                    }
+                }
+            });
+        }
+
+        Self { receiver, cancelled, total, done: 0 }
+    }
+
+    // Resultados que chegaram desde a última chamada
+    pub fn poll(&mut self) -> Vec<(String, CheckResult)> {
+        let results: Vec<_> = self.receiver.try_iter().collect();
+        self.done += results.len();
+        results
+    }
+
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.done >= self.total || self.cancelled.load(Ordering::Relaxed)
+    }
+}
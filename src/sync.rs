@@ -0,0 +1,149 @@
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+use crate::effective;
+use crate::export;
+
+// Chaves privadas, sockets e arquivos que pertencem a cada máquina
+pub fn default_excludes() -> Vec<String> {
+    ["id_* !id_*.pub", "*.pem", "*.key", "*.ppk", "known_hosts*", "authorized_keys*", "*.sock"]
+        .iter()
+        .map(|p| p.to_string())
+        .collect()
+}
+
+fn excluded(name: &str, excludes: &[String]) -> bool {
+    excludes.iter().any(|patterns| effective::pattern_matches(patterns, name))
+}
+
+fn walk(root: &Path, dir: &Path, excludes: &[String], files: &mut Vec<PathBuf>) -> Result<(), Box<dyn std::error::Error>> {
+    let mut entries: Vec<_> = fs::read_dir(dir)?.flatten().collect();
+    entries.sort_by_key(|e| e.file_name());
+    for entry in entries {
+        let name = entry.file_name().to_string_lossy().to_string();
+        // .git e outros diretórios ocultos ficam de fora
+        if name.starts_with('.') || excluded(&name, excludes) {
+            continue;
+        }
+        let path = entry.path();
+        if path.is_dir() {
+            walk(root, &path, excludes, files)?;
+        } else if path.is_file() {
+            files.push(path.strip_prefix(root)?.to_path_buf());
+        }
+    }
+    Ok(())
+}
+
+// Arquivos a enviar, relativos ao workdir; `selection` vazio = workdir inteiro
+pub fn collect_files(workdir: &Path, selection: &[String], excludes: &[String]) -> Result<Vec<PathBuf>, Box<dyn std::error::Error>> {
+    let mut files = Vec::new();
+    if selection.is_empty() {
+        walk(workdir, workdir, excludes, &mut files)?;
+    }
+    for item in selection {
+        let path = workdir.join(item);
+        if path.is_dir() {
+            walk(workdir, &path, excludes, &mut files)?;
+        } else if path.is_file() {
+            let name = path.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+            if excluded(&name, excludes) {
+                return Err(format!("{} está na lista de exclusão", item).into());
+            }
+            files.push(path.strip_prefix(workdir)?.to_path_buf());
+        } else {
+            return Err(format!("{} não existe no workdir", item).into());
+        }
+    }
+    files.sort();
+    files.dedup();
+    Ok(files)
+}
+
+// `~/.ssh` vira `.ssh`: caminhos relativos já partem do home remoto
+fn remote_path(remote_dir: &str) -> String {
+    let dir = remote_dir.trim().trim_end_matches('/');
+    let dir = dir.strip_prefix("~/").unwrap_or(dir);
+    if dir.is_empty() || dir == "~" { ".".to_string() } else { dir.to_string() }
+}
+
+fn has_rsync() -> bool {
+    Command::new("rsync").arg("--version").stdout(Stdio::null()).stderr(Stdio::null()).status().is_ok_and(|s| s.success())
+}
+
+fn check(status: std::process::ExitStatus, program: &str) -> Result<(), Box<dyn std::error::Error>> {
+    if !status.success() {
+        return Err(format!("{} terminou com código {:?}", program, status.code()).into());
+    }
+    Ok(())
+}
+
+// Envia os arquivos para `host:remote_dir`, com rsync ou, sem ele, scp.
+// Roda com o terminal liberado, para o ssh poder pedir senha
+pub fn push(workdir: &Path, files: &[PathBuf], host: &str, remote_dir: &str) -> Result<&'static str, Box<dyn std::error::Error>> {
+    if files.is_empty() {
+        return Err("nenhum arquivo para enviar".into());
+    }
+    let remote = remote_path(remote_dir);
+
+    if has_rsync() {
+        // -R mantém a estrutura de pastas relativa ao workdir
+        let status = Command::new("rsync")
+            .current_dir(workdir)
+            .args(["-azR", "--chmod=D700,F600"])
+            .args(files)
+            .arg(format!("{}:{}/", host, remote))
+            .status()?;
+        check(status, "rsync")?;
+        return Ok("rsync");
+    }
+
+    let mut dirs: Vec<PathBuf> = files.iter().map(|f| f.parent().unwrap_or(Path::new("")).to_path_buf()).collect();
+    dirs.sort();
+    dirs.dedup();
+    let remote_dirs: Vec<String> = dirs.iter().map(|d| format!("{}/{}", remote, d.display())).collect();
+    let status = Command::new("ssh").arg(host).arg("mkdir").arg("-p").args(&remote_dirs).status()?;
+    check(status, "ssh mkdir")?;
+    for (dir, remote_dir) in dirs.iter().zip(&remote_dirs) {
+        let group: Vec<&PathBuf> = files.iter().filter(|f| f.parent().unwrap_or(Path::new("")) == dir).collect();
+        let status = Command::new("scp")
+            .current_dir(workdir)
+            .arg("-p")
+            .args(group)
+            .arg(format!("{}:{}/", host, remote_dir))
+            .status()?;
+        check(status, "scp")?;
+    }
+    Ok("scp")
+}
+
+// Cópias atuais dos arquivos no host, para o diff antes do envio.
+// Um único ssh com tar: arquivos que não existem lá simplesmente não voltam
+pub fn fetch_remote(host: &str, remote_dir: &str, files: &[PathBuf]) -> Result<Vec<Option<String>>, Box<dyn std::error::Error>> {
+    let remote = remote_path(remote_dir);
+    let quoted: Vec<String> = files.iter().map(|f| export::shell_quote(&f.to_string_lossy())).collect();
+    let command = format!("tar -C {} -cf - {} 2>/dev/null; true", export::shell_quote(&remote), quoted.join(" "));
+    let output = Command::new("ssh").arg(host).arg(command).stderr(Stdio::inherit()).output()?;
+    check(output.status, "ssh")?;
+
+    let scratch = std::env::temp_dir().join(format!("lazysshrs-sync-{}", std::process::id()));
+    fs::create_dir_all(&scratch)?;
+    let extracted = (|| -> Result<Vec<Option<String>>, Box<dyn std::error::Error>> {
+        if !output.stdout.is_empty() {
+            let mut tar = Command::new("tar")
+                .arg("-xf").arg("-").arg("-C").arg(&scratch)
+                .stdin(Stdio::piped())
+                .stderr(Stdio::null())
+                .spawn()?;
+            if let Some(mut stdin) = tar.stdin.take() {
+                stdin.write_all(&output.stdout)?;
+            }
+            tar.wait()?;
+        }
+        Ok(files.iter().map(|f| fs::read_to_string(scratch.join(f)).ok()).collect())
+    })();
+    let _ = fs::remove_dir_all(&scratch);
+    extracted
+}
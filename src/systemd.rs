@@ -0,0 +1,123 @@
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+
+use crate::forward::ForwardSpec;
+
+const UNIT_PREFIX: &str = "lazysshrs-tunnel-";
+
+// Túnel mantido por uma unit `systemctl --user`, fora da TUI
+#[derive(Debug, Clone)]
+pub struct PersistentTunnel {
+    pub unit: String,
+    pub host: String,
+    pub forward: ForwardSpec,
+    pub status: String,
+}
+
+pub fn unit_dir() -> PathBuf {
+    let home_dir = home::home_dir().unwrap_or_else(|| PathBuf::from("/"));
+    home_dir.join(".config").join("systemd").join("user")
+}
+
+pub fn unit_name(host: &str, forward: &ForwardSpec) -> String {
+    let sanitized: String = host
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '.' || c == '_' { c } else { '-' })
+        .collect();
+    format!("{}{}-{}.service", UNIT_PREFIX, sanitized, forward.local_port().unwrap_or(0))
+}
+
+// O systemd exige caminho absoluto no ExecStart
+fn ssh_path() -> String {
+    env::var_os("PATH")
+        .and_then(|paths| env::split_paths(&paths).map(|p| p.join("ssh")).find(|p| p.is_file()))
+        .map(|p| p.to_string_lossy().to_string())
+        .unwrap_or_else(|| "/usr/bin/ssh".to_string())
+}
+
+fn unit_content(host: &str, forward: &ForwardSpec) -> String {
+    format!(
+        "# Gerado pelo lazysshrs\n\
+         # host: {host}\n\
+         # forward: {spec}\n\
+         [Unit]\n\
+         Description=lazysshrs tunnel {host} {spec}\n\
+         After=network-online.target\n\
+         Wants=network-online.target\n\
+         \n\
+         [Service]\n\
+         ExecStart={ssh} -N -o BatchMode=yes -o ExitOnForwardFailure=yes -o ServerAliveInterval=30 -o ServerAliveCountMax=3 -L {arg} {host}\n\
+         Restart=always\n\
+         RestartSec=10\n\
+         \n\
+         [Install]\n\
+         WantedBy=default.target\n",
+        host = host,
+        spec = forward.to_spec(),
+        ssh = ssh_path(),
+        arg = forward.to_l_arg(),
+    )
+}
+
+fn systemctl(args: &[&str]) -> Result<String, Box<dyn std::error::Error>> {
+    let output = Command::new("systemctl")
+        .arg("--user")
+        .args(args)
+        .stdin(Stdio::null())
+        .output()?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr).trim().to_string();
+        return Err(format!("systemctl {}: {}", args.join(" "), stderr).into());
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+pub fn install(host: &str, forward: &ForwardSpec) -> Result<String, Box<dyn std::error::Error>> {
+    let unit = unit_name(host, forward);
+    let dir = unit_dir();
+    fs::create_dir_all(&dir)?;
+    fs::write(dir.join(&unit), unit_content(host, forward))?;
+    systemctl(&["daemon-reload"])?;
+    systemctl(&["enable", "--now", &unit])?;
+    Ok(unit)
+}
+
+pub fn remove(unit: &str) -> Result<(), Box<dyn std::error::Error>> {
+    systemctl(&["disable", "--now", unit])?;
+    fs::remove_file(unit_dir().join(unit))?;
+    systemctl(&["daemon-reload"])?;
+    Ok(())
+}
+
+// Units geradas pelo lazysshrs, com o estado informado pelo systemd
+pub fn list() -> Vec<PersistentTunnel> {
+    let Ok(entries) = fs::read_dir(unit_dir()) else {
+        return Vec::new();
+    };
+    let mut tunnels: Vec<PersistentTunnel> = entries
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| {
+            let unit = entry.file_name().to_string_lossy().to_string();
+            if !unit.starts_with(UNIT_PREFIX) || !unit.ends_with(".service") {
+                return None;
+            }
+            let content = fs::read_to_string(entry.path()).ok()?;
+            let header = |key: &str| {
+                content.lines().find_map(|l| l.strip_prefix(&format!("# {}: ", key)).map(str::to_string))
+            };
+            let host = header("host")?;
+            let forward = ForwardSpec::parse(&header("forward")?)?;
+            // is-active sai com erro quando a unit não está ativa, mas ainda imprime o estado
+            let status = Command::new("systemctl")
+                .args(["--user", "is-active", &unit])
+                .output()
+                .map(|o| String::from_utf8_lossy(&o.stdout).trim().to_string())
+                .unwrap_or_default();
+            Some(PersistentTunnel { unit, host, forward, status })
+        })
+        .collect();
+    tunnels.sort_by(|a, b| a.unit.cmp(&b.unit));
+    tunnels
+}
@@ -0,0 +1,99 @@
+use std::cmp::Ordering;
+use std::collections::BTreeMap;
+use serde::{Deserialize, Serialize};
+
+use crate::ssh_config::SshHost;
+use crate::state::CheckResult;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Column {
+    Alias,
+    Hostname,
+    User,
+    Port,
+    Folder,
+    Status,
+}
+
+// Coluna da visão em tabela; largura 0 ocupa o espaço que sobrar
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TableColumn {
+    pub column: Column,
+    #[serde(default)]
+    pub width: u16,
+}
+
+pub fn default_columns() -> Vec<TableColumn> {
+    [
+        (Column::Status, 6),
+        (Column::Alias, 0),
+        (Column::Hostname, 18),
+        (Column::User, 10),
+        (Column::Port, 5),
+        (Column::Folder, 10),
+    ]
+    .into_iter()
+    .map(|(column, width)| TableColumn { column, width })
+    .collect()
+}
+
+impl Column {
+    pub fn title(self) -> &'static str {
+        match self {
+            Column::Alias => "Alias",
+            Column::Hostname => "Hostname",
+            Column::User => "User",
+            Column::Port => "Port",
+            Column::Folder => "Folder",
+            Column::Status => "Status",
+        }
+    }
+
+    // Texto da célula; Status é desenhado à parte, com cor
+    pub fn text(self, host: &SshHost) -> String {
+        match self {
+            Column::Alias => host.name.clone(),
+            Column::Hostname => host.hostname.clone().unwrap_or_default(),
+            Column::User => host.user.clone().unwrap_or_default(),
+            Column::Port => host.port.map(|p| p.to_string()).unwrap_or_default(),
+            Column::Folder => host.source_dir.clone().unwrap_or_default(),
+            Column::Status => String::new(),
+        }
+    }
+
+    fn compare(self, a: &SshHost, b: &SshHost, checks: &BTreeMap<String, CheckResult>) -> Ordering {
+        match self {
+            Column::Port => a.port.unwrap_or(22).cmp(&b.port.unwrap_or(22)),
+            // No ar e mais rápidos primeiro, depois fora do ar, depois sem verificação
+            Column::Status => {
+                let rank = |h: &SshHost| match checks.get(&h.name) {
+                    Some(c) if c.up => (0, c.latency_ms.unwrap_or(u64::MAX)),
+                    Some(_) => (1, 0),
+                    None => (2, 0),
+                };
+                rank(a).cmp(&rank(b))
+            }
+            _ => self.text(a).to_lowercase().cmp(&self.text(b).to_lowercase()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TableSort {
+    pub column: Column,
+    pub descending: bool,
+}
+
+// Linhas da tabela (índices em `hosts`, sem separadores) na ordem pedida;
+// sem ordenação, a ordem do arquivo
+pub fn rows(hosts: &[SshHost], sort: Option<TableSort>, checks: &BTreeMap<String, CheckResult>) -> Vec<usize> {
+    let mut rows: Vec<usize> = (0..hosts.len()).filter(|&i| !hosts[i].is_separator).collect();
+    if let Some(sort) = sort {
+        rows.sort_by(|&a, &b| {
+            let order = sort.column.compare(&hosts[a], &hosts[b], checks);
+            if sort.descending { order.reverse() } else { order }
+        });
+    }
+    rows
+}
@@ -0,0 +1,54 @@
+use ratatui::buffer::Buffer;
+use ratatui::style::{Color, Modifier};
+
+// Perfil de acessibilidade: aplicado ao quadro pronto, para não espalhar
+// variantes por cada tela. Troca símbolos Unicode por ASCII, cinzas de baixo
+// contraste pela cor padrão do terminal e remove estilos que leitores de tela
+// e terminais limitados não mostram bem
+pub fn make_accessible(buffer: &mut Buffer, keep_blocks: bool) {
+    let area = buffer.area;
+    for y in area.top()..area.bottom() {
+        for x in area.left()..area.right() {
+            let cell = buffer.get_mut(x, y);
+            if let Some(ascii) = ascii_symbol(cell.symbol(), keep_blocks) {
+                cell.set_symbol(ascii);
+            }
+            if matches!(cell.fg, Color::Gray | Color::DarkGray) {
+                cell.fg = Color::Reset;
+            }
+            if matches!(cell.bg, Color::Gray | Color::DarkGray) {
+                cell.bg = Color::Reset;
+            }
+            cell.modifier.remove(
+                Modifier::DIM | Modifier::ITALIC | Modifier::CROSSED_OUT | Modifier::SLOW_BLINK | Modifier::RAPID_BLINK,
+            );
+        }
+    }
+}
+
+// `keep_blocks` preserva os meios-blocos do QR code, que não têm equivalente
+fn ascii_symbol(symbol: &str, keep_blocks: bool) -> Option<&'static str> {
+    let ascii = match symbol {
+        "─" | "━" | "╌" => "-",
+        "│" | "┃" | "║" | "╎" => "|",
+        "═" => "=",
+        "┌" | "┐" | "└" | "┘" | "├" | "┤" | "┬" | "┴" | "┼" | "╭" | "╮" | "╰" | "╯" | "╔" | "╗" | "╚" | "╝" => "+",
+        "●" | "•" => "*",
+        "○" => "o",
+        "▁" => ".",
+        "▃" => ":",
+        "▅" => "|",
+        "▲" | "↑" => "^",
+        "▼" | "↓" | "▾" => "v",
+        "→" | "▸" => ">",
+        "←" => "<",
+        "↵" => "/",
+        "✓" => "+",
+        "✗" => "x",
+        "⚠" => "!",
+        "…" => ".",
+        "█" | "▀" | "▄" if !keep_blocks => "#",
+        _ => return None,
+    };
+    Some(ascii)
+}
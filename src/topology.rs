@@ -0,0 +1,130 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::ssh_config::SshHost;
+
+pub struct TopologyRow {
+    pub name: String,
+    pub prefix: String,
+    pub known: bool,
+    pub dependents: Vec<String>,
+}
+
+// Bastião imediato de um host, derivado de ProxyJump ou ProxyCommand
+pub fn bastion_of(host: &SshHost) -> Option<String> {
+    if let Some(proxy_jump) = host.other_options.get("proxyjump") {
+        if proxy_jump.eq_ignore_ascii_case("none") {
+            return None;
+        }
+        return proxy_jump.rsplit(',').next().map(strip_user_and_port);
+    }
+
+    let proxy_command = host.other_options.get("proxycommand")?;
+    let mut tokens = proxy_command.split_whitespace();
+    if !tokens.next()?.ends_with("ssh") {
+        return None;
+    }
+
+    let mut skip_next = false;
+    for token in tokens {
+        if skip_next {
+            skip_next = false;
+            continue;
+        }
+        if let Some(flag) = token.strip_prefix('-') {
+            // Opções do ssh que recebem argumento
+            skip_next = flag.len() == 1 && "WpliJoFEcmbDLRS".contains(flag);
+            continue;
+        }
+        if !token.contains('%') {
+            return Some(strip_user_and_port(token));
+        }
+    }
+    None
+}
+
+fn strip_user_and_port(spec: &str) -> String {
+    let spec = spec.trim();
+    let spec = spec.rsplit_once('@').map(|(_, h)| h).unwrap_or(spec);
+    let spec = spec.split(':').next().unwrap_or(spec);
+    spec.to_string()
+}
+
+pub fn build_rows(hosts: &[SshHost]) -> Vec<TopologyRow> {
+    let mut children: HashMap<String, Vec<String>> = HashMap::new();
+    let mut parents: HashMap<String, String> = HashMap::new();
+    let mut known: HashSet<&str> = HashSet::new();
+
+    for host in hosts.iter().filter(|h| !h.is_separator) {
+        known.insert(&host.name);
+        if let Some(bastion) = bastion_of(host) {
+            if bastion != host.name {
+                children.entry(bastion.clone()).or_default().push(host.name.clone());
+                parents.insert(host.name.clone(), bastion);
+            }
+        }
+    }
+
+    // Raízes: bastiões que não saltam por nenhum outro
+    let mut roots: Vec<&String> = children.keys().filter(|b| !parents.contains_key(*b)).collect();
+    roots.sort();
+
+    let mut rows = Vec::new();
+    let mut visited = HashSet::new();
+    for root in roots {
+        push_rows(root, "", "", &children, &known, &mut visited, &mut rows);
+    }
+    rows
+}
+
+fn push_rows(
+    name: &str,
+    prefix: &str,
+    child_prefix: &str,
+    children: &HashMap<String, Vec<String>>,
+    known: &HashSet<&str>,
+    visited: &mut HashSet<String>,
+    rows: &mut Vec<TopologyRow>,
+) {
+    // Proteção contra ciclos de ProxyJump
+    if !visited.insert(name.to_string()) {
+        return;
+    }
+
+    rows.push(TopologyRow {
+        name: name.to_string(),
+        prefix: prefix.to_string(),
+        known: known.contains(name),
+        dependents: dependents_of(name, children),
+    });
+
+    let kids = children.get(name).cloned().unwrap_or_default();
+    for (i, kid) in kids.iter().enumerate() {
+        let last = i == kids.len() - 1;
+        let branch = if last { "└─ " } else { "├─ " };
+        let next = if last { "   " } else { "│  " };
+        push_rows(
+            kid,
+            &format!("{}{}", child_prefix, branch),
+            &format!("{}{}", child_prefix, next),
+            children,
+            known,
+            visited,
+            rows,
+        );
+    }
+}
+
+// Todos os hosts que deixam de ser alcançáveis se `name` cair
+fn dependents_of(name: &str, children: &HashMap<String, Vec<String>>) -> Vec<String> {
+    let mut result = Vec::new();
+    let mut stack = vec![name.to_string()];
+    while let Some(current) = stack.pop() {
+        for kid in children.get(&current).into_iter().flatten() {
+            if !result.contains(kid) && kid != name {
+                result.push(kid.clone());
+                stack.push(kid.clone());
+            }
+        }
+    }
+    result
+}
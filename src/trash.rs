@@ -0,0 +1,130 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use serde::{Deserialize, Serialize};
+
+use crate::config::AppConfig;
+use crate::crypto;
+use crate::db::{HostDb, HostRecord};
+use crate::ssh_config::{SshConfig, SshHost};
+use crate::state;
+
+// Host apagado, com o bloco como estava e de onde veio
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TrashEntry {
+    pub name: String,
+    pub file: PathBuf,
+    pub folder: Option<String>,
+    // Veio do banco de hosts, e não direto de um arquivo
+    #[serde(default)]
+    pub database: bool,
+    pub deleted_at: u64,
+    pub description: Option<String>,
+    pub directives: Vec<(String, String)>,
+}
+
+impl TrashEntry {
+    pub fn new(host: &SshHost, file: PathBuf, database: bool) -> Self {
+        let folder = host.source_dir.clone();
+        let record = HostRecord::from_host(host, folder.as_deref().unwrap_or_default());
+        Self {
+            name: host.name.clone(),
+            file,
+            folder,
+            database,
+            deleted_at: state::now(),
+            description: record.description,
+            directives: record.directives,
+        }
+    }
+
+    fn record(&self) -> HostRecord {
+        HostRecord {
+            name: self.name.clone(),
+            folder: self.folder.clone().unwrap_or_default(),
+            description: self.description.clone(),
+            directives: self.directives.clone(),
+        }
+    }
+
+    // Devolve o bloco ao arquivo (ou ao banco) de origem; retorna os arquivos alterados
+    pub fn restore(&self, host_db: Option<&HostDb>, workdir: &Path, main_config: &Path) -> Result<Vec<PathBuf>, Box<dyn std::error::Error>> {
+        let mut paths = Vec::new();
+        match host_db {
+            Some(host_db) if self.database => {
+                host_db.insert(&self.record(), None)?;
+                for path in host_db.regenerate(workdir)? {
+                    if SshConfig::add_include(main_config, &path)? {
+                        paths.push(main_config.to_path_buf());
+                    }
+                    paths.push(path);
+                }
+            }
+            _ => {
+                let is_new_file = !self.file.exists();
+                SshConfig::append_host_block(&self.file, &self.name, self.description.as_deref(), &self.directives)?;
+                if is_new_file && self.file != main_config && SshConfig::add_include(main_config, &self.file)? {
+                    paths.push(main_config.to_path_buf());
+                }
+                paths.push(self.file.clone());
+            }
+        }
+        Ok(paths)
+    }
+}
+
+// Lixeira de hosts apagados, em `<state dir>/trash`
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Trash {
+    pub entries: Vec<TrashEntry>,
+    // Arquivo que existe mas não foi lido; com ele, `save` recusa gravar por cima
+    #[serde(skip)]
+    load_error: Option<String>,
+}
+
+impl Trash {
+    pub fn path() -> PathBuf {
+        AppConfig::get_state_dir().join("trash")
+    }
+
+    // Uma lixeira ilegível (senha errada, TOML quebrado) fica vazia e bloqueada
+    // para gravação: os hosts dela não existem em nenhum outro lugar
+    pub fn load() -> Self {
+        let path = Self::path();
+        if !path.exists() {
+            return Self::default();
+        }
+        let error = match crypto::read_to_string(&path).map(|content| toml::from_str::<Self>(&content)) {
+            Some(Ok(trash)) => return trash,
+            Some(Err(e)) => e.to_string(),
+            None => "não foi possível ler ou decifrar o arquivo".to_string(),
+        };
+        Self {
+            load_error: Some(format!("{} não foi carregada ({}); a lixeira não será gravada até o arquivo ser corrigido", path.display(), error.lines().next().unwrap_or_default())),
+            ..Self::default()
+        }
+    }
+
+    pub fn load_error(&self) -> Option<&str> {
+        self.load_error.as_deref()
+    }
+
+    pub fn save(&self) -> Result<(), Box<dyn std::error::Error>> {
+        if let Some(error) = &self.load_error {
+            return Err(error.clone().into());
+        }
+        let path = Self::path();
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        crypto::write(&path, &toml::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    // Guarda o host antes de ele ser apagado; mais recentes primeiro
+    pub fn push(entry: TrashEntry) -> Result<(), Box<dyn std::error::Error>> {
+        let mut trash = Self::load();
+        trash.entries.insert(0, entry);
+        trash.save()
+    }
+}
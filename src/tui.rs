@@ -1,25 +1,59 @@
 use crossterm::{
-    event::{self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode},
+    event::{self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyModifiers, MouseButton, MouseEvent, MouseEventKind},
     execute,
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
 };
 use ratatui::{
-    backend::CrosstermBackend,
-    layout::{Constraint, Direction, Layout},
+    backend::{Backend, CrosstermBackend},
+    layout::{Alignment, Constraint, Direction, Layout, Margin, Rect},
     style::{Color, Modifier, Style},
     text::{Line, Span},
-    widgets::{Block, Borders, List, ListItem, ListState, Paragraph},
+    widgets::{
+        block::{Position, Title},
+        Block, Borders, Cell, List, ListItem, ListState, Paragraph, Row, Scrollbar, ScrollbarOrientation, ScrollbarState,
+        Table, TableState,
+    },
     Frame, Terminal,
 };
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::io;
 use std::path::Path;
+use std::time::{Duration, Instant};
 
-use crate::ssh_config::{SshConfig, SshHost};
-use crate::form::HostForm;
-use crate::config::AppConfig;
-use crate::connectivity::ConnectivityTest;
-use fuzzy_matcher::FuzzyMatcher;
-use fuzzy_matcher::skim::SkimMatcherV2;
+use crate::ssh_config::{self, ConfigFile, SshConfig, SshHost};
+use crate::form::{HostForm, FIELD_COUNT};
+use crate::config::{AppConfig, Destructive, RestoreTunnels, SavedSearch};
+use crate::connectivity::{ConnectivityTest, ReachMethod};
+use crate::metadata::{self, MetadataStore};
+use crate::state::{self, CheckResult, SavedState, SavedTunnel};
+use crate::sweep::{CheckTarget, HostTest, Sweep};
+use crate::export::{self, ReachabilityRow};
+use crate::index::HostIndex;
+use crate::search::{self, SearchResults, SearchWorker};
+use crate::db::{self, HostDb, HostRecord};
+use crate::git::{self, GitStatus, PullOutcome};
+use crate::sync;
+use crate::backup;
+use crate::diff::{self, FileDiff, FileStatus, RowKind};
+use crate::conflict::{FileMerge, HostBlock, Resolution};
+use crate::share;
+use crate::topology::{self, TopologyRow};
+use crate::highlight;
+use crate::effective;
+use crate::folder::{self, FolderMeta};
+use crate::forward::ForwardSpec;
+use crate::tunnel::{TunnelManager, TunnelStatus};
+use crate::launch;
+use crate::privacy;
+use crate::theme;
+use crate::table::{self, Column, TableSort};
+use crate::actions::{self, QuickAction};
+use crate::trash::{Trash, TrashEntry};
+use crate::help;
+use crate::audit::{self, Finding, Severity};
+use crate::keys::{self, KeyPair, KeyUsage};
+use crate::rotation::{Rotation, RotationStep};
+use crate::recording::{self, Recording};
 
 #[derive(PartialEq, Clone)]
 pub enum AppState {
@@ -30,45 +64,310 @@ pub enum AppState {
     ConfirmEdit,
     Search,
     Popup,
+    JumpChain,
+    Topology,
+    Includes,
+    Prompt,
+    TemplatePicker,
+    Tunnels,
+    RestoreTunnels,
+    SyncDiff,
+    Conflicts,
+    QrCode,
+    SmartGroups,
+    QuickActions,
+    ConfirmDelete,
+    ConfirmOverwrite,
+    ConfirmMerge,
+    Trash,
+    History,
+    Audit,
+    IdentityPicker,
+    KeyMap,
+    Rotation,
+    Help,
+}
+
+// Destino da sincronização em revisão na tela de diff
+#[derive(PartialEq, Clone)]
+pub enum SyncTarget {
+    Host(String),
+    GitPush,
+}
+
+#[derive(PartialEq, Clone)]
+pub enum PromptAction {
+    RenameInclude(usize),
+    NewFolder,
+    AdHocForward(usize),
+    CheckCommand(usize),
+    Console(usize),
+    Desktop(usize),
+    Notes(usize),
+    Tags(usize),
+    Mac(usize),
+    SyncTo(usize),
+    RemoteEdit(usize),
+    RunCommand(usize),
+    ConnectWithOptions(usize),
+    SaveSearch(String),
+    ExportReachability,
+    ExportAudit,
+    ExportAliases,
 }
 
 pub struct App {
     hosts: Vec<SshHost>,
+    files: Vec<ConfigFile>,
     list_state: ListState,
     state: AppState,
     form: HostForm,
     app_config: AppConfig,
     search_query: String,
     filtered_hosts: Vec<usize>,
-    matcher: SkimMatcherV2,
+    // Resultados que casaram por outro campo (HostName, User...) e não pelo nome
+    search_fields: HashMap<usize, usize>,
+    // Matcher da busca, refeito no desenho só para as posições a destacar
+    highlighter: Box<dyn search::Matcher>,
+    suggestions: Vec<usize>,
+    search: SearchWorker,
     editing_host_index: Option<usize>,
     popup_message: String,
+    popup_title: String,
     previous_state: AppState,
+    jump_chain: Vec<String>,
+    jump_list_state: ListState,
+    topology_rows: Vec<TopologyRow>,
+    topology_state: ListState,
+    include_state: ListState,
+    raw_view: bool,
+    raw_cache: Option<(usize, Vec<String>)>,
+    needs_clear: bool,
+    prompt_action: Option<PromptAction>,
+    prompt_title: String,
+    prompt_input: String,
+    merge_source: Option<usize>,
+    template_state: ListState,
+    form_state: AppState,
+    tunnels: TunnelManager,
+    tunnel_state: ListState,
+    metadata: MetadataStore,
+    saved_state: SavedState,
+    sweep: Option<Sweep>,
+    // Teste do `p` em andamento
+    host_test: Option<HostTest>,
+    // Túnel que o `b` espera para abrir o navegador: índice, URL e prazo
+    browser_wait: Option<(usize, String, Instant)>,
+    // Resultados de trabalho em segundo plano que chegaram fora da lista:
+    // aparecem quando o usuário volta a ela, sem roubar o prompt ou o formulário
+    deferred_popups: Vec<(String, String)>,
+    index: HostIndex,
+    list_offset: usize,
+    search_offset: usize,
+    // Área da lista no último quadro e último clique, para o mouse
+    list_area: Rect,
+    last_click: Option<(usize, Instant)>,
+    shadow_cache: Option<(usize, Vec<effective::Shadowed>, audit::HostKeyPolicy)>,
+    restored_search: Option<String>,
+    // `lazysshrs pick`: Enter devolve o host em `picked` em vez de ir para a lista
+    pick_mode: bool,
+    picked: Option<String>,
+    host_db: Option<HostDb>,
+    git_status: Option<GitStatus>,
+    sync_target: Option<SyncTarget>,
+    sync_files: Vec<FileDiff>,
+    sync_state: ListState,
+    diff_scroll: u16,
+    // Primeira linha visível da ajuda
+    help_scroll: u16,
+    merges: Vec<FileMerge>,
+    // Conflitos de um host importado da área de transferência, não de um git pull
+    import_merge: bool,
+    conflict_state: ListState,
+    qr_host: usize,
+    qr_json: bool,
+    group_state: ListState,
+    // Hosts de cada busca salva; calculado ao abrir a tela e refeito quando os hosts mudam
+    group_counts: Option<Vec<usize>>,
+    table_view: bool,
+    table_sort: Option<TableSort>,
+    table_rows: Option<Vec<usize>>,
+    privacy_mode: bool,
+    // Seções de include recolhidas (pelo arquivo do separador) e as linhas
+    // visíveis da lista que resultam delas
+    collapsed: HashSet<String>,
+    list_rows: Option<Vec<usize>>,
+    // Hosts com índice 1–9 no último quadro desenhado, para conexão rápida
+    quick_targets: Vec<usize>,
+    // Host do menu de ações rápidas e o que aguarda confirmação para ser apagado
+    action_host: usize,
+    // Últimos argumentos de "connect with options", só nesta sessão
+    last_connect_args: String,
+    // Terminal onde as sessões abrem em abas; `None` também depois de uma falha
+    tab_terminal: Option<launch::TabTerminal>,
+    action_state: ListState,
+    pending_delete: Option<usize>,
+    // Origem e destino da mescla de includes aguardando confirmação
+    pending_merge: Option<(usize, usize)>,
+    // Hosts apagados, carregados ao abrir a lixeira
+    trash: Trash,
+    trash_state: ListState,
+    // Tela de histórico: o host e suas sessões gravadas
+    history_host: usize,
+    recordings: Vec<Recording>,
+    recordings_state: ListState,
+    // Achados da auditoria, recalculados ao abrir a tela
+    audit: Vec<(usize, Vec<Finding>)>,
+    audit_state: ListState,
+    // IdentityFile ausentes ou ilegíveis, pelo valor escrito
+    key_problems: BTreeMap<String, &'static str>,
+    // Hosts com ForwardAgent efetivo, e se estão em `trusted_hosts`
+    agent_forwarding: BTreeMap<usize, bool>,
+    // Chaves de `~/.ssh` oferecidas para o IdentityFile vazio; a linha 0 é "nenhuma"
+    identity_choices: Vec<KeyPair>,
+    identity_state: ListState,
+    // Mapa chave → hosts, recalculado ao abrir a tela
+    key_map: Vec<KeyUsage>,
+    key_map_state: ListState,
+    // Rotação de chave aberta no assistente; também fica gravada em disco
+    rotation: Option<Rotation>,
 }
 
 impl App {
-    pub fn new(config: SshConfig, app_config: AppConfig) -> Self {
+    pub fn new(mut config: SshConfig, app_config: AppConfig, host_db: Option<HostDb>) -> Self {
+        folder::arrange_sections(&mut config, &app_config.section_order);
+        let mut warnings = config.warnings;
+        let key_problems = keys::check_identities(&config.hosts);
+        let agent_forwarding = audit::agent_forwarding(&config.hosts, &app_config.trusted_hosts);
+        let metadata = MetadataStore::load();
+        warnings.extend(metadata.load_error().map(str::to_string));
+        let haystack = search::haystack(&config.hosts, &metadata, app_config.search_frecency_weight);
+        let debounce = Duration::from_millis(app_config.search_debounce_ms);
+        let search = SearchWorker::spawn(haystack, debounce, app_config.matcher);
+        let highlighter = search::matcher(app_config.matcher);
+        let table_view = app_config.table_view;
+        let collapsed = app_config.collapsed_sections.iter().cloned().collect();
+        let privacy_mode = app_config.privacy_mode;
+        let tab_terminal = if app_config.terminal_tabs { launch::TabTerminal::detect() } else { None };
         let mut app = Self {
             hosts: config.hosts,
+            files: config.files,
             list_state: ListState::default(),
             state: AppState::List,
             form: HostForm::default(),
             app_config,
             search_query: String::new(),
             filtered_hosts: Vec::new(),
-            matcher: SkimMatcherV2::default(),
+            search_fields: HashMap::new(),
+            highlighter,
+            suggestions: Vec::new(),
+            search,
             editing_host_index: None,
             popup_message: String::new(),
+            popup_title: String::new(),
             previous_state: AppState::List,
+            jump_chain: Vec::new(),
+            jump_list_state: ListState::default(),
+            topology_rows: Vec::new(),
+            topology_state: ListState::default(),
+            include_state: ListState::default(),
+            raw_view: false,
+            raw_cache: None,
+            needs_clear: false,
+            prompt_action: None,
+            prompt_title: String::new(),
+            prompt_input: String::new(),
+            merge_source: None,
+            template_state: ListState::default(),
+            group_state: ListState::default(),
+            group_counts: None,
+            table_view,
+            table_sort: None,
+            table_rows: None,
+            privacy_mode,
+            collapsed,
+            list_rows: None,
+            quick_targets: Vec::new(),
+            action_host: 0,
+            last_connect_args: String::new(),
+            tab_terminal,
+            action_state: ListState::default(),
+            pending_delete: None,
+            pending_merge: None,
+            trash: Trash::default(),
+            trash_state: ListState::default(),
+            history_host: 0,
+            recordings: Vec::new(),
+            recordings_state: ListState::default(),
+            audit: Vec::new(),
+            audit_state: ListState::default(),
+            key_problems,
+            agent_forwarding,
+            identity_choices: Vec::new(),
+            identity_state: ListState::default(),
+            key_map: Vec::new(),
+            key_map_state: ListState::default(),
+            rotation: None,
+            form_state: AppState::Form,
+            tunnels: TunnelManager::default(),
+            tunnel_state: ListState::default(),
+            metadata,
+            saved_state: SavedState::load(),
+            sweep: None,
+            host_test: None,
+            browser_wait: None,
+            deferred_popups: Vec::new(),
+            index: HostIndex::default(),
+            list_offset: 0,
+            search_offset: 0,
+            list_area: Rect::default(),
+            last_click: None,
+            shadow_cache: None,
+            restored_search: None,
+            pick_mode: false,
+            picked: None,
+            host_db,
+            git_status: None,
+            sync_target: None,
+            sync_files: Vec::new(),
+            sync_state: ListState::default(),
+            diff_scroll: 0,
+            help_scroll: 0,
+            merges: Vec::new(),
+            import_merge: false,
+            conflict_state: ListState::default(),
+            qr_host: 0,
+            qr_json: false,
         };
+        app.git_status = git::status(&app.app_config.get_workdir());
+        app.index = HostIndex::build(&app.hosts);
         if !app.hosts.is_empty() {
-            let first_host = app.hosts.iter().position(|h| !h.is_separator).unwrap_or(0);
-            app.list_state.select(Some(first_host));
+            // Volta para onde o usuário estava na última execução
+            let restored = app.saved_state.selected_host.as_deref().and_then(|name| app.index.position(name));
+            app.list_state.select(Some(restored.or(app.index.first_host()).unwrap_or(0)));
+        }
+        app.restored_search = app.saved_state.last_search.clone();
+        // Aparece quando a lista estiver na frente (no `pick`, nunca)
+        if !warnings.is_empty() {
+            app.deferred_popups.push(("Configuração".to_string(), warnings.join("\n")));
         }
         app
     }
 
     pub fn run(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+        // Túneis e varredura só na interface completa: o `pick` sai logo e
+        // derrubaria os túneis restaurados
+        if !self.saved_state.tunnels.is_empty() {
+            match self.app_config.restore_tunnels {
+                RestoreTunnels::Ask => self.state = AppState::RestoreTunnels,
+                RestoreTunnels::Auto => self.restore_tunnels(),
+                RestoreTunnels::Never => {}
+            }
+        }
+        if self.app_config.sweep_on_startup {
+            self.start_sweep();
+        }
+
         enable_raw_mode()?;
         let mut stdout = io::stdout();
         execute!(stdout, EnterAlternateScreen, EnableMouseCapture)?;
@@ -76,6 +375,7 @@ impl App {
         let mut terminal = Terminal::new(backend)?;
 
         let result = self.run_app(&mut terminal);
+        self.save_session();
 
         disable_raw_mode()?;
         execute!(
@@ -88,14 +388,92 @@ impl App {
         result
     }
 
-    fn run_app(&mut self, terminal: &mut Terminal<CrosstermBackend<io::Stdout>>) -> Result<(), Box<dyn std::error::Error>> {
+    // Só a busca, desenhada no stderr para o stdout ficar livre para a alias
+    pub fn pick(&mut self) -> Result<Option<String>, Box<dyn std::error::Error>> {
+        self.pick_mode = true;
+        self.state = AppState::Search;
+        self.update_search();
+        self.list_state.select(self.filtered_hosts.first().copied());
+
+        enable_raw_mode()?;
+        let mut stderr = io::stderr();
+        execute!(stderr, EnterAlternateScreen)?;
+        let mut terminal = Terminal::new(CrosstermBackend::new(stderr))?;
+
+        let result = self.run_app(&mut terminal);
+
+        disable_raw_mode()?;
+        execute!(terminal.backend_mut(), LeaveAlternateScreen)?;
+        terminal.show_cursor()?;
+
+        result.map(|_| self.picked.take())
+    }
+
+    fn run_app<B: Backend>(&mut self, terminal: &mut Terminal<B>) -> Result<(), Box<dyn std::error::Error>> {
         loop {
+            // Após devolver o terminal a um processo externo, redesenhar tudo
+            if self.needs_clear {
+                terminal.clear()?;
+                self.needs_clear = false;
+            }
+            self.poll_sweep();
+            self.poll_host_test();
+            self.poll_browser_wait();
+            if self.state == AppState::List && !self.deferred_popups.is_empty() {
+                let (title, message) = self.deferred_popups.remove(0);
+                self.show_popup(&title, message);
+            }
+            if let Some(results) = self.search.poll() {
+                self.apply_search_results(results);
+            }
             terminal.draw(|f| self.ui(f))?;
 
-            if let Event::Key(key) = event::read()? {
+            // Sem teclas, redesenha periodicamente para refletir o trabalho em segundo plano;
+            // no modo acessível, com menos frequência
+            let tick = match (self.search.is_pending(), self.app_config.accessible) {
+                (true, _) => 10,
+                (false, false) if self.host_test.is_some() || self.browser_wait.is_some() => 100,
+                (false, false) => 200,
+                (false, true) => 1000,
+            };
+            if !event::poll(Duration::from_millis(tick))? {
+                continue;
+            }
+            let event = event::read()?;
+            if let Event::Mouse(mouse) = event {
+                self.handle_mouse(mouse);
+            }
+            if let Event::Key(key) = event {
+                // F1 abre a ajuda em qualquer tela; `?`, só onde não é texto digitado
+                if key.code == KeyCode::F(1) || key.code == KeyCode::Char('?') {
+                    if let Some((section, typing)) = self.help_section() {
+                        if !typing || key.code == KeyCode::F(1) {
+                            self.open_help(section);
+                            continue;
+                        }
+                    }
+                }
                 match self.state {
                     AppState::List => match key.code {
                         KeyCode::Char('q') => return Ok(()),
+                        KeyCode::Char('P') => self.start_sweep_of(true),
+                        KeyCode::Char('d') => {
+                            if let Some(selected) = self.list_state.selected() {
+                                if self.hosts.get(selected).is_some_and(|h| !h.is_separator) {
+                                    if self.confirms(Destructive::Delete) {
+                                        self.pending_delete = Some(selected);
+                                        self.state = AppState::ConfirmDelete;
+                                    } else {
+                                        self.delete_host(selected);
+                                    }
+                                }
+                            }
+                        }
+                        KeyCode::Esc if self.sweep.is_some() => {
+                            if let Some(sweep) = &self.sweep {
+                                sweep.cancel();
+                            }
+                        }
                         KeyCode::Char('a') => {
                             self.state = AppState::Form;
                             self.form = HostForm::default();
@@ -120,14 +498,41 @@ impl App {
                                 }
                             }
                         }
+                        KeyCode::Char(c @ '1'..='9') if self.app_config.quick_connect => {
+                            self.quick_connect(c as usize - '1' as usize);
+                        }
+                        KeyCode::Enter | KeyCode::Char(' ') if !self.table_view && self.selected_separator().is_some() => {
+                            if let Some(separator) = self.selected_separator() {
+                                self.toggle_section(separator);
+                            }
+                        }
+                        // ←: recolhe a seção (de um host, volta ao separador); →: expande
+                        KeyCode::Left if !self.table_view && !self.saved_state.sort_recent => {
+                            if let Some(separator) = self.list_state.selected().and_then(|i| self.section_of(i)) {
+                                self.set_collapsed(separator, true);
+                                self.list_state.select(Some(separator));
+                            }
+                        }
+                        KeyCode::Right if !self.table_view && !self.saved_state.sort_recent => {
+                            if let Some(separator) = self.selected_separator() {
+                                self.set_collapsed(separator, false);
+                            }
+                        }
+                        KeyCode::Char(' ') => {
+                            if let Some(selected) = self.list_state.selected() {
+                                if self.hosts.get(selected).is_some_and(|h| !h.is_separator) {
+                                    self.action_host = selected;
+                                    self.action_state.select(Some(0));
+                                    self.state = AppState::QuickActions;
+                                }
+                            }
+                        }
                         KeyCode::Enter => {
                             if let Some(selected) = self.list_state.selected() {
                                 if let Some(host) = self.hosts.get(selected).cloned() {
                                     if !host.is_separator {
                                         if let Err(e) = self.connect_ssh(&host) {
-                                            self.previous_state = self.state.clone();
-                                            self.popup_message = format!("Erro na conexão SSH: {}", e);
-                                            self.state = AppState::Popup;
+                                            self.show_popup("Conexão SSH", format!("Erro na conexão SSH: {}", e));
                                         }
                                     }
                                 }
@@ -135,11 +540,252 @@ impl App {
                         }
                         KeyCode::Char('/') => {
                             self.state = AppState::Search;
-                            self.search_query.clear();
+                            // A primeira busca da sessão retoma a última usada
+                            self.search_query = self.restored_search.take().unwrap_or_default();
                             self.update_search();
                         }
+                        KeyCode::Char('F') => {
+                            if self.app_config.saved_searches.is_empty() {
+                                self.show_popup("Grupos", "Nenhuma busca salva; use Ctrl+S na busca (/)".to_string());
+                            } else {
+                                self.group_state.select(Some(0));
+                                self.group_counts = None;
+                                self.state = AppState::SmartGroups;
+                            }
+                        }
+                        KeyCode::Char('h') => {
+                            if let Some(selected) = self.list_state.selected() {
+                                if self.hosts.get(selected).is_some_and(|h| !h.is_separator) {
+                                    self.open_history(selected);
+                                }
+                            }
+                        }
+                        KeyCode::Char('D') => {
+                            self.trash = Trash::load();
+                            if let Some(error) = self.trash.load_error() {
+                                self.show_popup("Lixeira", error.to_string());
+                            } else {
+                                self.trash_state.select(if self.trash.entries.is_empty() { None } else { Some(0) });
+                                self.state = AppState::Trash;
+                            }
+                        }
+                        KeyCode::Char('K') => {
+                            self.key_map = keys::key_usage(&self.hosts);
+                            self.key_map_state.select(if self.key_map.is_empty() { None } else { Some(0) });
+                            self.state = AppState::KeyMap;
+                        }
+                        KeyCode::Char('V') => {
+                            self.audit = audit::audit_all(&self.hosts, &self.app_config.trusted_hosts);
+                            self.audit_state.select(if self.audit.is_empty() { None } else { Some(0) });
+                            self.state = AppState::Audit;
+                        }
+                        KeyCode::Char('J') => {
+                            self.jump_chain.clear();
+                            self.jump_list_state.select(Some(0));
+                            self.state = AppState::JumpChain;
+                        }
+                        KeyCode::Char('G') => {
+                            self.topology_rows = topology::build_rows(&self.hosts);
+                            self.topology_state.select(if self.topology_rows.is_empty() { None } else { Some(0) });
+                            self.state = AppState::Topology;
+                        }
+                        KeyCode::Char('I') => {
+                            self.include_state.select(Some(0));
+                            self.state = AppState::Includes;
+                        }
+                        KeyCode::Char('f') => {
+                            if let Some(selected) = self.list_state.selected() {
+                                if self.hosts.get(selected).is_some_and(|h| !h.is_separator) {
+                                    self.open_prompt("Forward: porta local → host:porta", PromptAction::AdHocForward(selected), String::new());
+                                }
+                            }
+                        }
+                        KeyCode::Char('m') => {
+                            if let Some(selected) = self.list_state.selected() {
+                                if let Some(host) = self.hosts.get(selected).filter(|h| !h.is_separator) {
+                                    let name = host.name.clone();
+                                    if let Err(e) = self.metadata.update(&name, |meta| meta.reachability = ReachMethod::cycle(meta.reachability)) {
+                                        self.show_popup("Metadados", format!("Erro ao salvar metadados: {}", e));
+                                    }
+                                }
+                            }
+                        }
+                        KeyCode::Char('c') => {
+                            if let Some(selected) = self.list_state.selected() {
+                                if let Some(host) = self.hosts.get(selected).filter(|h| !h.is_separator) {
+                                    let current = self.metadata.get(&host.name).check_command.unwrap_or_default();
+                                    self.open_prompt("Health-check (vazio: padrão)", PromptAction::CheckCommand(selected), current);
+                                }
+                            }
+                        }
+                        KeyCode::Char('C') => {
+                            if let Some(selected) = self.list_state.selected() {
+                                if let Some(host) = self.hosts.get(selected).filter(|h| !h.is_separator) {
+                                    let current = self.metadata.get(&host.name).console.unwrap_or_default();
+                                    let title = "Conexão no lugar do ssh (ex.: telnet {hostname}, picocom -b 9600 /dev/ttyUSB0; vazio: ssh)";
+                                    self.open_prompt(title, PromptAction::Console(selected), current);
+                                }
+                            }
+                        }
+                        KeyCode::Char('R') => {
+                            if let Some(selected) = self.list_state.selected() {
+                                if let Some(host) = self.hosts.get(selected).filter(|h| !h.is_separator) {
+                                    let title = format!("Sincronizar com {} (pastas/arquivos; vazio: workdir inteiro)", host.name);
+                                    self.open_prompt(&title, PromptAction::SyncTo(selected), String::new());
+                                }
+                            }
+                        }
+                        KeyCode::Char('E') => {
+                            if let Some(selected) = self.list_state.selected() {
+                                if let Some(host) = self.hosts.get(selected).filter(|h| !h.is_separator) {
+                                    let title = format!("Abrir {} em {} (diretório remoto)", host.name, self.app_config.remote_editor);
+                                    let initial = self.metadata.get(&host.name).remote_dir.unwrap_or_else(|| "~".to_string());
+                                    self.open_prompt(&title, PromptAction::RemoteEdit(selected), initial);
+                                }
+                            }
+                        }
+                        KeyCode::Char('g') => {
+                            if let Some(selected) = self.list_state.selected() {
+                                if let Some(host) = self.hosts.get(selected).filter(|h| !h.is_separator) {
+                                    let title = format!("Acesso gráfico a {} (rdp://host:porta ou vnc://host:porta)", host.name);
+                                    let initial = self.metadata.get(&host.name).desktop.unwrap_or_else(|| "rdp://".to_string());
+                                    self.open_prompt(&title, PromptAction::Desktop(selected), initial);
+                                }
+                            }
+                        }
+                        KeyCode::Char('i') => self.import_from_clipboard(),
+                        KeyCode::Char('y') | KeyCode::Char('Y') => {
+                            if let Some(selected) = self.list_state.selected() {
+                                if self.hosts.get(selected).is_some_and(|h| !h.is_separator) {
+                                    self.copy_snippet(selected, key.code == KeyCode::Char('Y'));
+                                }
+                            }
+                        }
+                        KeyCode::Char('Q') => {
+                            if let Some(selected) = self.list_state.selected() {
+                                if self.hosts.get(selected).is_some_and(|h| !h.is_separator) {
+                                    self.qr_host = selected;
+                                    self.qr_json = false;
+                                    self.state = AppState::QrCode;
+                                }
+                            }
+                        }
+                        KeyCode::Char('U') => self.git_pull(),
+                        KeyCode::Char('S') => self.review_git_push(),
+                        KeyCode::Char('N') => {
+                            if let Some(selected) = self.list_state.selected() {
+                                if let Some(host) = self.hosts.get(selected).filter(|h| !h.is_separator) {
+                                    let current = self.metadata.get(&host.name).notes.unwrap_or_default();
+                                    self.open_prompt("Notas do host", PromptAction::Notes(selected), current);
+                                }
+                            }
+                        }
+                        KeyCode::Char('T') => {
+                            if let Some(selected) = self.list_state.selected() {
+                                if let Some(host) = self.hosts.get(selected).filter(|h| !h.is_separator) {
+                                    let current = self.metadata.get(&host.name).tags.join(", ");
+                                    self.open_prompt("Tags (separadas por vírgula)", PromptAction::Tags(selected), current);
+                                }
+                            }
+                        }
+                        KeyCode::Char('M') => {
+                            if let Some(selected) = self.list_state.selected() {
+                                if let Some(host) = self.hosts.get(selected).filter(|h| !h.is_separator) {
+                                    let current = self.metadata.get(&host.name).mac.unwrap_or_default();
+                                    self.open_prompt("Endereço MAC", PromptAction::Mac(selected), current);
+                                }
+                            }
+                        }
+                        KeyCode::Char('A') => {
+                            if let Some(selected) = self.list_state.selected() {
+                                if self.hosts.get(selected).is_some_and(|h| !h.is_separator) {
+                                    let (name, args) = self.host_shell_command(selected);
+                                    let message = format!("{}\n\n{}", export::alias_line(&name, &args), export::function_block(&name, &args));
+                                    self.show_popup("Shell Alias", message);
+                                }
+                            }
+                        }
+                        KeyCode::Char('O') => {
+                            if let Some(selected) = self.list_state.selected() {
+                                if self.hosts.get(selected).is_some_and(|h| !h.is_separator) {
+                                    self.prompt_connect_options(selected);
+                                }
+                            }
+                        }
+                        KeyCode::Char('k') => {
+                            if let Some(selected) = self.list_state.selected() {
+                                if self.hosts.get(selected).is_some_and(|h| !h.is_separator) {
+                                    let message = match self.fix_host_key_checking(selected) {
+                                        Ok(message) => message,
+                                        Err(e) => format!("Erro: {}", e),
+                                    };
+                                    self.show_popup("Host keys", message);
+                                }
+                            }
+                        }
+                        KeyCode::Char('W') => {
+                            // Fora do ~/.ssh, que costuma ser sincronizado e versionado
+                            let default = AppConfig::get_state_dir().join("aliases.sh").to_string_lossy().to_string();
+                            self.open_prompt("Exportar aliases de todos os hosts", PromptAction::ExportAliases, default);
+                        }
+                        KeyCode::Char('X') => {
+                            self.open_prompt("Exportar verificações (.csv ou .json)", PromptAction::ExportReachability, "reachability.csv".to_string());
+                        }
+                        KeyCode::Char('b') => {
+                            if let Some(selected) = self.list_state.selected() {
+                                if let Some(host) = self.hosts.get(selected).cloned() {
+                                    if !host.is_separator {
+                                        self.open_forward_in_browser(&host);
+                                    }
+                                }
+                            }
+                        }
+                        KeyCode::Char('t') => {
+                            self.tunnels.refresh();
+                            self.tunnels.refresh_persistent();
+                            let empty = self.tunnels.tunnels.is_empty() && self.tunnels.persistent.is_empty();
+                            self.tunnel_state.select(if empty { None } else { Some(0) });
+                            self.state = AppState::Tunnels;
+                        }
+                        KeyCode::Char('v') => {
+                            self.raw_view = !self.raw_view;
+                            self.raw_cache = None;
+                        }
+                        KeyCode::Char('o') => {
+                            if let Some(selected) = self.list_state.selected() {
+                                if let Some(host) = self.hosts.get(selected).cloned() {
+                                    if !host.is_separator {
+                                        if let Err(e) = self.open_in_editor(&host) {
+                                            self.show_popup("Editor", format!("Erro ao abrir o editor: {}", e));
+                                        }
+                                    }
+                                }
+                            }
+                        }
+                        KeyCode::Down if self.table_view => self.step_table(true),
+                        KeyCode::Up if self.table_view => self.step_table(false),
                         KeyCode::Down => self.next(),
                         KeyCode::Up => self.previous(),
+                        KeyCode::Char(']') | KeyCode::PageDown if !self.table_view => self.step_section(true),
+                        KeyCode::Char('[') | KeyCode::PageUp if !self.table_view => self.step_section(false),
+                        KeyCode::Char('H') => self.privacy_mode = !self.privacy_mode,
+                        KeyCode::Char('L') => {
+                            self.table_view = !self.table_view;
+                            self.list_offset = 0;
+                        }
+                        KeyCode::Char('s') if !self.table_view => {
+                            self.saved_state.sort_recent = !self.saved_state.sort_recent;
+                            self.list_rows = None;
+                            self.list_offset = 0;
+                        }
+                        KeyCode::Char('<') if self.table_view => self.cycle_table_sort(false),
+                        KeyCode::Char('>') if self.table_view => self.cycle_table_sort(true),
+                        KeyCode::Char('!') if self.table_view => {
+                            if let Some(sort) = &mut self.table_sort {
+                                sort.descending = !sort.descending;
+                                self.table_rows = None;
+                            }
+                        }
                         _ => {}
                     },
                     AppState::Form | AppState::Edit => match key.code {
@@ -147,17 +793,61 @@ impl App {
                             self.state = AppState::List;
                             self.editing_host_index = None;
                         }
-                        KeyCode::Tab => self.form.next_field(),
-                        KeyCode::BackTab => self.form.prev_field(),
+                        KeyCode::Char('t') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                            if self.app_config.templates.is_empty() {
+                                self.show_popup("Templates", "Nenhum template definido em ~/.config/lazysshrs".to_string());
+                            } else {
+                                self.form_state = self.state.clone();
+                                self.template_state.select(Some(0));
+                                self.state = AppState::TemplatePicker;
+                            }
+                        }
+                        KeyCode::Tab => {
+                            self.leave_form_field();
+                            self.form.next_field();
+                        }
+                        KeyCode::BackTab => {
+                            self.leave_form_field();
+                            self.form.prev_field();
+                        }
                         KeyCode::Enter => {
+                            self.leave_form_field();
                             if self.form.is_valid() {
-                                self.state = if self.editing_host_index.is_some() {
-                                    AppState::ConfirmEdit
+                                if self.app_config.confirm_forms {
+                                    self.state = if self.editing_host_index.is_some() {
+                                        AppState::ConfirmEdit
+                                    } else {
+                                        AppState::Confirm
+                                    };
                                 } else {
-                                    AppState::Confirm
-                                };
+                                    self.finish_form()?;
+                                }
                             }
                         }
+                        KeyCode::Char(' ') | KeyCode::Right if HostForm::is_toggle(self.form.current_field) => {
+                            self.form.cycle_field(self.form.current_field, true);
+                        }
+                        KeyCode::Left if HostForm::is_toggle(self.form.current_field) => {
+                            self.form.cycle_field(self.form.current_field, false);
+                        }
+                        KeyCode::Backspace if HostForm::is_toggle(self.form.current_field) => {
+                            self.form.set_field(self.form.current_field, String::new());
+                        }
+                        KeyCode::Char(_) if HostForm::is_toggle(self.form.current_field) => {}
+                        KeyCode::Char('n') if self.form.current_field == 7 && key.modifiers.contains(KeyModifiers::CONTROL) => {
+                            self.form.add_forward();
+                        }
+                        KeyCode::Char('d') if self.form.current_field == 7 && key.modifiers.contains(KeyModifiers::CONTROL) => {
+                            self.form.remove_forward();
+                        }
+                        KeyCode::Down if self.form.current_field == 5 && self.form.identity_file.is_empty() => {
+                            self.identity_choices = keys::find_keypairs();
+                            self.identity_state.select(Some(0));
+                            self.form_state = self.state.clone();
+                            self.state = AppState::IdentityPicker;
+                        }
+                        KeyCode::Down if self.form.current_field == 7 => self.form.select_forward(true),
+                        KeyCode::Up if self.form.current_field == 7 => self.form.select_forward(false),
                         KeyCode::Char(c) => {
                             let mut current = self.form.get_field(self.form.current_field).to_string();
                             current.push(c);
@@ -172,33 +862,74 @@ impl App {
                     },
                     AppState::Confirm => match key.code {
                         KeyCode::Esc => self.state = AppState::Form,
-                        KeyCode::Enter => {
-                            self.save_host()?;
-                            self.state = AppState::List;
-                            self.editing_host_index = None;
-                        }
+                        KeyCode::Enter => self.finish_form()?,
                         _ => {}
                     },
                     AppState::ConfirmEdit => match key.code {
                         KeyCode::Esc => self.state = AppState::Edit,
-                        KeyCode::Enter => {
-                            self.update_host()?;
-                            self.state = AppState::List;
-                            self.editing_host_index = None;
+                        KeyCode::Enter => self.finish_form()?,
+                        _ => {}
+                    },
+                    AppState::ConfirmOverwrite => match key.code {
+                        KeyCode::Enter | KeyCode::Char('y') => self.save_form()?,
+                        KeyCode::Esc | KeyCode::Char('n') => {
+                            self.state = if self.editing_host_index.is_some() { AppState::Edit } else { AppState::Form };
                         }
                         _ => {}
                     },
                     AppState::Search => match key.code {
+                        KeyCode::Esc if self.pick_mode => return Ok(()),
+                        KeyCode::Char('c') if self.pick_mode && key.modifiers.contains(KeyModifiers::CONTROL) => return Ok(()),
+                        KeyCode::Enter if self.pick_mode => {
+                            if let Some(results) = self.search.wait(Duration::from_secs(2)) {
+                                self.apply_search_results(results);
+                            }
+                            // O resultado selecionado, ou o primeiro que seja um host de verdade;
+                            // sem acertos, a primeira sugestão
+                            let selected = self.list_state.selected().filter(|i| self.filtered_hosts.contains(i));
+                            let chosen = selected.into_iter()
+                                .chain(self.filtered_hosts.iter().copied())
+                                .chain(self.suggestions.iter().copied())
+                                .find(|&i| self.is_target(&self.hosts[i]));
+                            if let Some(i) = chosen {
+                                self.picked = Some(effective::host_alias(&self.hosts[i]).to_string());
+                                return Ok(());
+                            }
+                        }
+                        KeyCode::Char(c @ '1'..='9') if self.pick_mode && key.modifiers.contains(KeyModifiers::ALT) => {
+                            if let Some(&i) = self.quick_targets.get(c as usize - '1' as usize) {
+                                self.picked = Some(effective::host_alias(&self.hosts[i]).to_string());
+                                return Ok(());
+                            }
+                        }
+                        KeyCode::Char('s') if self.pick_mode && key.modifiers.contains(KeyModifiers::CONTROL) => {}
                         KeyCode::Esc => {
                             self.state = AppState::List;
                             self.search_query.clear();
                         }
                         KeyCode::Enter => {
-                            if !self.filtered_hosts.is_empty() {
-                                self.list_state.select(Some(self.filtered_hosts[0]));
+                            if let Some(results) = self.search.wait(Duration::from_secs(2)) {
+                                self.apply_search_results(results);
+                            }
+                            // Sem acertos, Enter vai para a primeira sugestão
+                            if let Some(&first) = self.filtered_hosts.first().or(self.suggestions.first()) {
+                                self.list_state.select(Some(first));
+                            }
+                            self.saved_state.last_search = Some(self.search_query.clone()).filter(|q| !q.is_empty());
+                            self.state = AppState::List;
+                            self.search_query.clear();
+                        }
+                        KeyCode::Char('s') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                            let query = self.search_query.trim().to_string();
+                            if !query.is_empty() {
+                                self.open_prompt("Save search as", PromptAction::SaveSearch(query), String::new());
                             }
+                        }
+                        KeyCode::Char(c @ '1'..='9') if self.app_config.quick_connect && key.modifiers.contains(KeyModifiers::ALT) => {
+                            self.saved_state.last_search = Some(self.search_query.clone()).filter(|q| !q.is_empty());
                             self.state = AppState::List;
                             self.search_query.clear();
+                            self.quick_connect(c as usize - '1' as usize);
                         }
                         KeyCode::Char(c) => {
                             self.search_query.push(c);
@@ -208,599 +939,4530 @@ impl App {
                             self.search_query.pop();
                             self.update_search();
                         }
+                        KeyCode::Tab if self.filtered_hosts.is_empty() => {
+                            if let Some(&first) = self.suggestions.first() {
+                                self.search_query = self.hosts[first].name.clone();
+                                self.update_search();
+                            }
+                        }
                         KeyCode::Down => self.next_search_result(),
                         KeyCode::Up => self.prev_search_result(),
                         _ => {}
                     },
+                    AppState::QuickActions => match key.code {
+                        KeyCode::Esc => self.state = AppState::List,
+                        KeyCode::Down => {
+                            let i = self.action_state.selected().map(|i| (i + 1) % actions::ALL.len()).unwrap_or(0);
+                            self.action_state.select(Some(i));
+                        }
+                        KeyCode::Up => {
+                            let len = actions::ALL.len();
+                            let i = self.action_state.selected().map(|i| (i + len - 1) % len).unwrap_or(0);
+                            self.action_state.select(Some(i));
+                        }
+                        KeyCode::Enter => {
+                            if let Some(action) = self.action_state.selected().and_then(|i| actions::ALL.get(i)) {
+                                self.run_quick_action(*action);
+                            }
+                        }
+                        KeyCode::Char(c) => {
+                            if let Some(action) = QuickAction::from_key(c) {
+                                self.run_quick_action(action);
+                            }
+                        }
+                        _ => {}
+                    },
+                    AppState::ConfirmMerge => match key.code {
+                        KeyCode::Enter | KeyCode::Char('y') => {
+                            self.state = AppState::Includes;
+                            if let Some((source, target)) = self.pending_merge.take() {
+                                self.finish_merge(source, target);
+                            }
+                        }
+                        KeyCode::Esc | KeyCode::Char('n') => {
+                            self.pending_merge = None;
+                            self.state = AppState::Includes;
+                        }
+                        _ => {}
+                    },
+                    AppState::ConfirmDelete => match key.code {
+                        KeyCode::Enter | KeyCode::Char('y') => {
+                            self.state = AppState::List;
+                            if let Some(index) = self.pending_delete.take() {
+                                self.delete_host(index);
+                            }
+                        }
+                        KeyCode::Esc | KeyCode::Char('n') => {
+                            self.pending_delete = None;
+                            self.state = AppState::List;
+                        }
+                        _ => {}
+                    },
                     AppState::Popup => match key.code {
                         KeyCode::Enter | KeyCode::Esc => {
                             self.state = self.previous_state.clone();
                         }
                         _ => {}
                     },
-                }
-            }
-        }
-    }
-
-    fn ui(&mut self, f: &mut Frame) {
-        match self.state {
-            AppState::List => self.render_list(f),
-            AppState::Form => self.render_form(f, "Add Host"),
-            AppState::Edit => self.render_form(f, "Edit Host"),
-            AppState::Confirm => self.render_confirm(f, "Confirm New Host"),
-            AppState::ConfirmEdit => self.render_confirm(f, "Confirm Changes"),
-            AppState::Search => self.render_search(f),
-            AppState::Popup => {
-                // Renderizar estado anterior como fundo
-                match self.previous_state {
-                    AppState::List => self.render_list(f),
-                    AppState::Search => self.render_search(f),
-                    _ => self.render_list(f),
-                }
-                // Renderizar popup por cima
-                self.render_popup(f);
-            }
-        }
-    }
-
-    fn render_list(&mut self, f: &mut Frame) {
-        let chunks = Layout::default()
-            .direction(Direction::Horizontal)
-            .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
-            .split(f.size());
-
-        let items: Vec<ListItem> = self
-            .hosts
-            .iter()
-            .map(|host| {
-                if host.is_separator {
-                    ListItem::new(Line::from(Span::styled(&host.name, Style::default().fg(Color::Gray))))
-                } else {
-                    ListItem::new(Line::from(Span::raw(&host.name)))
-                }
-            })
-            .collect();
-
-        let hosts_list = List::new(items)
-            .block(Block::default().borders(Borders::ALL).title("SSH Hosts (Enter: connect, a: add, e: edit, p: ping, /: search)"))
-            .highlight_style(Style::default().add_modifier(Modifier::BOLD))
-            .highlight_symbol(">> ");
-
-        f.render_stateful_widget(hosts_list, chunks[0], &mut self.list_state);
-
-        let selected_host = self.list_state.selected()
-            .and_then(|i| self.hosts.get(i))
-            .filter(|host| !host.is_separator);
-
-        let details = if let Some(host) = selected_host {
-            let mut lines = vec![
-                Line::from(vec![
-                    Span::styled("Host: ", Style::default().fg(Color::Yellow)),
-                    Span::raw(&host.name),
-                ]),
-            ];
-
-            if let Some(hostname) = &host.hostname {
-                lines.push(Line::from(vec![
+                    AppState::Help => match key.code {
+                        KeyCode::Esc | KeyCode::Enter | KeyCode::Char('?') | KeyCode::Char('q') | KeyCode::F(1) => {
+                            self.state = self.previous_state.clone();
+                        }
+                        KeyCode::Down => self.help_scroll = self.help_scroll.saturating_add(1),
+                        KeyCode::Up => self.help_scroll = self.help_scroll.saturating_sub(1),
+                        KeyCode::PageDown => self.help_scroll = self.help_scroll.saturating_add(10),
+                        KeyCode::PageUp => self.help_scroll = self.help_scroll.saturating_sub(10),
+                        KeyCode::Home => self.help_scroll = 0,
+                        _ => {}
+                    },
+                    AppState::JumpChain => match key.code {
+                        KeyCode::Esc => self.state = AppState::List,
+                        KeyCode::Down => {
+                            let len = self.jump_candidates().len();
+                            if len > 0 {
+                                let i = self.jump_list_state.selected().map(|i| (i + 1) % len).unwrap_or(0);
+                                self.jump_list_state.select(Some(i));
+                            }
+                        }
+                        KeyCode::Up => {
+                            let len = self.jump_candidates().len();
+                            if len > 0 {
+                                let i = self.jump_list_state.selected().map(|i| if i == 0 { len - 1 } else { i - 1 }).unwrap_or(0);
+                                self.jump_list_state.select(Some(i));
+                            }
+                        }
+                        KeyCode::Enter => {
+                            let candidates = self.jump_candidates();
+                            if let Some(&host_index) = self.jump_list_state.selected().and_then(|i| candidates.get(i)) {
+                                let name = self.hosts[host_index].name.clone();
+                                if !self.jump_chain.contains(&name) {
+                                    self.jump_chain.push(name);
+                                }
+                            }
+                        }
+                        KeyCode::Backspace => {
+                            self.jump_chain.pop();
+                        }
+                        KeyCode::Char('c') => {
+                            if let Some((target, jumps)) = self.jump_chain.split_last() {
+                                let target = target.clone();
+                                let args = ConnectivityTest::jump_args(jumps);
+                                if let Err(e) = self.run_ssh(&target, &args) {
+                                    self.show_popup("Conexão SSH", format!("Erro na conexão SSH: {}", e));
+                                }
+                            }
+                        }
+                        KeyCode::Char('s') => self.save_jump_chain(),
+                        _ => {}
+                    },
+                    AppState::Topology => match key.code {
+                        KeyCode::Esc => self.state = AppState::List,
+                        KeyCode::Down => {
+                            let len = self.topology_rows.len();
+                            if len > 0 {
+                                let i = self.topology_state.selected().map(|i| (i + 1) % len).unwrap_or(0);
+                                self.topology_state.select(Some(i));
+                            }
+                        }
+                        KeyCode::Up => {
+                            let len = self.topology_rows.len();
+                            if len > 0 {
+                                let i = self.topology_state.selected().map(|i| if i == 0 { len - 1 } else { i - 1 }).unwrap_or(0);
+                                self.topology_state.select(Some(i));
+                            }
+                        }
+                        KeyCode::Enter => {
+                            if let Some(row) = self.topology_state.selected().and_then(|i| self.topology_rows.get(i)) {
+                                if let Some(index) = self.index.position(&row.name) {
+                                    self.list_state.select(Some(index));
+                                    self.state = AppState::List;
+                                }
+                            }
+                        }
+                        _ => {}
+                    },
+                    AppState::Includes => match key.code {
+                        KeyCode::Esc if self.merge_source.is_some() => self.merge_source = None,
+                        KeyCode::Esc => self.state = AppState::List,
+                        KeyCode::Char('r') => {
+                            if let Some(i) = self.include_state.selected() {
+                                let current = self.include_display_name(i);
+                                self.open_prompt("Novo nome", PromptAction::RenameInclude(i), current);
+                            }
+                        }
+                        KeyCode::Char('n') => {
+                            self.open_prompt("Nome da nova pasta", PromptAction::NewFolder, String::new());
+                        }
+                        KeyCode::Char('m') => {
+                            if let Some(i) = self.include_state.selected() {
+                                match self.merge_source.take() {
+                                    None => self.merge_source = Some(i),
+                                    Some(source) if self.confirms(Destructive::IncludeRemoval) => {
+                                        self.pending_merge = Some((source, i));
+                                        self.state = AppState::ConfirmMerge;
+                                    }
+                                    Some(source) => self.finish_merge(source, i),
+                                }
+                            }
+                        }
+                        KeyCode::Down => {
+                            let len = self.files.len();
+                            if len > 0 {
+                                let i = self.include_state.selected().map(|i| (i + 1) % len).unwrap_or(0);
+                                self.include_state.select(Some(i));
+                            }
+                        }
+                        KeyCode::Up => {
+                            let len = self.files.len();
+                            if len > 0 {
+                                let i = self.include_state.selected().map(|i| if i == 0 { len - 1 } else { i - 1 }).unwrap_or(0);
+                                self.include_state.select(Some(i));
+                            }
+                        }
+                        KeyCode::Enter => {
+                            if let Some(file) = self.include_state.selected().and_then(|i| self.files.get(i)) {
+                                match file.first_host {
+                                    Some(index) => {
+                                        self.list_state.select(Some(index));
+                                        self.state = AppState::List;
+                                    }
+                                    None => {
+                                        let message = format!("{} não possui hosts", file.path.display());
+                                        self.show_popup("Includes", message);
+                                    }
+                                }
+                            }
+                        }
+                        _ => {}
+                    },
+                    AppState::TemplatePicker => match key.code {
+                        KeyCode::Esc => self.state = self.form_state.clone(),
+                        KeyCode::Down => {
+                            let len = self.app_config.templates.len();
+                            let i = self.template_state.selected().map(|i| (i + 1) % len).unwrap_or(0);
+                            self.template_state.select(Some(i));
+                        }
+                        KeyCode::Up => {
+                            let len = self.app_config.templates.len();
+                            let i = self.template_state.selected().map(|i| if i == 0 { len - 1 } else { i - 1 }).unwrap_or(0);
+                            self.template_state.select(Some(i));
+                        }
+                        KeyCode::Enter => {
+                            if let Some(template) = self.template_state.selected().and_then(|i| self.app_config.templates.get(i)) {
+                                self.form.apply_template(template);
+                            }
+                            self.state = self.form_state.clone();
+                        }
+                        _ => {}
+                    },
+                    AppState::IdentityPicker => match key.code {
+                        KeyCode::Esc => self.state = self.form_state.clone(),
+                        KeyCode::Down => {
+                            let len = self.identity_choices.len() + 1;
+                            let i = self.identity_state.selected().map(|i| (i + 1) % len).unwrap_or(0);
+                            self.identity_state.select(Some(i));
+                        }
+                        KeyCode::Up => {
+                            let len = self.identity_choices.len() + 1;
+                            let i = self.identity_state.selected().map(|i| if i == 0 { len - 1 } else { i - 1 }).unwrap_or(0);
+                            self.identity_state.select(Some(i));
+                        }
+                        // "Nenhuma" deixa o campo vazio: vale o agente ou as chaves padrão do ssh
+                        KeyCode::Enter => {
+                            let choice = self.identity_state.selected()
+                                .and_then(|i| i.checked_sub(1))
+                                .and_then(|i| self.identity_choices.get(i));
+                            if let Some(pair) = choice {
+                                self.form.set_field(5, pair.path.clone());
+                            }
+                            self.state = self.form_state.clone();
+                        }
+                        _ => {}
+                    },
+                    AppState::SmartGroups => match key.code {
+                        KeyCode::Esc => self.state = AppState::List,
+                        KeyCode::Down => {
+                            let len = self.app_config.saved_searches.len();
+                            let i = self.group_state.selected().map(|i| (i + 1) % len).unwrap_or(0);
+                            self.group_state.select(Some(i));
+                        }
+                        KeyCode::Up => {
+                            let len = self.app_config.saved_searches.len();
+                            let i = self.group_state.selected().map(|i| if i == 0 { len - 1 } else { i - 1 }).unwrap_or(0);
+                            self.group_state.select(Some(i));
+                        }
+                        // Aplica a consulta na busca, onde dá para refiná-la
+                        KeyCode::Enter => {
+                            if let Some(group) = self.group_state.selected().and_then(|i| self.app_config.saved_searches.get(i)) {
+                                self.search_query = group.query.clone();
+                                self.restored_search = None;
+                                self.state = AppState::Search;
+                                self.update_search();
+                            }
+                        }
+                        KeyCode::Char('d') => {
+                            if let Some(i) = self.group_state.selected().filter(|&i| i < self.app_config.saved_searches.len()) {
+                                self.app_config.saved_searches.remove(i);
+                                if let Some(counts) = self.group_counts.as_mut().filter(|c| i < c.len()) {
+                                    counts.remove(i);
+                                }
+                                if let Err(e) = self.app_config.save() {
+                                    self.show_popup("Grupos", format!("Erro ao salvar a configuração: {}", e));
+                                }
+                                let len = self.app_config.saved_searches.len();
+                                if len == 0 {
+                                    self.state = AppState::List;
+                                } else {
+                                    self.group_state.select(Some(i.min(len - 1)));
+                                }
+                            }
+                        }
+                        _ => {}
+                    },
+                    AppState::KeyMap => match key.code {
+                        KeyCode::Esc => self.state = AppState::List,
+                        KeyCode::Down => {
+                            let len = self.key_map.len();
+                            if len > 0 {
+                                let i = self.key_map_state.selected().map(|i| (i + 1) % len).unwrap_or(0);
+                                self.key_map_state.select(Some(i));
+                            }
+                        }
+                        KeyCode::Up => {
+                            let len = self.key_map.len();
+                            if len > 0 {
+                                let i = self.key_map_state.selected().map(|i| if i == 0 { len - 1 } else { i - 1 }).unwrap_or(0);
+                                self.key_map_state.select(Some(i));
+                            }
+                        }
+                        // Uma rotação por vez: se já houver uma, ela é retomada
+                        KeyCode::Char('r') => match Rotation::load() {
+                            Some(rotation) => {
+                                self.rotation = Some(rotation);
+                                self.state = AppState::Rotation;
+                            }
+                            None => {
+                                let planned = self.key_map_state.selected()
+                                    .and_then(|i| self.key_map.get(i))
+                                    .map(|usage| Rotation::plan(&self.hosts, usage).and_then(|r| r.save().map(|_| r)));
+                                match planned {
+                                    Some(Ok(rotation)) => {
+                                        self.rotation = Some(rotation);
+                                        self.state = AppState::Rotation;
+                                    }
+                                    Some(Err(e)) => self.show_popup("Rotação de chave", format!("Erro: {}", e)),
+                                    None => {}
+                                }
+                            }
+                        },
+                        _ => {}
+                    },
+                    AppState::Rotation => match key.code {
+                        KeyCode::Esc => self.state = AppState::List,
+                        KeyCode::Enter => self.run_rotation_step()?,
+                        KeyCode::Char('s') => {
+                            if let Some(rotation) = self.rotation.as_mut() {
+                                rotation.step = match rotation.step {
+                                    RotationStep::Push => RotationStep::Update,
+                                    RotationStep::RemoveOld => RotationStep::Done,
+                                    step => step,
+                                };
+                                if let Err(e) = rotation.save() {
+                                    self.show_popup("Rotação de chave", format!("Erro ao salvar: {}", e));
+                                }
+                            }
+                        }
+                        KeyCode::Char('x') => {
+                            self.rotation = None;
+                            match Rotation::clear() {
+                                Ok(()) => self.state = AppState::List,
+                                Err(e) => self.show_popup("Rotação de chave", format!("Erro ao descartar: {}", e)),
+                            }
+                        }
+                        _ => {}
+                    },
+                    AppState::Audit => match key.code {
+                        KeyCode::Esc => self.state = AppState::List,
+                        KeyCode::Down => {
+                            let len = self.audit.len();
+                            if len > 0 {
+                                let i = self.audit_state.selected().map(|i| (i + 1) % len).unwrap_or(0);
+                                self.audit_state.select(Some(i));
+                            }
+                        }
+                        KeyCode::Up => {
+                            let len = self.audit.len();
+                            if len > 0 {
+                                let i = self.audit_state.selected().map(|i| if i == 0 { len - 1 } else { i - 1 }).unwrap_or(0);
+                                self.audit_state.select(Some(i));
+                            }
+                        }
+                        KeyCode::Char('x') => {
+                            self.open_prompt("Exportar auditoria (.md ou .json)", PromptAction::ExportAudit, "audit.md".to_string());
+                        }
+                        // Volta para a lista com o host selecionado
+                        KeyCode::Enter => {
+                            if let Some(&(index, _)) = self.audit_state.selected().and_then(|i| self.audit.get(i)) {
+                                self.list_state.select(Some(index));
+                                self.reveal_selected();
+                                self.state = AppState::List;
+                            }
+                        }
+                        _ => {}
+                    },
+                    AppState::Trash => match key.code {
+                        KeyCode::Esc => self.state = AppState::List,
+                        KeyCode::Down => {
+                            let len = self.trash.entries.len();
+                            if len > 0 {
+                                let i = self.trash_state.selected().map(|i| (i + 1) % len).unwrap_or(0);
+                                self.trash_state.select(Some(i));
+                            }
+                        }
+                        KeyCode::Up => {
+                            let len = self.trash.entries.len();
+                            if len > 0 {
+                                let i = self.trash_state.selected().map(|i| if i == 0 { len - 1 } else { i - 1 }).unwrap_or(0);
+                                self.trash_state.select(Some(i));
+                            }
+                        }
+                        KeyCode::Enter | KeyCode::Char('r') => {
+                            if let Some(i) = self.trash_state.selected() {
+                                self.restore_from_trash(i);
+                            }
+                        }
+                        KeyCode::Char('x') => {
+                            if let Some(i) = self.trash_state.selected() {
+                                self.purge_from_trash(i);
+                            }
+                        }
+                        _ => {}
+                    },
+                    AppState::History => match key.code {
+                        KeyCode::Esc => self.state = AppState::List,
+                        KeyCode::Down => {
+                            let len = self.recordings.len();
+                            if len > 0 {
+                                let i = self.recordings_state.selected().map(|i| (i + 1) % len).unwrap_or(0);
+                                self.recordings_state.select(Some(i));
+                            }
+                        }
+                        KeyCode::Up => {
+                            let len = self.recordings.len();
+                            if len > 0 {
+                                let i = self.recordings_state.selected().map(|i| if i == 0 { len - 1 } else { i - 1 }).unwrap_or(0);
+                                self.recordings_state.select(Some(i));
+                            }
+                        }
+                        KeyCode::Enter | KeyCode::Char('p') => {
+                            if let Some(path) = self.recordings_state.selected().and_then(|i| self.recordings.get(i)).map(|r| r.path.clone()) {
+                                if let Err(e) = self.suspend_tui(|| recording::play(&path)) {
+                                    self.show_popup("Gravações", format!("Erro ao reproduzir: {}", e));
+                                }
+                            }
+                        }
+                        KeyCode::Char('x') => {
+                            if let Some(i) = self.recordings_state.selected() {
+                                self.delete_recording(i);
+                            }
+                        }
+                        _ => {}
+                    },
+                    AppState::RestoreTunnels => match key.code {
+                        KeyCode::Enter | KeyCode::Char('y') => {
+                            self.state = AppState::List;
+                            self.restore_tunnels();
+                        }
+                        KeyCode::Esc | KeyCode::Char('n') => self.state = AppState::List,
+                        _ => {}
+                    },
+                    AppState::Tunnels => match key.code {
+                        KeyCode::Esc => self.state = AppState::List,
+                        KeyCode::Down => {
+                            let len = self.tunnels.tunnels.len() + self.tunnels.persistent.len();
+                            if len > 0 {
+                                let i = self.tunnel_state.selected().map(|i| (i + 1) % len).unwrap_or(0);
+                                self.tunnel_state.select(Some(i));
+                            }
+                        }
+                        KeyCode::Up => {
+                            let len = self.tunnels.tunnels.len() + self.tunnels.persistent.len();
+                            if len > 0 {
+                                let i = self.tunnel_state.selected().map(|i| if i == 0 { len - 1 } else { i - 1 }).unwrap_or(0);
+                                self.tunnel_state.select(Some(i));
+                            }
+                        }
+                        KeyCode::Char('x') | KeyCode::Delete => {
+                            if let Some(i) = self.tunnel_state.selected() {
+                                let local = self.tunnels.tunnels.len();
+                                if i < local {
+                                    self.tunnels.remove(i);
+                                } else if let Err(e) = self.tunnels.remove_persistent(i - local) {
+                                    self.show_popup("Túneis", format!("Erro ao remover a unit: {}", e));
+                                }
+                                let len = self.tunnels.tunnels.len() + self.tunnels.persistent.len();
+                                self.tunnel_state.select(if len == 0 { None } else { Some(i.min(len - 1)) });
+                            }
+                        }
+                        KeyCode::Char('s') => {
+                            if let Some(i) = self.tunnel_state.selected().filter(|&i| i < self.tunnels.tunnels.len()) {
+                                let message = match self.tunnels.make_persistent(i) {
+                                    Ok(unit) => format!("Túnel mantido pelo systemd: {}", unit),
+                                    Err(e) => format!("Erro ao criar a unit: {}", e),
+                                };
+                                self.show_popup("Túneis", message);
+                            }
+                        }
+                        _ => {}
+                    },
+                    AppState::Conflicts if self.import_merge => match key.code {
+                        KeyCode::Esc => {
+                            self.merges.clear();
+                            self.import_merge = false;
+                            self.state = AppState::List;
+                        }
+                        KeyCode::Enter => {
+                            self.state = AppState::List;
+                            let message = match self.apply_import() {
+                                Ok(message) => message,
+                                Err(e) => format!("Erro ao importar: {}", e),
+                            };
+                            self.show_popup("Importar", message);
+                        }
+                        _ => self.handle_conflict_choice(key.code),
+                    },
+                    AppState::Conflicts => match key.code {
+                        KeyCode::Esc => {
+                            self.merges.clear();
+                            self.state = AppState::List;
+                            let message = match git::abort_merge(&self.app_config.get_workdir()) {
+                                Ok(()) => "Merge desfeito; nada mudou".to_string(),
+                                Err(e) => format!("Erro ao desfazer o merge: {}", e),
+                            };
+                            self.git_status = git::status(&self.app_config.get_workdir());
+                            self.show_popup("Git", message);
+                        }
+                        KeyCode::Enter => {
+                            self.state = AppState::List;
+                            let message = match self.apply_conflicts() {
+                                Ok(()) => "Conflitos resolvidos e merge concluído".to_string(),
+                                Err(e) => format!("Erro ao concluir o merge: {}", e),
+                            };
+                            self.git_status = git::status(&self.app_config.get_workdir());
+                            self.show_popup("Git", message);
+                        }
+                        _ => self.handle_conflict_choice(key.code),
+                    },
+                    AppState::QrCode => match key.code {
+                        KeyCode::Esc | KeyCode::Enter | KeyCode::Char('q') => self.state = AppState::List,
+                        KeyCode::Char('j') => self.qr_json = !self.qr_json,
+                        KeyCode::Char('H') => self.privacy_mode = !self.privacy_mode,
+                        _ => {}
+                    },
+                    AppState::SyncDiff => match key.code {
+                        KeyCode::Esc => {
+                            self.sync_target = None;
+                            self.sync_files.clear();
+                            self.state = AppState::List;
+                        }
+                        KeyCode::Enter => self.confirm_sync(),
+                        KeyCode::Down | KeyCode::Up => {
+                            let len = self.sync_files.len();
+                            if len > 0 {
+                                let i = self.sync_state.selected().unwrap_or(0);
+                                let i = if key.code == KeyCode::Down { (i + 1) % len } else { (i + len - 1) % len };
+                                self.sync_state.select(Some(i));
+                                self.diff_scroll = 0;
+                            }
+                        }
+                        KeyCode::PageDown => self.diff_scroll = self.diff_scroll.saturating_add(10),
+                        KeyCode::PageUp => self.diff_scroll = self.diff_scroll.saturating_sub(10),
+                        KeyCode::Char(' ') => {
+                            if self.sync_target == Some(SyncTarget::GitPush) {
+                                self.show_popup("Git", "O push envia os commits inteiros; não dá para excluir arquivos".to_string());
+                            } else if let Some(file) = self.sync_state.selected().and_then(|i| self.sync_files.get_mut(i)) {
+                                file.excluded = !file.excluded;
+                            }
+                        }
+                        _ => {}
+                    },
+                    AppState::Prompt => match key.code {
+                        KeyCode::Esc => {
+                            self.prompt_action = None;
+                            self.state = self.previous_state.clone();
+                        }
+                        KeyCode::Enter => {
+                            self.state = self.previous_state.clone();
+                            if let Some(action) = self.prompt_action.take() {
+                                let input = self.prompt_input.trim().to_string();
+                                self.submit_prompt(action, input);
+                            }
+                        }
+                        KeyCode::Char(c) => self.prompt_input.push(c),
+                        KeyCode::Backspace => {
+                            self.prompt_input.pop();
+                        }
+                        _ => {}
+                    },
+                }
+            }
+        }
+    }
+
+    fn ui(&mut self, f: &mut Frame) {
+        match self.state {
+            AppState::Popup => {
+                // Renderizar estado anterior como fundo
+                self.render_screen(f, self.previous_state.clone());
+                // Renderizar popup por cima
+                self.render_popup(f);
+            }
+            AppState::Prompt => {
+                self.render_screen(f, self.previous_state.clone());
+                self.render_prompt(f);
+            }
+            AppState::Help => {
+                self.render_screen(f, self.previous_state.clone());
+                self.render_help(f);
+            }
+            _ => self.render_screen(f, self.state.clone()),
+        }
+        if self.app_config.accessible {
+            let showing_qr = self.state == AppState::QrCode || self.previous_state == AppState::QrCode;
+            theme::make_accessible(f.buffer_mut(), showing_qr);
+        }
+    }
+
+    fn render_screen(&mut self, f: &mut Frame, state: AppState) {
+        match state {
+            AppState::List => self.render_list(f),
+            AppState::Form => self.render_form(f, "Add Host"),
+            AppState::Edit => self.render_form(f, "Edit Host"),
+            AppState::Confirm => self.render_confirm(f, "Confirm New Host"),
+            AppState::ConfirmEdit => self.render_confirm(f, "Confirm Changes"),
+            AppState::Search => self.render_search(f),
+            AppState::JumpChain => self.render_jump_chain(f),
+            AppState::Topology => self.render_topology(f),
+            AppState::Includes => self.render_includes(f),
+            AppState::Tunnels => self.render_tunnels(f),
+            AppState::Trash => self.render_trash(f),
+            AppState::History => self.render_history(f),
+            AppState::Audit => self.render_audit(f),
+            AppState::KeyMap => self.render_key_map(f),
+            AppState::Rotation => self.render_rotation(f),
+            AppState::SyncDiff => self.render_sync_diff(f),
+            AppState::Conflicts => self.render_conflicts(f),
+            AppState::QrCode => {
+                self.render_list(f);
+                self.render_qr(f);
+            }
+            AppState::RestoreTunnels => {
+                self.render_list(f);
+                self.render_restore_tunnels(f);
+            }
+            AppState::SmartGroups => {
+                self.render_list(f);
+                self.render_smart_groups(f);
+            }
+            AppState::QuickActions => {
+                self.render_list(f);
+                self.render_quick_actions(f);
+            }
+            AppState::ConfirmDelete => {
+                self.render_list(f);
+                self.render_confirm_delete(f);
+            }
+            AppState::ConfirmOverwrite => {
+                let form_state = if self.editing_host_index.is_some() { AppState::Edit } else { AppState::Form };
+                self.render_screen(f, form_state);
+                self.render_confirm_overwrite(f);
+            }
+            AppState::ConfirmMerge => {
+                self.render_includes(f);
+                self.render_confirm_merge(f);
+            }
+            AppState::TemplatePicker => {
+                self.render_screen(f, self.form_state.clone());
+                self.render_template_picker(f);
+            }
+            AppState::IdentityPicker => {
+                self.render_screen(f, self.form_state.clone());
+                self.render_identity_picker(f);
+            }
+            AppState::Popup | AppState::Prompt | AppState::Help => self.render_list(f),
+        }
+    }
+
+    fn render_list(&mut self, f: &mut Frame) {
+        // A tabela precisa de mais largura para as colunas
+        let list_width = if self.table_view { 65 } else { 50 };
+        let chunks = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([Constraint::Percentage(list_width), Constraint::Percentage(100 - list_width)])
+            .split(f.size());
+
+        self.list_area = chunks[0];
+        if self.table_view {
+            self.render_host_table(f, chunks[0]);
+        } else {
+            self.render_host_list(f, chunks[0]);
+        }
+
+        if self.raw_view {
+            let privacy_mode = self.privacy_mode;
+            let lines: Vec<Line> = self.selected_raw_block().iter()
+                .map(|l| if privacy_mode { highlight::highlight_line(&privacy::mask_line(l)) } else { highlight::highlight_line(l) })
+                .collect();
+            let raw = Paragraph::new(lines)
+                .block(Block::default().borders(Borders::ALL).title("Raw Config (v: details)"));
+            f.render_widget(raw, chunks[1]);
+            return;
+        }
+
+        let selected_index = self.list_state.selected()
+            .filter(|&i| self.hosts.get(i).is_some_and(|h| !h.is_separator));
+
+        let details = if let Some(index) = selected_index {
+            // A resolução percorre todos os blocos; só refazer quando a seleção muda
+            if self.shadow_cache.as_ref().is_none_or(|(cached, _, _)| *cached != index) {
+                let (options, shadowed) = effective::resolve(&self.hosts, index);
+                self.shadow_cache = Some((index, shadowed, audit::HostKeyPolicy::from_options(&options)));
+            }
+            let (shadowed, policy) = self.shadow_cache.as_ref()
+                .map(|(_, s, p)| (s.as_slice(), p.clone()))
+                .unwrap_or_default();
+            let host = &self.hosts[index];
+            // Cada trecho do painel tem uma chave, para `detail_fields` escolher e ordenar
+            let mut sections: Vec<(String, Vec<Line>)> = Vec::new();
+            let mut shadowed_shown = false;
+
+            for (label, key, value) in Self::detail_fields(host) {
+                if self.app_config.detail_hidden_options.iter().any(|h| h.eq_ignore_ascii_case(&key)) {
+                    continue;
+                }
+                let key_problem = if key == "identityfile" { self.key_problems.get(&value).copied() } else { None };
+                let value = if self.privacy_mode { privacy::mask_value(&key, &value) } else { value };
+                let mut spans = vec![Span::styled(format!("{}: ", label), Style::default().fg(Color::Yellow))];
+                // Valor ignorado pelo ssh porque um bloco anterior já definiu a opção
+                match shadowed.iter().find(|s| s.block == index && s.key == key) {
+                    Some(shadow) => {
+                        let winner = &self.hosts[shadow.winner];
+                        let winning_value = if self.privacy_mode {
+                            privacy::mask_value(&key, &shadow.winning_value)
+                        } else {
+                            shadow.winning_value.clone()
+                        };
+                        spans.push(Span::styled(value, Style::default().fg(Color::Red).add_modifier(Modifier::CROSSED_OUT)));
+                        spans.push(Span::styled(
+                            format!("  ← vence Host {} ({}): {}", winner.name, self.host_location(winner), winning_value),
+                            Style::default().fg(Color::Gray),
+                        ));
+                        shadowed_shown = true;
+                    }
+                    None => spans.push(Span::raw(value)),
+                }
+                if let Some(problem) = key_problem {
+                    spans.push(Span::styled(format!("  ⚠ {}", problem), Style::default().fg(Color::Red)));
+                }
+                sections.push((key, vec![Line::from(spans)]));
+            }
+
+            sections.push(("hostkeys".to_string(), vec![self.host_key_line(index, &policy)]));
+
+            if let Some(description) = &host.description {
+                sections.push(("description".to_string(), description.lines()
+                    .map(|l| Line::from(Span::styled(l.to_string(), Style::default().fg(Color::DarkGray))))
+                    .collect()));
+            }
+
+            let meta = self.metadata.get(&host.name);
+            let check = match (&meta.check_command, meta.reachability) {
+                (Some(command), _) => format!("{} (c: alterar)", command),
+                (None, Some(method)) => format!("{} (m: alterar)", method.label()),
+                (None, None) => format!("{} (global, m: alterar)", self.app_config.reachability.label()),
+            };
+            if let Some(result) = self.saved_state.checks.get(&host.name) {
+                let (status, color) = if result.up { ("up", Color::Green) } else { ("down", Color::Red) };
+                let latency = result.latency_ms.filter(|_| result.up).map(|ms| format!(" ({} ms)", ms)).unwrap_or_default();
+                let mut spans = vec![
+                    Span::styled("Status: ", Style::default().fg(Color::Gray)),
+                    Span::styled(format!("{}{}", status, latency), Style::default().fg(color)),
+                    Span::styled(format!(", checked {}", state::format_age(result.age())), Style::default().fg(Color::Gray)),
+                ];
+                if result.is_stale(self.app_config.check_ttl_secs) {
+                    spans.push(Span::styled(" (stale, p: re-check)", Style::default().fg(Color::DarkGray)));
+                }
+                sections.push(("status".to_string(), vec![Line::from(spans)]));
+            }
+            sections.push(("check".to_string(), vec![Line::from(vec![
+                Span::styled("Check: ", Style::default().fg(Color::Gray)),
+                Span::styled(check, Style::default().fg(Color::Gray)),
+            ])]));
+            if !meta.tags.is_empty() {
+                sections.push(("tags".to_string(), vec![Line::from(vec![
+                    Span::styled("Tags: ", Style::default().fg(Color::Gray)),
+                    Span::styled(meta.tags.join(", "), Style::default().fg(Color::Cyan)),
+                ])]));
+            }
+            if let Some(mac) = &meta.mac {
+                sections.push(("mac".to_string(), vec![Line::from(vec![
+                    Span::styled("MAC: ", Style::default().fg(Color::Gray)),
+                    Span::raw(mac.clone()),
+                ])]));
+            }
+            if let Some(last) = self.last_connected(&host.name) {
+                let age = Duration::from_secs(state::now().saturating_sub(last));
+                sections.push(("connections".to_string(), vec![
+                    Line::from(vec![
+                        Span::styled("Last connected: ", Style::default().fg(Color::Gray)),
+                        Span::styled(format!("{} ({})", state::format_timestamp(last), state::format_age(age)), Style::default().fg(Color::Gray)),
+                    ]),
+                    Line::from(vec![
+                        Span::styled("Connections: ", Style::default().fg(Color::Gray)),
+                        Span::styled(meta.connections.len().to_string(), Style::default().fg(Color::Gray)),
+                    ]),
+                ]));
+            }
+            if let Some(notes) = &meta.notes {
+                sections.push(("notes".to_string(), vec![
+                    Line::from(""),
+                    Line::from(Span::styled("Notes:", Style::default().fg(Color::Gray))),
+                    Line::from(notes.clone()),
+                ]));
+            }
+            if host.source_file.is_some() {
+                sections.push(("source".to_string(), vec![Line::from(vec![
+                    Span::styled("Source: ", Style::default().fg(Color::Gray)),
+                    Span::styled(self.host_location(host), Style::default().fg(Color::Gray)),
+                ])]));
+            }
+
+            let mut lines = arrange_details(&self.app_config.detail_fields, sections);
+            if shadowed_shown {
+                lines.push(Line::from(""));
+                lines.push(Line::from(Span::styled(
+                    "Valores riscados são ignorados: o ssh usa o primeiro valor encontrado na ordem dos blocos",
+                    Style::default().fg(Color::Red),
+                )));
+            }
+
+            Paragraph::new(lines).wrap(ratatui::widgets::Wrap { trim: false })
+        } else if let Some(separator) = self.selected_separator() {
+            let section = &self.hosts[separator];
+            let collapsed = self.is_collapsed(section);
+            let file = section.source_file.as_deref().map(|f| {
+                f.strip_prefix(self.app_config.get_workdir()).unwrap_or(f).display().to_string()
+            });
+            Paragraph::new(vec![
+                Line::from(vec![
+                    Span::styled("Section: ", Style::default().fg(Color::Yellow)),
+                    Span::raw(section.name.trim_matches(|c: char| c == '─' || c == ' ').to_string()),
+                ]),
+                Line::from(vec![
+                    Span::styled("File: ", Style::default().fg(Color::Yellow)),
+                    Span::raw(file.unwrap_or_default()),
+                ]),
+                Line::from(vec![
+                    Span::styled("Hosts: ", Style::default().fg(Color::Yellow)),
+                    Span::raw(self.section_range(separator).len().to_string()),
+                ]),
+                Line::from(""),
+                Line::from(Span::styled(
+                    if collapsed { "Enter/Space/→: expand" } else { "Enter/Space/←: collapse" },
+                    Style::default().fg(Color::Gray),
+                )),
+            ])
+        } else {
+            Paragraph::new("No host selected")
+        };
+
+        let details_block = details.block(Block::default().borders(Borders::ALL).title("Host Details"));
+        f.render_widget(details_block, chunks[1]);
+    }
+
+    fn render_host_list(&mut self, f: &mut Frame, area: Rect) {
+        // Só as linhas visíveis viram ListItem; com dezenas de milhares de hosts
+        // montar a lista inteira a cada quadro deixa a interface lenta
+        self.reveal_selected();
+        let selected_pos = self.list_state.selected().and_then(|current| self.row_position(current).ok());
+        let rows = self.list_rows().to_vec();
+        let height = area.height.saturating_sub(2) as usize;
+        self.list_offset = scroll_offset(selected_pos, self.list_offset, height, rows.len());
+        let window = self.list_offset..(self.list_offset + height).min(rows.len());
+
+        let quick_connect = self.app_config.quick_connect;
+        self.quick_targets = rows[window.clone()].iter()
+            .copied()
+            .filter(|&i| quick_connect && !self.hosts[i].is_separator && self.is_target(&self.hosts[i]))
+            .take(9)
+            .collect();
+        let items: Vec<ListItem> = rows[window.clone()]
+            .iter()
+            .map(|&i| {
+                let host = &self.hosts[i];
+                if host.is_separator {
+                    let collapsed = self.is_collapsed(host);
+                    let mut spans = vec![Span::styled(
+                        format!("{} {}", if collapsed { "▸" } else { "▾" }, host.name),
+                        Style::default().fg(Color::Gray),
+                    )];
+                    if collapsed {
+                        let count = self.section_range(i).len();
+                        spans.push(Span::styled(format!(" ({} hosts)", count), Style::default().fg(Color::DarkGray)));
+                    }
+                    return ListItem::new(Line::from(spans));
+                }
+                let mut spans = Vec::new();
+                if quick_connect {
+                    spans.push(quick_label(self.quick_targets.iter().position(|&t| t == i)));
+                }
+                if self.app_config.status_column {
+                    spans.extend(self.status_spans(host));
+                }
+                // Padrões (`Host *`) só fornecem opções: apagados, na seção Defaults
+                if host.is_pattern {
+                    spans.push(Span::styled(&host.name, Style::default().fg(Color::DarkGray)));
+                } else {
+                    spans.push(Span::raw(&host.name));
+                }
+                if keys::identity_files(host).iter().any(|v| self.key_problems.contains_key(*v)) {
+                    spans.push(Span::styled(" ⚠", Style::default().fg(Color::Red)));
+                }
+                // Agente encaminhado: vermelho fora de `trusted_hosts`
+                if let Some(&trusted) = self.agent_forwarding.get(&i) {
+                    let color = if trusted { Color::Yellow } else { Color::Red };
+                    spans.push(Span::styled(" [agent]", Style::default().fg(color).add_modifier(Modifier::BOLD)));
+                }
+                // Conecta por outro programa (telnet, console serial)
+                if let Some(program) = self.metadata.hosts.get(&host.name)
+                    .and_then(|m| m.console.as_deref())
+                    .and_then(|c| c.split_whitespace().next())
+                    .and_then(|p| p.rsplit('/').next())
+                {
+                    spans.push(Span::styled(format!(" [{}]", program), Style::default().fg(Color::Magenta)));
+                }
+                // Primeira linha da descrição, apagada, depois do nome
+                if let Some(description) = host.description.as_deref().and_then(|d| d.lines().next()) {
+                    spans.push(Span::styled(format!("  {}", description), Style::default().fg(Color::DarkGray)));
+                }
+                ListItem::new(Line::from(spans))
+            })
+            .collect();
+
+        let position = match selected_pos {
+            Some(pos) => format!(" {}/{} ", pos + 1, rows.len()),
+            None => format!(" {} ", rows.len()),
+        };
+        let hosts_list = List::new(items)
+            .block(self.list_block(
+                if self.saved_state.sort_recent {
+                    "Recent first — s: by folder, Enter: connect, a: add, e: edit, /: search, ?: help"
+                } else {
+                    "?: help, Enter: connect, a: add, e: edit, p: ping, /: search, J: jump, G: graph, I: includes, L: table, s: recent"
+                },
+                position,
+            ))
+            .highlight_style(Style::default().add_modifier(Modifier::BOLD))
+            .highlight_symbol(">> ");
+
+        let mut window_state = ListState::default()
+            .with_selected(selected_pos.filter(|i| window.contains(i)).map(|i| i - window.start));
+        f.render_stateful_widget(hosts_list, area, &mut window_state);
+        render_scrollbar(f, area, rows.len(), height, self.list_offset);
+    }
+
+    // Borda da lista/tabela: atalhos (ou o progresso da varredura), posição e git
+    fn list_block(&self, hints: &str, position: String) -> Block<'static> {
+        let title = match (&self.sweep, &self.host_test, &self.browser_wait) {
+            (Some(sweep), _, _) => format!("SSH Hosts (checking {}/{}, Esc: cancel)", sweep.done, sweep.total),
+            (None, Some(test), _) => format!("SSH Hosts (testing {} {})", test.name, test.spinner()),
+            (None, None, Some((_, url, _))) => format!("SSH Hosts (waiting for the tunnel to {})", url),
+            (None, None, None) => format!("SSH Hosts ({})", hints),
+        };
+        let mut block = Block::default()
+            .borders(Borders::ALL)
+            .title(title)
+            .title(Title::from(position).position(Position::Bottom).alignment(Alignment::Right))
+            .title(Title::from(self.git_status_line()).position(Position::Bottom).alignment(Alignment::Left));
+        if self.privacy_mode {
+            block = block.title(Title::from(Span::styled(" private ", Style::default().fg(Color::Black).bg(Color::Magenta)))
+                .position(Position::Bottom)
+                .alignment(Alignment::Center));
+        }
+        block
+    }
+
+    fn render_host_table(&mut self, f: &mut Frame, area: Rect) {
+        let rows = self.table_rows().to_vec();
+        let selected_pos = self.list_state.selected().and_then(|current| rows.iter().position(|&i| i == current));
+        // Cabeçalho ocupa uma linha além das bordas
+        let height = area.height.saturating_sub(3) as usize;
+        self.list_offset = scroll_offset(selected_pos, self.list_offset, height, rows.len());
+        let window = self.list_offset..(self.list_offset + height).min(rows.len());
+
+        let quick_connect = self.app_config.quick_connect;
+        // Mesmos alvos da lista: padrões só com `pattern_actions`
+        self.quick_targets = rows[window.clone()].iter()
+            .copied()
+            .filter(|&i| quick_connect && !self.hosts[i].is_separator && self.is_target(&self.hosts[i]))
+            .take(9)
+            .collect();
+        let columns = &self.app_config.table_columns;
+        // Coluna estreita com o índice da conexão rápida
+        let quick_cell = |label: Span<'static>| quick_connect.then(|| Cell::from(label));
+        let header = Row::new(quick_cell(Span::raw("")).into_iter().chain(columns.iter().map(|c| {
+            let arrow = match self.table_sort {
+                Some(sort) if sort.column == c.column => if sort.descending { " ▼" } else { " ▲" },
+                _ => "",
+            };
+            Cell::from(format!("{}{}", c.column.title(), arrow))
+        })))
+        .style(Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD));
+
+        let table_rows: Vec<Row> = rows[window.clone()].iter()
+            .map(|&i| {
+                let host = &self.hosts[i];
+                let quick = quick_cell(quick_label(self.quick_targets.iter().position(|&t| t == i)));
+                Row::new(quick.into_iter().chain(columns.iter().map(|c| match c.column {
+                    Column::Status => Cell::from(Line::from(self.status_spans(host))),
+                    Column::Hostname if self.privacy_mode && host.hostname.is_some() => Cell::from(privacy::MASK),
+                    column => Cell::from(column.text(host)),
+                })))
+                .style(if host.is_pattern { Style::default().fg(Color::DarkGray) } else { Style::default() })
+            })
+            .collect();
+        let widths: Vec<Constraint> = quick_connect.then_some(Constraint::Length(1)).into_iter()
+            .chain(columns.iter().map(|c| if c.width == 0 { Constraint::Min(8) } else { Constraint::Length(c.width) }))
+            .collect();
+
+        let position = match selected_pos {
+            Some(pos) => format!(" {}/{} ", pos + 1, rows.len()),
+            None => format!(" {} ", rows.len()),
+        };
+        let table = Table::new(table_rows, widths)
+            .header(header)
+            .block(self.list_block("?: help, Enter: connect, L: list, </>: sort column, !: reverse", position))
+            .highlight_style(Style::default().add_modifier(Modifier::BOLD))
+            .highlight_symbol(">> ");
+
+        let mut window_state = TableState::default()
+            .with_selected(selected_pos.filter(|p| window.contains(p)).map(|p| p - window.start));
+        f.render_stateful_widget(table, area, &mut window_state);
+        render_scrollbar(f, area, rows.len(), height, self.list_offset);
+    }
+
+    // Ordem das linhas da tabela, refeita só quando hosts, ordenação ou status mudam
+    fn table_rows(&mut self) -> &[usize] {
+        if self.table_rows.is_none() {
+            self.table_rows = Some(table::rows(&self.hosts, self.table_sort, &self.saved_state.checks));
+        }
+        self.table_rows.as_deref().unwrap_or_default()
+    }
+
+    // Percorre as colunas configuradas, passando por "sem ordenação" (ordem do arquivo)
+    fn cycle_table_sort(&mut self, forward: bool) {
+        let columns: Vec<Column> = self.app_config.table_columns.iter().map(|c| c.column).collect();
+        let current = self.table_sort.and_then(|sort| columns.iter().position(|&c| c == sort.column));
+        let next = match (current, forward) {
+            (None, true) => columns.first().copied(),
+            (None, false) => columns.last().copied(),
+            (Some(i), true) => columns.get(i + 1).copied(),
+            (Some(i), false) => i.checked_sub(1).and_then(|i| columns.get(i)).copied(),
+        };
+        self.table_sort = next.map(|column| TableSort { column, descending: false });
+        self.table_rows = None;
+    }
+
+    // Setas na tabela seguem a ordem exibida
+    fn step_table(&mut self, forward: bool) {
+        let selected = self.list_state.selected();
+        let rows = self.table_rows();
+        if rows.is_empty() {
+            return;
+        }
+        let len = rows.len();
+        let next = match selected.and_then(|current| rows.iter().position(|&i| i == current)) {
+            Some(pos) if forward => rows[(pos + 1) % len],
+            Some(pos) => rows[(pos + len - 1) % len],
+            None => rows[0],
+        };
+        self.list_state.select(Some(next));
+    }
+
+    fn render_form(&mut self, f: &mut Frame, title: &str) {
+        use ratatui::widgets::{Clear, Paragraph};
+        use ratatui::layout::Alignment;
+        
+        let area = f.size();
+        f.render_widget(Clear, area);
+        
+        let mut lines = vec![];
+        let field_names = HostForm::field_names();
+        
+        for (i, name) in field_names.iter().enumerate() {
+            let value = self.form.get_field(i);
+            let style = if i == self.form.current_field {
+                Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD)
+            } else {
+                Style::default()
+            };
+            
+            let mut spans = vec![Span::styled(format!("{}: ", name), style)];
+            if HostForm::is_toggle(i) {
+                let shown = if value.is_empty() { "-" } else { value };
+                spans.push(Span::styled(format!("[{}]", shown), style));
+                if i == self.form.current_field {
+                    spans.push(Span::styled(" (Space: cycle)", Style::default().fg(Color::DarkGray)));
+                }
+            } else if i == 7 {
+                // Editor estruturado: porta local → host:porta remota
+                let current = self.form.local_forwards.get(self.form.forward_index).cloned().unwrap_or_default();
+                let part_style = |part: usize| if i == self.form.current_field && part == self.form.forward_subfield {
+                    Style::default().fg(Color::Black).bg(Color::Yellow)
+                } else {
+                    style
+                };
+                spans.push(Span::styled(format!("[{}]", current.local), part_style(0)));
+                spans.push(Span::styled(" → ", style));
+                spans.push(Span::styled(format!("[{}]", current.remote_host), part_style(1)));
+                spans.push(Span::styled(":", style));
+                spans.push(Span::styled(format!("[{}]", current.remote_port), part_style(2)));
+                if self.form.local_forwards.len() > 1 {
+                    spans.push(Span::styled(
+                        format!(" ({}/{})", self.form.forward_index + 1, self.form.local_forwards.len()),
+                        Style::default().fg(Color::DarkGray),
+                    ));
+                }
+            } else if i == 5 && value.is_empty() && i == self.form.current_field {
+                spans.push(Span::styled("↓: pick a key from ~/.ssh ", Style::default().fg(Color::DarkGray)));
+            } else if i == 11 {
+                // Descrição de várias linhas (vinda do arquivo) fica numa linha só
+                spans.push(Span::styled(value.replace('\n', " ↵ "), style));
+            } else {
+                spans.push(Span::styled(value, style));
+            }
+            // Valor herdado aparece apagado até o campo ser preenchido
+            if value.is_empty() {
+                if let Some(inherited) = self.form.inherited.get(&i) {
+                    spans.push(Span::styled(format!("{} (herdado)", inherited), Style::default().fg(Color::DarkGray)));
+                }
+            }
+            lines.push(Line::from(spans));
+            
+            // Demais entradas de LocalForward abaixo da que está em edição
+            if i == 7 {
+                for (n, forward) in self.form.local_forwards.iter().enumerate() {
+                    if n != self.form.forward_index && !forward.is_empty() {
+                        lines.push(Line::from(Span::styled(format!("  {}. {}", n + 1, forward.to_spec()), Style::default().fg(Color::Gray))));
+                    }
+                }
+                if i == self.form.current_field {
+                    lines.push(Line::from(Span::styled(
+                        "  Local port → remote host : remote port | Tab: Next part | ↑/↓: Select | Ctrl+N: Add | Ctrl+D: Remove",
+                        Style::default().fg(Color::DarkGray),
+                    )));
+                }
+                for error in self.form.forward_errors() {
+                    lines.push(Line::from(Span::styled(format!("  {}", error), Style::default().fg(Color::Red))));
+                }
+            }
+        }
+        
+        for (key, value) in &self.form.extra_options {
+            lines.push(Line::from(Span::styled(format!("{}: {}", key, value), Style::default().fg(Color::Gray))));
+        }
+        
+        lines.push(Line::from(""));
+        lines.push(Line::from("Tab/Shift+Tab: Navigate | Ctrl+T: Template | Enter: OK | Esc: Cancel"));
+        
+        let form_area = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Length(lines.len() as u16 + 2), Constraint::Min(0)])
+            .split(area)[0];
+        
+        let form = Paragraph::new(lines)
+            .block(Block::default().borders(Borders::ALL).title(title))
+            .alignment(Alignment::Left);
+        
+        f.render_widget(form, form_area);
+    }
+    
+    fn render_confirm(&mut self, f: &mut Frame, title: &str) {
+        use ratatui::widgets::{Clear, Paragraph};
+        use ratatui::layout::Alignment;
+        
+        let area = f.size();
+        f.render_widget(Clear, area);
+        
+        let mut lines = vec![Line::from("Confirm host configuration:"), Line::from("")];
+        let field_names = HostForm::field_names();
+        
+        for (i, name) in field_names.iter().enumerate() {
+            let value = self.form.get_field(i);
+            if i == 7 {
+                for forward in self.form.local_forwards.iter().filter(|f| !f.is_empty()) {
+                    lines.push(Line::from(vec![
+                        Span::styled(format!("{}: ", name), Style::default().fg(Color::Yellow)),
+                        Span::raw(forward.to_spec()),
+                    ]));
+                }
+            } else if !value.is_empty() {
+                lines.push(Line::from(vec![
+                    Span::styled(format!("{}: ", name), Style::default().fg(Color::Yellow)),
+                    Span::raw(value.replace('\n', " ↵ ")),
+                ]));
+            } else if let Some(inherited) = self.form.inherited.get(&i) {
+                lines.push(Line::from(vec![
+                    Span::styled(format!("{}: ", name), Style::default().fg(Color::DarkGray)),
+                    Span::styled(format!("{} (herdado)", inherited), Style::default().fg(Color::DarkGray)),
+                ]));
+            }
+        }
+        
+        for (key, value) in &self.form.extra_options {
+            lines.push(Line::from(vec![
+                Span::styled(format!("{}: ", key), Style::default().fg(Color::Yellow)),
+                Span::raw(value),
+            ]));
+        }
+        
+        lines.push(Line::from(""));
+        lines.push(Line::from("Enter: Save | Esc: Back to form"));
+        
+        let confirm_area = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Length(lines.len() as u16 + 2), Constraint::Min(0)])
+            .split(area)[0];
+        
+        let confirm = Paragraph::new(lines)
+            .block(Block::default().borders(Borders::ALL).title(title))
+            .alignment(Alignment::Left);
+        
+        f.render_widget(confirm, confirm_area);
+    }
+    
+    fn form_description(&self) -> Option<String> {
+        Some(self.form.description.trim().to_string()).filter(|d| !d.is_empty())
+    }
+    
+    // Diretivas do formulário, na ordem em que vão para o arquivo
+    fn form_directives(&self) -> Vec<(String, String)> {
+        let mut directives = Vec::new();
+        // Campos vazios continuam herdando dos blocos com curinga
+        if !self.form.hostname.is_empty() {
+            directives.push(("HostName".to_string(), self.form.hostname.clone()));
+        }
+        if !self.form.user.is_empty() {
+            directives.push(("User".to_string(), self.form.user.clone()));
+        }
+        if !self.form.port.is_empty() {
+            directives.push(("Port".to_string(), self.form.port.clone()));
+        }
+        if !self.form.identity_file.is_empty() {
+            directives.push(("IdentityFile".to_string(), self.form.identity_file.clone()));
+        }
+        if !self.form.proxy_jump.is_empty() {
+            directives.push(("ProxyJump".to_string(), self.form.proxy_jump.trim().to_string()));
+        }
+        for local_forward in self.form.local_forwards.iter().filter(|f| !f.is_empty()) {
+            directives.push(("LocalForward".to_string(), local_forward.to_spec()));
+        }
+        let field_names = HostForm::field_names();
+        for i in (0..FIELD_COUNT).filter(|&i| HostForm::is_toggle(i)) {
+            let value = self.form.get_field(i);
+            if !value.is_empty() {
+                directives.push((field_names[i].to_string(), value.to_string()));
+            }
+        }
+        for (key, value) in &self.form.extra_options {
+            directives.push((key.clone(), value.clone()));
+        }
+        directives
+    }
+
+    // No modo banco o host vai para o SQLite e os includes são regerados;
+    // hosts sem pasta, ou de pastas cujo include não veio do banco, continuam
+    // no arquivo
+    fn form_uses_db(&self) -> bool {
+        let folder = self.form.folder.trim();
+        self.host_db.is_some() && !folder.is_empty() && db::manages(&self.app_config.get_workdir().join(folder).join("config"))
+    }
+
+    // `position` mantém o lugar do bloco no include gerado ao editar um host
+    fn save_host(&mut self, position: Option<i64>) -> Result<(), Box<dyn std::error::Error>> {
+        let config_path = self.app_config.get_workdir().join(self.form.folder.trim()).join("config");
+        if self.form_uses_db() {
+            let record = HostRecord {
+                name: self.form.host.clone(),
+                folder: self.form.folder.trim().to_string(),
+                description: self.form_description(),
+                directives: self.form_directives(),
+            };
+            if let Some(host_db) = &self.host_db {
+                host_db.insert(&record, position)?;
+            }
+            return self.regenerate_from_db();
+        }
+        
+        let is_new_file = !config_path.exists();
+        
+        SshConfig::append_host_block(&config_path, &self.form.host, self.form_description().as_deref(), &self.form_directives())?;
+        
+        // Adicionar Include se for arquivo novo
+        if is_new_file {
+            self.add_include_to_main_config(&config_path)?;
+        }
+        
+        Ok(())
+    }
+    
+    // Reescreve os includes a partir do banco e recarrega a lista
+    fn regenerate_from_db(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+        let Some(host_db) = &self.host_db else {
+            return Ok(());
+        };
+        for path in host_db.regenerate(&self.app_config.get_workdir())? {
+            self.add_include_to_main_config(&path)?;
+        }
+        self.reload_hosts()
+    }
+    
+    fn add_include_to_main_config(&self, new_config_path: &Path) -> Result<bool, Box<dyn std::error::Error>> {
+        SshConfig::add_include(&self.app_config.get_main_config_path(), new_config_path)
+    }
+
+    // Roda do mouse move a seleção; clique seleciona a linha e duplo clique
+    // faz o mesmo que Enter
+    fn handle_mouse(&mut self, mouse: MouseEvent) {
+        if self.state != AppState::List {
+            return;
+        }
+        match mouse.kind {
+            MouseEventKind::ScrollDown if self.table_view => self.step_table(true),
+            MouseEventKind::ScrollUp if self.table_view => self.step_table(false),
+            MouseEventKind::ScrollDown => self.next(),
+            MouseEventKind::ScrollUp => self.previous(),
+            MouseEventKind::Down(MouseButton::Left) => {
+                let Some(index) = self.row_at(mouse.column, mouse.row) else {
+                    return;
+                };
+                let double = self.last_click
+                    .is_some_and(|(last, at)| last == index && at.elapsed() < Duration::from_millis(400));
+                self.list_state.select(Some(index));
+                if !double {
+                    self.last_click = Some((index, Instant::now()));
+                    return;
+                }
+                self.last_click = None;
+                if self.hosts[index].is_separator {
+                    if !self.table_view {
+                        self.toggle_section(index);
+                    }
+                } else if let Some(host) = self.hosts.get(index).cloned() {
+                    if let Err(e) = self.connect_ssh(&host) {
+                        self.show_popup("Conexão SSH", format!("Erro na conexão SSH: {}", e));
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    // Host da linha sob o cursor, descontando bordas, cabeçalho da tabela e rolagem
+    fn row_at(&mut self, column: u16, row: u16) -> Option<usize> {
+        let area = self.list_area;
+        let top = area.y + if self.table_view { 2 } else { 1 };
+        if column <= area.x || column + 1 >= area.right() || row < top || row + 1 >= area.bottom() {
+            return None;
+        }
+        let position = self.list_offset + (row - top) as usize;
+        let rows = if self.table_view { self.table_rows() } else { self.list_rows() };
+        rows.get(position).copied()
+    }
+
+    // Setas na lista passam pelas linhas visíveis, incluindo os separadores
+    fn next(&mut self) {
+        self.step_list(true);
+    }
+
+    fn previous(&mut self) {
+        self.step_list(false);
+    }
+
+    fn step_list(&mut self, forward: bool) {
+        let position = self.list_state.selected().map(|current| self.row_position(current));
+        let rows = self.list_rows();
+        if rows.is_empty() {
+            return;
+        }
+        let len = rows.len();
+        let next = match position {
+            Some(Ok(pos)) if forward => rows[(pos + 1) % len],
+            Some(Ok(pos)) => rows[(pos + len - 1) % len],
+            // Seleção escondida: a próxima linha visível depois/antes dela
+            Some(Err(pos)) if forward => rows[pos % len],
+            Some(Err(pos)) => rows[(pos + len - 1) % len],
+            None => rows[0],
+        };
+        self.list_state.select(Some(next));
+    }
+
+    // `[`/`]`: separador visível anterior/seguinte, dando a volta na lista
+    fn step_section(&mut self, forward: bool) {
+        let position = self.list_state.selected().map(|current| self.row_position(current));
+        let rows = self.list_rows().to_vec();
+        let len = rows.len();
+        // Com a seleção escondida, `Err(pos)` já é a linha depois dela
+        let start = match position {
+            Some(Ok(pos)) => pos,
+            Some(Err(pos)) if forward => (pos + len - 1) % len.max(1),
+            Some(Err(pos)) => pos,
+            None => len.saturating_sub(1),
+        };
+        let order: Vec<usize> = if forward {
+            (1..=len).map(|n| (start + n) % len).collect()
+        } else {
+            (1..=len).map(|n| (start + len - n) % len).collect()
+        };
+        if let Some(pos) = order.into_iter().find(|&pos| self.hosts[rows[pos]].is_separator) {
+            self.list_state.select(Some(rows[pos]));
+        }
+    }
+
+    // Linhas da lista (índices em `hosts`): tudo menos os hosts de seções recolhidas
+    fn list_rows(&mut self) -> &[usize] {
+        if self.list_rows.is_none() && self.saved_state.sort_recent {
+            // Sem seções: usados mais recentemente primeiro, os nunca usados na ordem dos arquivos
+            let mut rows: Vec<usize> = (0..self.hosts.len()).filter(|&i| !self.hosts[i].is_separator).collect();
+            rows.sort_by_key(|&i| std::cmp::Reverse(self.last_connected(&self.hosts[i].name)));
+            self.list_rows = Some(rows);
+        }
+        if self.list_rows.is_none() {
+            let main = self.files.first().map(|f| f.path.clone());
+            let mut rows = Vec::with_capacity(self.hosts.len());
+            let mut hiding = false;
+            for (i, host) in self.hosts.iter().enumerate() {
+                if host.is_separator {
+                    hiding = self.is_collapsed(host);
+                    rows.push(i);
+                    continue;
+                }
+                // Hosts do config principal depois de um Include não são da seção
+                // (os padrões, na seção Defaults, são)
+                if host.source_file == main && !host.is_pattern {
+                    hiding = false;
+                }
+                if !hiding {
+                    rows.push(i);
+                }
+            }
+            self.list_rows = Some(rows);
+        }
+        self.list_rows.as_deref().unwrap_or_default()
+    }
+
+    // Posição de um host nas linhas da lista; `Err` é onde ele estaria, se escondido
+    fn row_position(&mut self, index: usize) -> Result<usize, usize> {
+        if self.saved_state.sort_recent {
+            return self.list_rows().iter().position(|&i| i == index).ok_or(0);
+        }
+        self.list_rows().binary_search(&index)
+    }
+
+    // Última sessão que terminou sem erro
+    fn last_connected(&self, host: &str) -> Option<u64> {
+        self.metadata.hosts.get(host).and_then(metadata::HostMeta::last_connected)
+    }
+
+    fn selected_separator(&self) -> Option<usize> {
+        self.list_state.selected().filter(|&i| self.hosts.get(i).is_some_and(|h| h.is_separator))
+    }
+
+    // Hosts da seção que começa no separador `separator`
+    fn section_range(&self, separator: usize) -> std::ops::Range<usize> {
+        let main = self.files.first().map(|f| &f.path);
+        let len = self.hosts[separator + 1..].iter()
+            .position(|h| h.is_separator || (h.source_file.as_ref() == main && !h.is_pattern))
+            .unwrap_or(self.hosts.len() - separator - 1);
+        separator + 1..separator + 1 + len
+    }
+
+    // Seções recolhidas, pelo arquivo do include; a Defaults não tem arquivo
+    fn section_key(separator: &SshHost) -> String {
+        separator.source_file.as_ref().map(|f| f.display().to_string()).unwrap_or_else(|| "Defaults".to_string())
+    }
+
+    fn is_collapsed(&self, separator: &SshHost) -> bool {
+        self.collapsed.contains(&Self::section_key(separator))
+    }
+
+    fn toggle_section(&mut self, separator: usize) {
+        if let Some(section) = self.hosts.get(separator) {
+            let collapse = !self.is_collapsed(section);
+            self.set_collapsed(separator, collapse);
+        }
+    }
+
+    // Grava a escolha em `collapsed_sections` para a próxima execução
+    fn set_collapsed(&mut self, separator: usize, collapse: bool) {
+        let Some(key) = self.hosts.get(separator).map(Self::section_key) else {
+            return;
+        };
+        let changed = if collapse { self.collapsed.insert(key) } else { self.collapsed.remove(&key) };
+        if !changed {
+            return;
+        }
+        self.list_rows = None;
+        let mut sections: Vec<String> = self.collapsed.iter().cloned().collect();
+        sections.sort();
+        self.app_config.collapsed_sections = sections;
+        if let Err(e) = self.app_config.save() {
+            self.show_popup("Seções", format!("Erro ao salvar a configuração: {}", e));
+        }
+    }
+
+    // Separador da seção de um host (ou ele mesmo, se for um)
+    fn section_of(&self, index: usize) -> Option<usize> {
+        let separator = (0..=index).rev().find(|&i| self.hosts[i].is_separator)?;
+        (separator == index || self.section_range(separator).contains(&index)).then_some(separator)
+    }
+
+    // Blocos só de padrões ficam fora de conectar/testar, a não ser com `pattern_actions`
+    fn is_target(&self, host: &SshHost) -> bool {
+        !host.is_pattern || self.app_config.pattern_actions
+    }
+
+    // Tecla 1–9: conecta ao host com esse índice na tela
+    fn quick_connect(&mut self, n: usize) {
+        let Some(&index) = self.quick_targets.get(n) else {
+            return;
+        };
+        self.list_state.select(Some(index));
+        let host = self.hosts[index].clone();
+        if let Err(e) = self.connect_ssh(&host) {
+            self.show_popup("Conexão SSH", format!("Erro na conexão SSH: {}", e));
+        }
+    }
+
+    // Um host escolhido pela busca ou por um salto pode estar numa seção recolhida
+    fn reveal_selected(&mut self) {
+        let Some(selected) = self.list_state.selected() else {
+            return;
+        };
+        if self.row_position(selected).is_ok() || self.saved_state.sort_recent {
+            return;
+        }
+        if let Some(separator) = (0..selected).rev().find(|&i| self.hosts[i].is_separator) {
+            self.toggle_section(separator);
+        }
+    }
+
+    fn apply_search_results(&mut self, results: SearchResults) {
+        self.filtered_hosts = results.matches;
+        self.search_fields = results.fields;
+        self.suggestions = results.suggestions;
+    }
+
+    fn update_search(&mut self) {
+        self.search_fields.clear();
+        // No `pick`, a busca vazia lista todos os hosts, os mais usados primeiro
+        if self.search_query.is_empty() && self.pick_mode {
+            self.filtered_hosts = (0..self.hosts.len()).filter(|&i| !self.hosts[i].is_separator && self.is_target(&self.hosts[i])).collect();
+            // Com o peso em 0 fica a ordem do arquivo
+            let weight = self.app_config.search_frecency_weight;
+            if weight > 0 {
+                self.filtered_hosts.sort_by_key(|&i| std::cmp::Reverse(self.metadata.search_bonus(&self.hosts[i].name, weight)));
+            }
+            self.suggestions.clear();
+            return;
+        }
+        if self.search_query.is_empty() {
+            self.filtered_hosts.clear();
+            self.suggestions.clear();
+            return;
+        }
+        // Resultados chegam pelo worker e são aplicados no laço principal
+        self.search.search(&self.search_query);
+    }
+    
+    fn next_search_result(&mut self) {
+        if !self.filtered_hosts.is_empty() {
+            let current = self.list_state.selected().unwrap_or(0);
+            if let Some(pos) = self.filtered_hosts.iter().position(|&i| i == current) {
+                let next_pos = (pos + 1) % self.filtered_hosts.len();
+                self.list_state.select(Some(self.filtered_hosts[next_pos]));
+            } else if !self.filtered_hosts.is_empty() {
+                self.list_state.select(Some(self.filtered_hosts[0]));
+            }
+        }
+    }
+    
+    fn prev_search_result(&mut self) {
+        if !self.filtered_hosts.is_empty() {
+            let current = self.list_state.selected().unwrap_or(0);
+            if let Some(pos) = self.filtered_hosts.iter().position(|&i| i == current) {
+                let prev_pos = if pos == 0 { self.filtered_hosts.len() - 1 } else { pos - 1 };
+                self.list_state.select(Some(self.filtered_hosts[prev_pos]));
+            } else if !self.filtered_hosts.is_empty() {
+                self.list_state.select(Some(self.filtered_hosts[0]));
+            }
+        }
+    }
+    
+    fn render_search(&mut self, f: &mut Frame) {
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Length(3), Constraint::Min(0)])
+            .split(f.size());
+        
+        // Barra de busca
+        let search_text = format!("Search: {}", self.search_query);
+        let search_bar = Paragraph::new(search_text)
+            .block(Block::default().borders(Borders::ALL).title(if self.pick_mode {
+                "Pick a host (Enter: print alias, Esc: cancel)"
+            } else {
+                "Fuzzy Search (Ctrl+S: save as group)"
+            }))
+            .style(Style::default().fg(Color::Yellow));
+        f.render_widget(search_bar, chunks[0]);
+        
+        // Lista filtrada
+        let list_chunks = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
+            .split(chunks[1]);
+        
+        // A seleção guarda o índice do host; na lista de resultados vale a posição no filtro
+        let selected_pos = self.list_state.selected()
+            .and_then(|current| self.filtered_hosts.iter().position(|&i| i == current));
+        let height = list_chunks[0].height.saturating_sub(2) as usize;
+        self.search_offset = scroll_offset(selected_pos, self.search_offset, height, self.filtered_hosts.len());
+        let window = self.search_offset..(self.search_offset + height).min(self.filtered_hosts.len());
+        self.quick_targets = if self.app_config.quick_connect && (self.pick_mode || !self.search_query.is_empty()) {
+            self.filtered_hosts[window.clone()].iter()
+                .copied()
+                .filter(|&i| !self.hosts[i].is_separator && self.is_target(&self.hosts[i]))
+                .take(9)
+                .collect()
+        } else {
+            Vec::new()
+        };
+        
+        let items: Vec<ListItem> = if self.search_query.is_empty() && !self.pick_mode {
+            vec![ListItem::new(Line::from("Type to search names, addresses, users, options and comments (filters: user: port: tag: folder: host:)"))]
+        } else if self.filtered_hosts.is_empty() && self.suggestions.is_empty() {
+            vec![ListItem::new(Line::from("No matches found"))]
+        } else if self.filtered_hosts.is_empty() {
+            let mut items = vec![ListItem::new(Line::from(Span::styled(
+                "No matches found. Did you mean (Tab: use, Enter: go to first):",
+                Style::default().fg(Color::Gray),
+            )))];
+            items.extend(self.suggestions.iter().map(|&i| ListItem::new(Line::from(format!("  {}", self.hosts[i].name)))));
+            items
+        } else {
+            let quick_connect = self.app_config.quick_connect;
+            // Só o texto da busca fuzzy; os operadores (`user:` etc.) não destacam nada
+            let text = search::Query::parse(&self.search_query).text;
+            self.filtered_hosts[window.clone()].iter().map(|&i| {
+                let host = &self.hosts[i];
+                let field = self.search_fields.get(&i).and_then(|&n| search::text_fields(host).into_iter().nth(n));
+                let mut spans = Vec::new();
+                if quick_connect {
+                    spans.push(quick_label(self.quick_targets.iter().position(|&t| t == i)));
+                }
+                let name_style = if host.is_pattern { Style::default().fg(Color::DarkGray) } else { Style::default() };
+                let name_hits = if field.is_none() { self.highlighter.indices(&host.name, &text) } else { Vec::new() };
+                spans.extend(highlight_matches(&host.name, &name_hits, name_style));
+                // Casou por outro campo: qual, e o valor que casou
+                if let Some((key, value)) = field {
+                    spans.push(Span::styled(format!("  {}: ", key), Style::default().fg(Color::DarkGray)));
+                    if self.privacy_mode && privacy::is_sensitive(&key) {
+                        spans.push(Span::styled(privacy::MASK, Style::default().fg(Color::Cyan)));
+                    } else {
+                        let hits = self.highlighter.indices(&value, &text);
+                        spans.extend(highlight_matches(&value, &hits, Style::default().fg(Color::Cyan)));
+                    }
+                }
+                ListItem::new(Line::from(spans))
+            }).collect()
+        };
+        let hosts_list = List::new(items)
+            .block(Block::default().borders(Borders::ALL).title(format!("Results ({})", self.filtered_hosts.len())))
+            .highlight_style(Style::default().add_modifier(Modifier::BOLD))
+            .highlight_symbol(">> ");
+        
+        let mut window_state = ListState::default()
+            .with_selected(selected_pos.filter(|i| window.contains(i)).map(|i| i - window.start));
+        f.render_stateful_widget(hosts_list, list_chunks[0], &mut window_state);
+        render_scrollbar(f, list_chunks[0], self.filtered_hosts.len(), height, self.search_offset);
+        
+        // Detalhes do host selecionado
+        let selected_host = self.list_state.selected()
+            .and_then(|i| self.hosts.get(i))
+            .filter(|host| !host.is_separator);
+        
+        let details = if let Some(host) = selected_host {
+            let mut lines = vec![
+                Line::from(vec![
+                    Span::styled("Host: ", Style::default().fg(Color::Yellow)),
+                    Span::raw(&host.name),
+                ]),
+            ];
+            
+            if let Some(hostname) = &host.hostname {
+                lines.push(Line::from(vec![
                     Span::styled("Hostname: ", Style::default().fg(Color::Yellow)),
-                    Span::raw(hostname),
+                    Span::raw(if self.privacy_mode { privacy::MASK } else { hostname }),
+                ]));
+            }
+            
+            if let Some(user) = &host.user {
+                lines.push(Line::from(vec![
+                    Span::styled("User: ", Style::default().fg(Color::Yellow)),
+                    Span::raw(user),
+                ]));
+            }
+            
+            Paragraph::new(lines)
+        } else {
+            Paragraph::new("No host selected")
+        };
+        
+        let details_block = details.block(Block::default().borders(Borders::ALL).title("Host Details"));
+        f.render_widget(details_block, list_chunks[1]);
+        
+        // Instruções
+        let help_text = if self.app_config.quick_connect {
+            "↑/↓: Navigate | Enter: Select | Alt+1-9: Connect | Esc: Cancel"
+        } else {
+            "↑/↓: Navigate | Enter: Select | Esc: Cancel"
+        };
+        let help = Paragraph::new(help_text)
+            .style(Style::default().fg(Color::Gray));
+        
+        let help_area = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Min(0), Constraint::Length(1)])
+            .split(f.size())[1];
+        
+        f.render_widget(help, help_area);
+    }
+    
+    // Ao sair de Pasta/Host de um novo host, aplicar padrões da pasta e valores herdados
+    fn leave_form_field(&mut self) {
+        if self.editing_host_index.is_some() {
+            return;
+        }
+        match self.form.current_field {
+            0 if !self.form.folder.is_empty() => {
+                let meta = FolderMeta::load(&self.app_config.get_workdir(), &self.form.folder);
+                self.form.apply_defaults(&meta.defaults);
+            }
+            1 if self.app_config.inherit_wildcard_defaults => {
+                self.form.inherited = effective::inherited_options(&self.hosts, &self.form.host)
+                    .into_iter()
+                    .filter_map(|o| HostForm::field_index(&o.key).map(|i| (i, o.value)))
+                    .collect();
+            }
+            _ => {}
+        }
+    }
+    
+    fn load_host_for_editing(&mut self, host_index: usize) {
+        if let Some(host) = self.hosts.get(host_index) {
+            self.editing_host_index = Some(host_index);
+            
+            // Determinar pasta baseada no source_dir ou usar pasta padrão
+            let folder = host.source_dir.clone().unwrap_or_else(|| "main".to_string());
+            
+            let option = |key: &str| host.other_options.get(key).cloned().unwrap_or_default();
+            
+            // Demais opções são preservadas ao reescrever o bloco
+            let mut extra_options: Vec<(String, String)> = host.other_options.iter()
+                .filter(|(k, _)| !k.eq_ignore_ascii_case("proxyjump"))
+                .filter(|(k, _)| !(0..FIELD_COUNT).any(|i| HostForm::toggle_key(i).is_some_and(|t| t.eq_ignore_ascii_case(k))))
+                .map(|(k, v)| (k.clone(), v.clone()))
+                .collect();
+            // O formulário edita o primeiro IdentityFile; os outros vão junto
+            extra_options.extend(host.identity_files.iter().skip(1).map(|v| ("IdentityFile".to_string(), v.clone())));
+            
+            self.form = HostForm {
+                folder,
+                host: host.name.clone(),
+                hostname: host.hostname.clone().unwrap_or_default(),
+                user: host.user.clone().unwrap_or_default(),
+                port: host.port.map(|p| p.to_string()).unwrap_or_default(),
+                identity_file: host.identity_files.first().cloned().unwrap_or_default(),
+                proxy_jump: option("proxyjump"),
+                local_forwards: Vec::new(),
+                forward_index: 0,
+                forward_subfield: 0,
+                forward_agent: option("forwardagent").to_lowercase(),
+                compression: option("compression").to_lowercase(),
+                strict_host_key_checking: option("stricthostkeychecking").to_lowercase(),
+                description: host.description.clone().unwrap_or_default(),
+                post_login: self.metadata.get(&host.name).post_login.unwrap_or_default(),
+                extra_options,
+                inherited: Default::default(),
+                current_field: 0,
+            };
+            for local_forward in &host.local_forwards {
+                self.form.add_forward_spec(local_forward);
+            }
+        }
+    }
+    
+    fn update_host(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+        if let Some(host_index) = self.editing_host_index {
+            // Para edição, precisamos remover o host antigo e adicionar o novo
+            // Por simplicidade, vamos apenas atualizar os dados na memória
+            // e depois reescrever o arquivo
+            let old_name = self.hosts.get(host_index).map(|h| h.name.clone()).unwrap_or_default();
+            let generated = self.hosts.get(host_index)
+                .map(|host| self.host_config_path(host))
+                .and_then(|path| std::fs::read_to_string(path).ok())
+                .is_some_and(|content| content.starts_with(db::GENERATED_HEADER));
+            let to_db = self.form_uses_db();
+            let mut previous = None;
+            let mut position = None;
+            match (&self.host_db, self.hosts.get(host_index)) {
+                (Some(host_db), Some(host)) if generated => {
+                    let folder = host.source_dir.as_deref().unwrap_or_default();
+                    let old_position = host_db.position(folder, &host.name)?;
+                    if folder == self.form.folder.trim() {
+                        position = old_position;
+                    }
+                    previous = Some((HostRecord::from_host(host, folder), old_position));
+                    host_db.delete(folder, &host.name)?;
+                }
+                _ => self.remove_host_from_file(host_index)?,
+            }
+            if let Err(e) = self.save_host(position) {
+                // Sem isso o host sumiria do banco na próxima regeração
+                if let (Some(host_db), Some((record, old_position))) = (&self.host_db, &previous) {
+                    host_db.restore(record, *old_position)?;
+                }
+                return Err(e);
+            }
+            self.metadata.rename(&old_name, &self.form.host)?;
+            // Saiu do banco para um arquivo comum: o include antigo precisa ser regerado
+            if generated && !to_db {
+                self.regenerate_from_db()?;
+            }
+        }
+        Ok(())
+    }
+    
+    fn remove_host_from_file(&mut self, host_index: usize) -> Result<(), Box<dyn std::error::Error>> {
+        if let Some(host) = self.hosts.get(host_index) {
+            // O arquivo de onde o host veio (um include, não o config principal);
+            // sem ele, o bloco ficaria no disco e voltaria no próximo carregamento
+            let config_path = self.host_config_path(host);
+            if !config_path.exists() {
+                return Err(format!("{} não existe; o host {} não foi removido", config_path.display(), host.name).into());
+            }
+            // Leva junto os comentários de descrição acima do bloco
+            SshConfig::remove_host_block(&config_path, &host.name, host.line)?;
+        }
+        
+        Ok(())
+    }
+    
+    fn detail_fields(host: &SshHost) -> Vec<(String, String, String)> {
+        let mut fields = vec![("Host".to_string(), "host".to_string(), host.name.clone())];
+        if let Some(hostname) = &host.hostname {
+            fields.push(("Hostname".to_string(), "hostname".to_string(), hostname.clone()));
+        }
+        if let Some(user) = &host.user {
+            fields.push(("User".to_string(), "user".to_string(), user.clone()));
+        }
+        if let Some(port) = host.port {
+            fields.push(("Port".to_string(), "port".to_string(), port.to_string()));
+        }
+        for identity_file in &host.identity_files {
+            fields.push(("Identity File".to_string(), "identityfile".to_string(), identity_file.clone()));
+        }
+        if let Some(proxy_jump) = host.other_options.get("proxyjump") {
+            fields.push(("ProxyJump".to_string(), "proxyjump".to_string(), proxy_jump.clone()));
+        }
+        for local_forward in &host.local_forwards {
+            fields.push(("LocalForward".to_string(), "localforward".to_string(), local_forward.clone()));
+        }
+        for (key, value) in host.other_options.iter().filter(|(k, _)| !k.eq_ignore_ascii_case("proxyjump")) {
+            fields.push((key.clone(), key.to_lowercase(), value.clone()));
+        }
+        fields
+    }
+    
+    fn host_location(&self, host: &SshHost) -> String {
+        match &host.source_file {
+            Some(source_file) => {
+                let workdir = self.app_config.get_workdir();
+                let path = source_file.strip_prefix(&workdir).unwrap_or(source_file);
+                format!("{}:{}", path.display(), host.line)
+            }
+            None => "?".to_string(),
+        }
+    }
+    
+    // Indicador colorido da política efetiva de chaves de host
+    fn host_key_line(&self, index: usize, policy: &audit::HostKeyPolicy) -> Line<'static> {
+        let color = match policy.risk() {
+            Some(Severity::High) => Color::Red,
+            Some(_) => Color::Yellow,
+            None => Color::Green,
+        };
+        let describe = |option: &Option<(String, usize)>, default: &str| match option {
+            Some((value, source)) if *source == index => value.clone(),
+            Some((value, source)) => format!("{} (via Host {})", value, self.hosts[*source].name),
+            None => format!("{} (default)", default),
+        };
+        let mut spans = vec![
+            Span::styled("Host keys: ", Style::default().fg(Color::Gray)),
+            Span::styled("● ", Style::default().fg(color)),
+            Span::styled(
+                format!("StrictHostKeyChecking {}, UserKnownHostsFile {}",
+                    describe(&policy.checking, "ask"), describe(&policy.known_hosts, "~/.ssh/known_hosts")),
+                Style::default().fg(color),
+            ),
+        ];
+        if policy.is_disabled() {
+            spans.push(Span::styled(" (k: accept-new)", Style::default().fg(Color::DarkGray)));
+        }
+        Line::from(spans)
+    }
+
+    // Troca `StrictHostKeyChecking no` por `accept-new` no bloco de onde a
+    // opção vem, que pode ser um padrão como `Host *`
+    fn fix_host_key_checking(&mut self, index: usize) -> Result<String, Box<dyn std::error::Error>> {
+        let (options, _) = effective::resolve(&self.hosts, index);
+        let policy = audit::HostKeyPolicy::from_options(&options);
+        let Some((value, source)) = policy.checking.clone().filter(|_| policy.is_disabled()) else {
+            return Err("a chave do host já é verificada; nada a corrigir".into());
+        };
+        let block = self.hosts[source].clone();
+        let path = self.host_config_path(&block);
+        let is_off = |v: &str| v.eq_ignore_ascii_case("no") || v.eq_ignore_ascii_case("off");
+        let generated = std::fs::read_to_string(&path).is_ok_and(|c| c.starts_with(db::GENERATED_HEADER));
+        match &self.host_db {
+            Some(host_db) if generated => {
+                let mut record = HostRecord::from_host(&block, block.source_dir.as_deref().unwrap_or_default());
+                for (key, value) in record.directives.iter_mut() {
+                    if key.eq_ignore_ascii_case("stricthostkeychecking") && is_off(value) {
+                        *value = "accept-new".to_string();
+                    }
+                }
+                host_db.upsert(&record)?;
+                self.regenerate_from_db()?;
+            }
+            _ => {
+                if !SshConfig::replace_host_value(&path, &block.name, "StrictHostKeyChecking", is_off, "accept-new")? {
+                    return Err(format!("StrictHostKeyChecking {} não encontrado em Host {}", value, block.name).into());
+                }
+                self.reload_hosts()?;
+            }
+        }
+        self.git_commit(format!("lazysshrs: StrictHostKeyChecking accept-new in {}", block.name), vec![path]);
+        let mut message = format!("Host {}: StrictHostKeyChecking {} → accept-new", block.name, value);
+        if source != index {
+            message.push_str("\n(o bloco vale também para outros hosts)");
+        }
+        Ok(message)
+    }
+
+    fn host_config_path(&self, host: &SshHost) -> std::path::PathBuf {
+        if let Some(source_file) = &host.source_file {
+            return source_file.clone();
+        }
+        let source_dir = host.source_dir.clone().unwrap_or_else(|| "ssh".to_string());
+        if source_dir == "ssh" {
+            self.app_config.get_main_config_path()
+        } else {
+            self.app_config.get_workdir().join(&source_dir).join("config")
+        }
+    }
+    
+    fn reach_method(&self, host: &SshHost) -> ReachMethod {
+        self.metadata.get(&host.name).reachability.unwrap_or(self.app_config.reachability)
+    }
+    
+    // Roda em segundo plano; o resultado chega em `poll_host_test`
+    fn test_connectivity(&mut self, host: &SshHost) {
+        if !self.is_target(host) {
+            self.show_popup("Teste de Conectividade", format!("{} é um padrão, não um host; pattern_actions = true permite testá-lo", host.name));
+            return;
+        }
+        self.host_test = Some(HostTest::start(self.check_target(host)));
+    }
+
+    fn poll_host_test(&mut self) {
+        let Some((result, message)) = self.host_test.as_ref().and_then(|test| test.poll()) else {
+            return;
+        };
+        let Some(test) = self.host_test.take() else {
+            return;
+        };
+        if !self.record_check(&test.name, result) {
+            return;
+        }
+        self.background_popup(&format!("Teste de Conectividade: {}", test.name), message);
+    }
+    
+    fn check_target(&self, host: &SshHost) -> CheckTarget {
+        let alias = effective::host_alias(host);
+        let hostname = host.hostname.clone().unwrap_or_else(|| alias.to_string());
+        let command = self.metadata.get(&host.name).check_command
+            .map(|c| c.replace("{host}", alias).replace("{hostname}", &hostname));
+        CheckTarget {
+            name: host.name.clone(),
+            hostname,
+            port: host.port.unwrap_or(22),
+            method: self.reach_method(host),
+            command,
+        }
+    }
+    
+    // Verifica em segundo plano os hosts sem resultado ou com resultado desatualizado
+    fn start_sweep(&mut self) {
+        self.start_sweep_of(false);
+    }
+
+    // Com `all`, verifica de novo também os hosts com resultado recente (`P`)
+    fn start_sweep_of(&mut self, all: bool) {
+        if let Some(sweep) = self.sweep.take() {
+            sweep.cancel();
+        }
+        let targets: Vec<CheckTarget> = self.hosts.iter()
+            .filter(|h| !h.is_separator && !h.is_pattern)
+            .filter(|h| {
+                all || self.saved_state.checks.get(&h.name)
+                    .is_none_or(|r| r.is_stale(self.app_config.check_ttl_secs))
+            })
+            .map(|h| self.check_target(h))
+            .collect();
+        if !targets.is_empty() {
+            self.sweep = Some(Sweep::start(targets, self.app_config.sweep_concurrency));
+        }
+    }
+    
+    fn poll_sweep(&mut self) {
+        let Some(sweep) = self.sweep.as_mut() else {
+            return;
+        };
+        let results = sweep.poll();
+        let finished = sweep.is_finished();
+        if !results.is_empty() {
+            self.saved_state.checks.extend(results);
+        }
+        if finished {
+            self.sweep = None;
+            if let Err(e) = self.saved_state.save() {
+                self.show_popup("Estado", format!("Erro ao salvar estado: {}", e));
+            }
+        }
+    }
+    
+    // Nome do alias e comando ssh com as opções efetivas (inclusive as herdadas de `Host *`)
+    fn host_shell_command(&self, index: usize) -> (String, Vec<String>) {
+        let host = &self.hosts[index];
+        let alias = effective::host_alias(host).to_string();
+        let options = self.effective_pairs(index);
+        let args = export::ssh_args(&alias, &options);
+        (alias, args)
+    }
+    
+    fn export_reachability(&self, path: &str) -> Result<usize, Box<dyn std::error::Error>> {
+        let rows: Vec<ReachabilityRow> = self.hosts.iter()
+            .filter(|h| !h.is_separator)
+            .filter_map(|h| {
+                let result = self.saved_state.checks.get(&h.name)?;
+                let target = self.check_target(h);
+                Some(ReachabilityRow::new(&h.name, &target.hostname, target.port, result))
+            })
+            .collect();
+        if rows.is_empty() {
+            return Err("nenhum host verificado ainda".into());
+        }
+        export::write_reachability(&expand_home(path)?, &rows)?;
+        Ok(rows.len())
+    }
+
+    fn export_audit(&self, path: &str) -> Result<usize, Box<dyn std::error::Error>> {
+        let hosts: Vec<export::AuditHost> = self.audit.iter()
+            .map(|(index, findings)| export::AuditHost {
+                host: self.hosts[*index].name.clone(),
+                location: self.host_location(&self.hosts[*index]),
+                findings: findings.clone(),
+            })
+            .collect();
+        export::write_audit(&expand_home(path)?, &hosts)?;
+        Ok(hosts.len())
+    }
+    
+    // Branch e estado do repositório do workdir no rodapé da lista
+    fn git_status_line(&self) -> Line<'static> {
+        match &self.git_status {
+            Some(status) if status.is_clean() => Line::from(vec![
+                Span::raw(format!(" git: {} ", status.branch)),
+                Span::styled("✓ clean ", Style::default().fg(Color::Green)),
+            ]),
+            Some(status) => Line::from(vec![
+                Span::raw(format!(" git: {} ", status.branch)),
+                Span::styled(format!("● {} changed ", status.changed), Style::default().fg(Color::Yellow)),
+            ]),
+            None => Line::from(""),
+        }
+    }
+    
+    // Emblema da coluna de status: verde/vermelho pelo cache, cinza se nunca verificado
+    fn status_spans(&self, host: &SshHost) -> Vec<Span<'static>> {
+        if let Some(test) = self.host_test.as_ref().filter(|t| t.name == host.name) {
+            return vec![Span::styled(format!("{}     ", test.spinner()), Style::default().fg(Color::Yellow))];
+        }
+        match self.saved_state.checks.get(&host.name) {
+            Some(result) => {
+                let color = if result.up { Color::Green } else { Color::Red };
+                let mut style = Style::default().fg(color);
+                if result.is_stale(self.app_config.check_ttl_secs) {
+                    style = style.add_modifier(Modifier::DIM);
+                }
+                vec![
+                    Span::styled("● ", style),
+                    Span::styled(format!("{} ", result.latency_bars()), style),
+                ]
+            }
+            None => vec![Span::styled("○     ", Style::default().fg(Color::DarkGray))],
+        }
+    }
+    
+    // Reabre os túneis que estavam ativos quando a sessão anterior terminou
+    fn restore_tunnels(&mut self) {
+        let mut failed = Vec::new();
+        for saved in std::mem::take(&mut self.saved_state.tunnels) {
+            let started = ForwardSpec::parse(&saved.forward)
+                .ok_or_else(|| "encaminhamento inválido".into())
+                .and_then(|forward| self.tunnels.start(&saved.host, &forward));
+            if let Err(e) = started {
+                failed.push(format!("{} {}: {}", saved.host, saved.forward, e));
+            }
+        }
+        if !failed.is_empty() {
+            self.show_popup("Túneis", format!("Não foi possível restaurar:\n{}", failed.join("\n")));
+        }
+    }
+    
+    fn render_restore_tunnels(&mut self, f: &mut Frame) {
+        use ratatui::widgets::Clear;
+        
+        let area = f.size();
+        let lines: Vec<Line> = self.saved_state.tunnels.iter()
+            .map(|t| Line::from(format!("{}  {}", t.host, t.forward)))
+            .collect();
+        let width = 60.min(area.width.saturating_sub(4));
+        let height = (lines.len() as u16 + 2).min(area.height.saturating_sub(2));
+        let dialog_area = Rect {
+            x: (area.width - width) / 2,
+            y: area.height.saturating_sub(height) / 2,
+            width,
+            height,
+        };
+        
+        f.render_widget(Clear, dialog_area);
+        let dialog = Paragraph::new(lines)
+            .block(Block::default().borders(Borders::ALL).title("Restore tunnels from last session? (Enter: yes | Esc: no)"))
+            .style(Style::default().fg(Color::Yellow));
+        f.render_widget(dialog, dialog_area);
+    }
+    
+    fn prompt_connect_options(&mut self, index: usize) {
+        let Some(host) = self.hosts.get(index) else {
+            return;
+        };
+        let title = format!("Opções do ssh para {} (ex.: -vvv -L 8080:x:80)", host.name);
+        self.open_prompt(&title, PromptAction::ConnectWithOptions(index), self.last_connect_args.clone());
+    }
+
+    fn run_quick_action(&mut self, action: QuickAction) {
+        let index = self.action_host;
+        let Some(host) = self.hosts.get(index).filter(|h| !h.is_separator).cloned() else {
+            self.state = AppState::List;
+            return;
+        };
+        self.state = AppState::List;
+        match action {
+            QuickAction::Connect => {
+                if let Err(e) = self.connect_ssh(&host) {
+                    self.show_popup("Conexão SSH", format!("Erro na conexão SSH: {}", e));
+                }
+            }
+            QuickAction::ConnectWithOptions => self.prompt_connect_options(index),
+            QuickAction::Record => {
+                if let Err(e) = self.open_session(&host, &[], true) {
+                    self.show_popup("Conexão SSH", format!("Erro na conexão SSH: {}", e));
+                }
+            }
+            QuickAction::Sftp => {
+                if let Err(e) = self.suspend_tui(|| ConnectivityTest::sftp(&host.name)) {
+                    self.show_popup("SFTP", format!("Erro ao abrir o sftp: {}", e));
+                }
+            }
+            QuickAction::RunCommand => {
+                self.open_prompt(&format!("Comando em {}", host.name), PromptAction::RunCommand(index), String::new());
+            }
+            QuickAction::Ping => self.test_connectivity(&host),
+            QuickAction::Edit => {
+                self.load_host_for_editing(index);
+                self.state = AppState::Edit;
+            }
+            // Formulário de novo host com os dados deste
+            QuickAction::Duplicate => {
+                self.load_host_for_editing(index);
+                self.editing_host_index = None;
+                self.form.host = format!("{}-copy", host.name);
+                self.state = AppState::Form;
+            }
+            QuickAction::Delete if self.confirms(Destructive::Delete) => {
+                self.pending_delete = Some(index);
+                self.state = AppState::ConfirmDelete;
+            }
+            QuickAction::Delete => self.delete_host(index),
+            QuickAction::CopyCommand => {
+                let (_, args) = self.host_shell_command(index);
+                let command = args.iter().map(|a| export::shell_quote(a)).collect::<Vec<_>>().join(" ");
+                let message = match launch::copy_to_clipboard(&command) {
+                    Ok(via) if self.privacy_mode => format!("Comando copiado via {} (oculto no modo privacidade)", via),
+                    Ok(via) => format!("{}\n\n(copiado via {})", command, via),
+                    Err(e) => format!("Erro ao copiar: {}", e),
+                };
+                self.show_popup("Comando ssh", message);
+            }
+        }
+    }
+
+    fn confirms(&self, action: Destructive) -> bool {
+        self.app_config.confirm_destructive.contains(&action)
+    }
+
+    // Outros blocos com o mesmo nome do host do formulário
+    fn conflicting_hosts(&self) -> Vec<usize> {
+        self.hosts.iter().enumerate()
+            .filter(|&(i, h)| !h.is_separator && h.name == self.form.host && Some(i) != self.editing_host_index)
+            .map(|(i, _)| i)
+            .collect()
+    }
+
+    // Enter no formulário (ou na tela de confirmação): grava, a menos que o
+    // nome já exista e `overwrite` peça confirmação
+    fn finish_form(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+        if self.confirms(Destructive::Overwrite) && !self.conflicting_hosts().is_empty() {
+            self.state = AppState::ConfirmOverwrite;
+            return Ok(());
+        }
+        self.save_form()
+    }
+
+    fn save_form(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+        // Uma pasta nova ganha um Include no config principal
+        let main_config = self.app_config.get_main_config_path();
+        let main_before = std::fs::read_to_string(&main_config).ok();
+        let mut paths = vec![self.app_config.get_workdir().join(self.form.folder.trim()).join("config")];
+        let message = if self.editing_host_index.is_some() {
+            let old_path = self.editing_host_index.and_then(|i| self.hosts.get(i)).map(|h| self.host_config_path(h));
+            self.update_host()?;
+            paths.extend(old_path);
+            format!("lazysshrs: edit host {}", self.form.host)
+        } else {
+            self.save_host(None)?;
+            format!("lazysshrs: add host {}", self.form.host)
+        };
+        if std::fs::read_to_string(&main_config).ok() != main_before {
+            paths.push(main_config);
+        }
+        self.git_commit(message, paths);
+        let post_login = Some(self.form.post_login.trim().to_string()).filter(|c| !c.is_empty());
+        self.metadata.update(&self.form.host, |meta| meta.post_login = post_login)?;
+        self.raw_cache = None;
+        self.state = AppState::List;
+        self.editing_host_index = None;
+        Ok(())
+    }
+
+    fn finish_merge(&mut self, source: usize, target: usize) {
+        let message = match self.merge_includes(source, target) {
+            Ok(message) => message,
+            Err(e) => format!("Erro ao mesclar: {}", e),
+        };
+        self.show_popup("Includes", message);
+    }
+
+    // Apaga o bloco do arquivo de onde ele veio (ou do banco) e commita
+    fn delete_host(&mut self, index: usize) {
+        let Some(host) = self.hosts.get(index).filter(|h| !h.is_separator).cloned() else {
+            return;
+        };
+        let path = self.host_config_path(&host);
+        let generated = std::fs::read_to_string(&path).is_ok_and(|content| content.starts_with(db::GENERATED_HEADER));
+        // Sem a cópia na lixeira o host não é apagado
+        let database = generated && self.host_db.is_some();
+        if let Err(e) = Trash::push(TrashEntry::new(&host, path.clone(), database)) {
+            self.show_popup("Apagar host", format!("Erro ao mover {} para a lixeira: {}", host.name, e));
+            return;
+        }
+        let result = match &self.host_db {
+            Some(host_db) if generated => host_db.delete(host.source_dir.as_deref().unwrap_or_default(), &host.name)
+                .and_then(|_| self.regenerate_from_db()),
+            _ => self.remove_host_from_file(index).and_then(|_| self.reload_hosts()),
+        };
+        match result {
+            Ok(()) => {
+                self.git_commit(format!("lazysshrs: remove host {}", host.name), vec![path]);
+                self.raw_cache = None;
+            }
+            Err(e) => self.show_popup("Apagar host", format!("Erro ao apagar {}: {}", host.name, e)),
+        }
+    }
+
+    // Devolve o host ao arquivo de origem e o tira da lixeira
+    fn restore_from_trash(&mut self, i: usize) {
+        let Some(entry) = self.trash.entries.get(i).cloned() else {
+            return;
+        };
+        if self.hosts.iter().any(|h| !h.is_separator && h.name == entry.name) {
+            self.show_popup("Lixeira", format!("O host {} já existe; apague-o ou renomeie-o antes de restaurar", entry.name));
+            return;
+        }
+        let workdir = self.app_config.get_workdir();
+        let main_config = self.app_config.get_main_config_path();
+        let result = entry.restore(self.host_db.as_ref(), &workdir, &main_config)
+            .and_then(|paths| {
+                self.trash.entries.remove(i);
+                self.trash.save()?;
+                Ok(paths)
+            });
+        match result {
+            Ok(paths) => {
+                self.raw_cache = None;
+                if let Err(e) = self.reload_hosts() {
+                    self.show_popup("Lixeira", format!("Erro ao recarregar os hosts: {}", e));
+                    return;
+                }
+                self.git_commit(format!("lazysshrs: restore host {}", entry.name), paths);
+                self.select_trash_entry(i);
+                self.show_popup("Lixeira", format!("{} restaurado em {}", entry.name, entry.file.display()));
+            }
+            Err(e) => self.show_popup("Lixeira", format!("Erro ao restaurar {}: {}", entry.name, e)),
+        }
+    }
+
+    // Apaga de vez; não há como recuperar depois
+    fn purge_from_trash(&mut self, i: usize) {
+        if i >= self.trash.entries.len() {
+            return;
+        }
+        let entry = self.trash.entries.remove(i);
+        if let Err(e) = self.trash.save() {
+            self.trash.entries.insert(i, entry);
+            self.show_popup("Lixeira", format!("Erro ao salvar a lixeira: {}", e));
+            return;
+        }
+        self.select_trash_entry(i);
+    }
+
+    fn open_history(&mut self, index: usize) {
+        self.history_host = index;
+        self.recordings = recording::list(&self.hosts[index].name);
+        self.recordings_state.select(if self.recordings.is_empty() { None } else { Some(0) });
+        self.state = AppState::History;
+    }
+
+    fn delete_recording(&mut self, i: usize) {
+        let Some(entry) = self.recordings.get(i) else {
+            return;
+        };
+        if let Err(e) = std::fs::remove_file(&entry.path) {
+            self.show_popup("Gravações", format!("Erro ao apagar {}: {}", entry.path.display(), e));
+            return;
+        }
+        self.recordings.remove(i);
+        let len = self.recordings.len();
+        self.recordings_state.select(if len == 0 { None } else { Some(i.min(len - 1)) });
+    }
+
+    fn render_history(&mut self, f: &mut Frame) {
+        let Some(host) = self.hosts.get(self.history_host) else {
+            return;
+        };
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Length(4), Constraint::Min(0), Constraint::Length(1)])
+            .split(f.size());
+
+        let meta = self.metadata.get(&host.name);
+        let last = meta.last_connected()
+            .map(|last| format!("{} ({})", state::format_timestamp(last), state::format_age(Duration::from_secs(state::now().saturating_sub(last)))))
+            .unwrap_or_else(|| "never".to_string());
+        let recording = if self.app_config.record_sessions { "every session" } else { "off (a in the action menu records one)" };
+        let summary = Paragraph::new(vec![
+            Line::from(vec![
+                Span::styled("Connections: ", Style::default().fg(Color::Yellow)),
+                Span::raw(format!("{}, last {}", meta.connections.len(), last)),
+            ]),
+            Line::from(vec![
+                Span::styled("Recording: ", Style::default().fg(Color::Yellow)),
+                Span::raw(recording),
+            ]),
+        ])
+        .block(Block::default().borders(Borders::ALL).title(format!("History: {}", host.name)));
+        f.render_widget(summary, chunks[0]);
+
+        let mut items: Vec<ListItem> = self.recordings.iter().map(|r| {
+            ListItem::new(Line::from(vec![
+                Span::styled(format!("{}  ", r.started), Style::default().fg(Color::Cyan)),
+                Span::styled(format!("{:>6} KB  ", r.size.div_ceil(1024)), Style::default().fg(Color::Gray)),
+                Span::styled(r.path.display().to_string(), Style::default().fg(Color::DarkGray)),
+            ]))
+        }).collect();
+        if items.is_empty() {
+            items.push(ListItem::new(Line::from("Nenhuma sessão gravada")));
+        }
+        let list = List::new(items)
+            .block(Block::default().borders(Borders::ALL).title(format!("Recordings ({})", self.recordings.len())))
+            .highlight_style(Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD))
+            .highlight_symbol(">> ");
+        f.render_stateful_widget(list, chunks[1], &mut self.recordings_state);
+
+        let help = Paragraph::new("↑/↓: Navigate | Enter/p: Play | x: Delete | Esc: Back")
+            .style(Style::default().fg(Color::Gray));
+        f.render_widget(help, chunks[2]);
+    }
+
+    fn select_trash_entry(&mut self, i: usize) {
+        let len = self.trash.entries.len();
+        self.trash_state.select(if len == 0 { None } else { Some(i.min(len - 1)) });
+    }
+
+    fn render_key_map(&mut self, f: &mut Frame) {
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Min(0), Constraint::Length(1)])
+            .split(f.size());
+        let panes = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
+            .split(chunks[0]);
+
+        let key_label = |usage: &KeyUsage| match &usage.value {
+            Some(value) if self.privacy_mode => privacy::mask_value("identityfile", value),
+            Some(value) => value.clone(),
+            None => "(agent / default keys)".to_string(),
+        };
+        let mut items: Vec<ListItem> = self.key_map.iter().map(|usage| {
+            let count = match usage.hosts.len() {
+                0 => Span::styled("unused", Style::default().fg(Color::DarkGray)),
+                n => Span::styled(format!("{} hosts", n), Style::default().fg(Color::Cyan)),
+            };
+            let mut spans = vec![Span::raw(format!("{:<36} ", key_label(usage))), count];
+            if let Some(problem) = usage.problem {
+                spans.push(Span::styled(format!("  ⚠ {}", problem), Style::default().fg(Color::Red)));
+            }
+            ListItem::new(Line::from(spans))
+        }).collect();
+        if items.is_empty() {
+            items.push(ListItem::new(Line::from("Nenhuma chave encontrada")));
+        }
+        let list = List::new(items)
+            .block(Block::default().borders(Borders::ALL).title(format!("Keys ({})", self.key_map.len())))
+            .highlight_style(Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD))
+            .highlight_symbol(">> ");
+        f.render_stateful_widget(list, panes[0], &mut self.key_map_state);
+
+        let mut lines = Vec::new();
+        let mut title = "Hosts".to_string();
+        if let Some(usage) = self.key_map_state.selected().and_then(|i| self.key_map.get(i)) {
+            title = format!("Hosts using {}", key_label(usage));
+            for &(index, via) in &usage.hosts {
+                let host = &self.hosts[index];
+                let mut spans = vec![Span::raw(format!("{:<24} ", host.name))];
+                spans.push(Span::styled(self.host_location(host), Style::default().fg(Color::Gray)));
+                if let Some(block) = via {
+                    spans.push(Span::styled(format!("  via Host {}", self.hosts[block].name), Style::default().fg(Color::DarkGray)));
+                }
+                lines.push(Line::from(spans));
+            }
+        }
+        let hosts = Paragraph::new(lines)
+            .block(Block::default().borders(Borders::ALL).title(title));
+        f.render_widget(hosts, panes[1]);
+
+        let help = Paragraph::new("↑/↓: Navigate | r: Rotate key (or resume rotation) | Esc: Back")
+            .style(Style::default().fg(Color::Gray));
+        f.render_widget(help, chunks[1]);
+    }
+
+    // Avança a rotação; cada passo grava o progresso para poder ser retomado
+    fn run_rotation_step(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+        let Some(mut rotation) = self.rotation.take() else {
+            return Ok(());
+        };
+        let result = match rotation.step {
+            RotationStep::Generate => self.suspend_tui(|| rotation.generate()).map(|_| {
+                rotation.step = RotationStep::Push;
+            }),
+            RotationStep::Push => self.suspend_tui(|| rotation.push()).map(|_| {
+                if rotation.hosts.iter().all(|h| h.pushed == Some(true)) {
+                    rotation.step = RotationStep::Update;
+                }
+            }),
+            RotationStep::Update => self.rotation_update(&mut rotation).map(|_| {
+                rotation.step = RotationStep::RemoveOld;
+            }),
+            RotationStep::RemoveOld => self.suspend_tui(|| rotation.remove_old()).map(|_| {
+                if rotation.hosts.iter().filter(|h| h.pushed == Some(true)).all(|h| h.removed == Some(true)) {
+                    rotation.step = RotationStep::Done;
+                }
+            }),
+            RotationStep::Done => {
+                match Rotation::clear() {
+                    Ok(()) => self.state = AppState::List,
+                    Err(e) => {
+                        self.rotation = Some(rotation);
+                        self.show_popup("Rotação de chave", format!("Erro ao concluir: {}", e));
+                    }
+                }
+                return Ok(());
+            }
+        };
+        let saved = rotation.save();
+        self.rotation = Some(rotation);
+        if let Err(e) = result.and(saved) {
+            self.show_popup("Rotação de chave", format!("Erro: {}", e));
+        }
+        Ok(())
+    }
+
+    // Troca o IdentityFile antigo pelo novo em cada bloco que o define
+    fn rotation_update(&mut self, rotation: &mut Rotation) -> Result<(), Box<dyn std::error::Error>> {
+        let mut paths = Vec::new();
+        let mut from_db = false;
+        for block in rotation.blocks.iter_mut().filter(|b| !b.updated) {
+            let generated = std::fs::read_to_string(&block.file).is_ok_and(|c| c.starts_with(db::GENERATED_HEADER));
+            match &self.host_db {
+                Some(host_db) if generated => {
+                    let host = self.hosts.iter()
+                        .find(|h| h.name == block.name && h.source_file.as_ref() == Some(&block.file))
+                        .ok_or_else(|| format!("Host {} não encontrado no banco", block.name))?;
+                    let mut record = HostRecord::from_host(host, host.source_dir.as_deref().unwrap_or_default());
+                    for (key, value) in record.directives.iter_mut() {
+                        if key.eq_ignore_ascii_case("identityfile") && keys::same_key(value, &rotation.old_key) {
+                            *value = rotation.new_key.clone();
+                        }
+                    }
+                    host_db.upsert(&record)?;
+                    from_db = true;
+                }
+                _ => {
+                    let is_old = |value: &str| keys::same_key(value, &rotation.old_key);
+                    SshConfig::replace_host_value(&block.file, &block.name, "IdentityFile", is_old, &rotation.new_key)?;
+                }
+            }
+            block.updated = true;
+            paths.push(block.file.clone());
+        }
+        if from_db {
+            self.regenerate_from_db()?;
+        } else {
+            self.reload_hosts()?;
+        }
+        self.git_commit(format!("lazysshrs: rotate key {} -> {}", rotation.old_key, rotation.new_key), paths);
+        Ok(())
+    }
+
+    fn render_rotation(&mut self, f: &mut Frame) {
+        let Some(rotation) = &self.rotation else {
+            return;
+        };
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Min(0), Constraint::Length(1)])
+            .split(f.size());
+
+        let current = RotationStep::ALL.iter().position(|&s| s == rotation.step).unwrap_or(0);
+        let mut lines = vec![
+            Line::from(vec![
+                Span::styled("Old key: ", Style::default().fg(Color::Yellow)),
+                Span::raw(rotation.old_key.clone()),
+            ]),
+            Line::from(vec![
+                Span::styled("New key: ", Style::default().fg(Color::Yellow)),
+                Span::raw(rotation.new_key.clone()),
+            ]),
+            Line::from(Span::styled(
+                format!("Started {}", state::format_timestamp(rotation.started_at)),
+                Style::default().fg(Color::Gray),
+            )),
+            Line::from(""),
+        ];
+        for (i, step) in RotationStep::ALL.iter().enumerate() {
+            let (mark, style) = match i.cmp(&current) {
+                std::cmp::Ordering::Less => ("✓", Style::default().fg(Color::Green)),
+                std::cmp::Ordering::Equal => ("→", Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD)),
+                std::cmp::Ordering::Greater => ("○", Style::default().fg(Color::DarkGray)),
+            };
+            lines.push(Line::from(Span::styled(format!("{} {}. {}", mark, i + 1, step.label()), style)));
+        }
+
+        let result = |value: Option<bool>| match value {
+            Some(true) => Span::styled("✓ ", Style::default().fg(Color::Green)),
+            Some(false) => Span::styled("✗ ", Style::default().fg(Color::Red)),
+            None => Span::styled("· ", Style::default().fg(Color::DarkGray)),
+        };
+        lines.push(Line::from(""));
+        lines.push(Line::from(Span::styled("Hosts (pushed, old key removed)", Style::default().fg(Color::Yellow))));
+        for host in &rotation.hosts {
+            lines.push(Line::from(vec![
+                Span::raw("  "),
+                result(host.pushed),
+                result(host.removed),
+                Span::raw(host.name.clone()),
+            ]));
+        }
+        lines.push(Line::from(""));
+        lines.push(Line::from(Span::styled("IdentityFile entries", Style::default().fg(Color::Yellow))));
+        for block in &rotation.blocks {
+            lines.push(Line::from(vec![
+                Span::raw("  "),
+                result(block.updated.then_some(true)),
+                Span::raw(format!("Host {} ", block.name)),
+                Span::styled(block.file.display().to_string(), Style::default().fg(Color::Gray)),
+            ]));
+        }
+
+        let body = Paragraph::new(lines)
+            .block(Block::default().borders(Borders::ALL).title("Key Rotation"))
+            .wrap(ratatui::widgets::Wrap { trim: false });
+        f.render_widget(body, chunks[0]);
+
+        let help = match rotation.step {
+            RotationStep::Push | RotationStep::RemoveOld => "Enter: Run step (retries failures) | s: Skip step | x: Abort | Esc: Back (resume later)",
+            RotationStep::Done => "Enter: Finish | Esc: Back",
+            _ => "Enter: Run step | x: Abort | Esc: Back (resume later)",
+        };
+        f.render_widget(Paragraph::new(help).style(Style::default().fg(Color::Gray)), chunks[1]);
+    }
+
+    fn render_audit(&mut self, f: &mut Frame) {
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Min(0), Constraint::Length(1)])
+            .split(f.size());
+        let panes = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([Constraint::Percentage(35), Constraint::Percentage(65)])
+            .split(chunks[0]);
+
+        let count = |severity: Severity| self.audit.iter().flat_map(|(_, f)| f).filter(|f| f.severity == severity).count();
+        let title = format!(
+            "Audit ({} hosts: {} high, {} medium, {} low)",
+            self.audit.len(), count(Severity::High), count(Severity::Medium), count(Severity::Low)
+        );
+        let mut items: Vec<ListItem> = self.audit.iter().map(|(index, findings)| {
+            let mut spans = vec![Span::raw(format!("{:<24}", self.hosts[*index].name))];
+            for severity in [Severity::High, Severity::Medium, Severity::Low] {
+                let n = findings.iter().filter(|f| f.severity == severity).count();
+                if n > 0 {
+                    spans.push(Span::styled(format!(" ●{}", n), Style::default().fg(severity_color(severity))));
+                }
+            }
+            ListItem::new(Line::from(spans))
+        }).collect();
+        if items.is_empty() {
+            items.push(ListItem::new(Line::from("Nenhuma opção arriscada encontrada")));
+        }
+        let list = List::new(items)
+            .block(Block::default().borders(Borders::ALL).title(title))
+            .highlight_style(Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD))
+            .highlight_symbol(">> ");
+        f.render_stateful_widget(list, panes[0], &mut self.audit_state);
+
+        let mut lines = Vec::new();
+        if let Some((index, findings)) = self.audit_state.selected().and_then(|i| self.audit.get(i)) {
+            for finding in findings {
+                let source = &self.hosts[finding.source];
+                let origin = if finding.source == *index {
+                    String::new()
+                } else {
+                    format!("  (via Host {}, {})", source.name, self.host_location(source))
+                };
+                lines.push(Line::from(vec![
+                    Span::styled(
+                        format!("[{}] ", finding.severity.label().to_uppercase()),
+                        Style::default().fg(severity_color(finding.severity)).add_modifier(Modifier::BOLD),
+                    ),
+                    Span::styled(finding.title.clone(), Style::default().add_modifier(Modifier::BOLD)),
+                ]));
+                lines.push(Line::from(vec![
+                    Span::styled(format!("  {} {}", finding.key, finding.value), Style::default().fg(Color::Cyan)),
+                    Span::styled(origin, Style::default().fg(Color::DarkGray)),
+                ]));
+                lines.push(Line::from(format!("  {}", finding.explanation)));
+                lines.push(Line::from(Span::styled(format!("  → {}", finding.recommendation), Style::default().fg(Color::Green))));
+                lines.push(Line::from(""));
+            }
+        }
+        let details = Paragraph::new(lines)
+            .block(Block::default().borders(Borders::ALL).title("Findings"))
+            .wrap(ratatui::widgets::Wrap { trim: false });
+        f.render_widget(details, panes[1]);
+
+        let help = Paragraph::new("↑/↓: Navigate | Enter: Go to host | x: Export (.md/.json) | Esc: Back")
+            .style(Style::default().fg(Color::Gray));
+        f.render_widget(help, chunks[1]);
+    }
+
+    fn render_trash(&mut self, f: &mut Frame) {
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Min(0), Constraint::Length(1)])
+            .split(f.size());
+        let panes = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
+            .split(chunks[0]);
+
+        let now = state::now();
+        let mut items: Vec<ListItem> = self.trash.entries.iter().map(|entry| {
+            let age = std::time::Duration::from_secs(now.saturating_sub(entry.deleted_at));
+            ListItem::new(Line::from(vec![
+                Span::styled(format!("{:<20} ", entry.name), Style::default().fg(Color::Cyan)),
+                Span::raw(format!("{:<12} ", entry.folder.as_deref().unwrap_or("main"))),
+                Span::styled(state::format_age(age), Style::default().fg(Color::Gray)),
+            ]))
+        }).collect();
+        if items.is_empty() {
+            items.push(ListItem::new(Line::from("Lixeira vazia")));
+        }
+        let list = List::new(items)
+            .block(Block::default().borders(Borders::ALL).title(format!("Trash ({} hosts)", self.trash.entries.len())))
+            .highlight_style(Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD))
+            .highlight_symbol(">> ");
+        f.render_stateful_widget(list, panes[0], &mut self.trash_state);
+
+        // Bloco como será devolvido ao arquivo
+        let mut lines = Vec::new();
+        if let Some(entry) = self.trash_state.selected().and_then(|i| self.trash.entries.get(i)) {
+            let origin = if entry.database { "host database" } else { "file" };
+            lines.push(Line::from(Span::styled(
+                format!("{} ({}), deleted {}", entry.file.display(), origin, state::format_timestamp(entry.deleted_at)),
+                Style::default().fg(Color::Gray),
+            )));
+            lines.push(Line::from(""));
+            for comment in ssh_config::description_lines(entry.description.as_deref()) {
+                lines.push(Line::from(Span::styled(comment, Style::default().fg(Color::DarkGray))));
+            }
+            lines.push(Line::from(format!("Host {}", entry.name)));
+            for (key, value) in &entry.directives {
+                lines.push(Line::from(format!("    {} {}", key, ssh_config::quote(key, value))));
+            }
+        }
+        let block = Paragraph::new(lines)
+            .block(Block::default().borders(Borders::ALL).title("Block"))
+            .wrap(ratatui::widgets::Wrap { trim: false });
+        f.render_widget(block, panes[1]);
+
+        let help = Paragraph::new("↑/↓: Navigate | Enter/r: Restore | x: Purge | Esc: Back")
+            .style(Style::default().fg(Color::Gray));
+        f.render_widget(help, chunks[1]);
+    }
+
+    fn render_quick_actions(&mut self, f: &mut Frame) {
+        use ratatui::widgets::Clear;
+
+        let Some(host) = self.hosts.get(self.action_host) else {
+            return;
+        };
+        let area = f.size();
+        let width = 40.min(area.width.saturating_sub(4));
+        let height = (actions::ALL.len() as u16 + 2).min(area.height.saturating_sub(2));
+        let menu_area = Rect {
+            x: (area.width - width) / 2,
+            y: area.height.saturating_sub(height) / 2,
+            width,
+            height,
+        };
+
+        let items: Vec<ListItem> = actions::ALL.iter()
+            .map(|a| ListItem::new(Line::from(vec![
+                Span::styled(format!("{}  ", a.key()), Style::default().fg(Color::DarkGray)),
+                Span::raw(a.label()),
+            ])))
+            .collect();
+        let menu = List::new(items)
+            .block(Block::default().borders(Borders::ALL).title(format!("{} (Enter | Esc)", host.name)))
+            .highlight_style(Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD))
+            .highlight_symbol(">> ");
+
+        f.render_widget(Clear, menu_area);
+        f.render_stateful_widget(menu, menu_area, &mut self.action_state);
+    }
+
+    fn render_confirm_delete(&mut self, f: &mut Frame) {
+        let Some(host) = self.pending_delete.and_then(|i| self.hosts.get(i)) else {
+            return;
+        };
+        let lines = vec![
+            Line::from(format!("Host {}", host.name)),
+            Line::from(Span::styled(self.host_location(host), Style::default().fg(Color::Gray))),
+        ];
+        render_dialog(f, "Delete host? (Enter: yes | Esc: no)", lines);
+    }
+
+    fn render_confirm_overwrite(&mut self, f: &mut Frame) {
+        let existing: Vec<Line> = self.conflicting_hosts().into_iter()
+            .map(|i| Line::from(Span::styled(self.host_location(&self.hosts[i]), Style::default().fg(Color::Gray))))
+            .collect();
+        let mut lines = vec![Line::from(format!("Host {} already exists:", self.form.host))];
+        lines.extend(existing);
+        render_dialog(f, "Save anyway? (Enter: yes | Esc: back)", lines);
+    }
+
+    fn render_confirm_merge(&mut self, f: &mut Frame) {
+        let Some((source, target)) = self.pending_merge else {
+            return;
+        };
+        let name = |i: usize| self.files.get(i).map(|f| f.path.display().to_string()).unwrap_or_default();
+        let lines = vec![
+            Line::from(format!("{} → {}", name(source), name(target))),
+            Line::from(Span::styled("The source file and its Include line are removed", Style::default().fg(Color::Gray))),
+        ];
+        render_dialog(f, "Merge includes? (Enter: yes | Esc: no)", lines);
+    }
+
+    fn save_session(&mut self) {
+        self.tunnels.refresh();
+        self.saved_state.tunnels = self.tunnels.tunnels.iter()
+            .filter(|t| t.status == TunnelStatus::Running)
+            .map(|t| SavedTunnel { host: t.host.clone(), forward: t.forward.to_spec() })
+            .collect();
+        self.saved_state.selected_host = self.list_state.selected()
+            .and_then(|i| self.hosts.get(i))
+            .filter(|h| !h.is_separator)
+            .map(|h| h.name.clone());
+        // Sem terminal para um popup: falhar ao salvar o estado não impede a saída
+        let _ = self.saved_state.save();
+    }
+    
+    // false se o estado não pôde ser gravado (o erro já foi mostrado)
+    fn record_check(&mut self, name: &str, result: CheckResult) -> bool {
+        self.saved_state.checks.insert(name.to_string(), result);
+        if self.table_sort.is_some_and(|sort| sort.column == Column::Status) {
+            self.table_rows = None;
+        }
+        if let Err(e) = self.saved_state.save() {
+            self.show_popup("Estado", format!("Erro ao salvar estado: {}", e));
+            return false;
+        }
+        true
+    }
+    
+    fn open_forward_in_browser(&mut self, host: &SshHost) {
+        let web = host.local_forwards.iter()
+            .filter_map(|spec| ForwardSpec::parse(spec))
+            .find_map(|forward| forward.url().map(|url| (forward, url)));
+        let Some((forward, url)) = web else {
+            self.show_popup("Navegador", format!("{} não possui LocalForward para uma porta HTTP(S)", host.name));
+            return;
+        };
+        
+        let host_name = effective::host_alias(host).to_string();
+        let index = match self.tunnels.ensure(&host_name, &forward) {
+            Ok(index) => index,
+            Err(e) => {
+                self.show_popup("Navegador", format!("Erro ao iniciar túnel: {}", e));
+                return;
+            }
+        };
+        // O navegador abre em `poll_browser_wait`, quando a porta responder
+        self.browser_wait = Some((index, url, Instant::now() + Duration::from_secs(10)));
+    }
+
+    fn poll_browser_wait(&mut self) {
+        let Some((index, url, deadline)) = self.browser_wait.clone() else {
+            return;
+        };
+        let ready = match self.tunnels.readiness(index) {
+            None if Instant::now() < deadline => return,
+            ready => ready.unwrap_or(false),
+        };
+        self.browser_wait = None;
+        if !ready {
+            let reason = match self.tunnels.tunnels.get(index).map(|t| &t.status) {
+                Some(TunnelStatus::Exited(reason)) => reason.clone(),
+                _ => "tempo esgotado".to_string(),
+            };
+            self.background_popup("Navegador", format!("Túnel para {} não ficou disponível: {}", url, reason));
+            return;
+        }
+        if let Err(e) = launch::open_url(&url) {
+            self.background_popup("Navegador", format!("Erro ao abrir {}: {}", url, e));
+        }
+    }
+    
+    fn selected_raw_block(&mut self) -> Vec<String> {
+        let Some(index) = self.list_state.selected().filter(|&i| i < self.hosts.len() && !self.hosts[i].is_separator) else {
+            return vec!["No host selected".to_string()];
+        };
+        if let Some((cached_index, lines)) = &self.raw_cache {
+            if *cached_index == index {
+                return lines.clone();
+            }
+        }
+        
+        let host = &self.hosts[index];
+        let config_path = self.host_config_path(host);
+        let lines = match SshConfig::read_host_block(&config_path, &host.name) {
+            Ok(lines) => lines,
+            Err(e) => vec![format!("Erro ao ler {}: {}", config_path.display(), e)],
+        };
+        self.raw_cache = Some((index, lines.clone()));
+        lines
+    }
+    
+    fn open_prompt(&mut self, title: &str, action: PromptAction, initial: String) {
+        self.previous_state = self.state.clone();
+        self.prompt_title = title.to_string();
+        self.prompt_action = Some(action);
+        self.prompt_input = initial;
+        self.state = AppState::Prompt;
+    }
+    
+    fn submit_prompt(&mut self, action: PromptAction, input: String) {
+        match action {
+            PromptAction::SaveSearch(query) => {
+                if input.is_empty() {
+                    return;
+                }
+                // Mesmo nome substitui a busca salva antes
+                match self.app_config.saved_searches.iter_mut().find(|g| g.name == input) {
+                    Some(group) => group.query = query,
+                    None => self.app_config.saved_searches.push(SavedSearch { name: input.clone(), query }),
+                }
+                if let Err(e) = self.app_config.save() {
+                    self.show_popup("Grupos", format!("Erro ao salvar a configuração: {}", e));
+                }
+            }
+            PromptAction::RenameInclude(index) => {
+                let message = match self.rename_include(index, &input) {
+                    Ok(message) => message,
+                    Err(e) => format!("Erro ao renomear: {}", e),
+                };
+                self.show_popup("Includes", message);
+            }
+            PromptAction::NewFolder => {
+                let message = match self.create_include_folder(&input) {
+                    Ok(message) => message,
+                    Err(e) => format!("Erro ao criar pasta: {}", e),
+                };
+                self.show_popup("Includes", message);
+            }
+            PromptAction::AdHocForward(index) => {
+                let Some(host) = self.hosts.get(index) else {
+                    return;
+                };
+                let host_name = effective::host_alias(host).to_string();
+                let message = match ForwardSpec::parse_flexible(&input) {
+                    None => format!("Encaminhamento inválido: '{}'\nUse: 8080 host:80", input),
+                    Some(forward) => match self.tunnels.start(&host_name, &forward) {
+                        Ok(_) => format!("Túnel iniciado: localhost:{} → {}:{} via {}", forward.local, forward.remote_host, forward.remote_port, host_name),
+                        Err(e) => format!("Erro ao iniciar túnel: {}", e),
+                    },
+                };
+                self.show_popup("Túneis", message);
+            }
+            PromptAction::ExportReachability => {
+                let message = match self.export_reachability(&input) {
+                    Ok(count) => format!("{} resultados exportados para {}", count, input),
+                    Err(e) => format!("Erro ao exportar: {}", e),
+                };
+                self.show_popup("Exportar", message);
+            }
+            PromptAction::ExportAudit => {
+                let message = match self.export_audit(&input) {
+                    Ok(count) => format!("Achados de {} hosts exportados para {}", count, input),
+                    Err(e) => format!("Erro ao exportar: {}", e),
+                };
+                self.show_popup("Exportar", message);
+            }
+            PromptAction::ExportAliases => {
+                let entries: Vec<(String, Vec<String>)> = (0..self.hosts.len())
+                    .filter(|&i| !self.hosts[i].is_separator && !self.hosts[i].is_pattern)
+                    .map(|i| self.host_shell_command(i))
+                    .collect();
+                let message = match expand_home(&input).and_then(|path| export::write_aliases(&path, &entries)) {
+                    Ok(()) => format!("{} aliases gravados em {}\nUse: source {}", entries.len(), input, input),
+                    Err(e) => format!("Erro ao exportar: {}", e),
+                };
+                self.show_popup("Exportar", message);
+            }
+            PromptAction::CheckCommand(index) => {
+                let Some(host) = self.hosts.get(index) else {
+                    return;
+                };
+                let name = host.name.clone();
+                let command = if input.is_empty() { None } else { Some(input) };
+                if let Err(e) = self.metadata.update(&name, |meta| meta.check_command = command) {
+                    self.show_popup("Metadados", format!("Erro ao salvar metadados: {}", e));
+                }
+            }
+            PromptAction::Console(index) => {
+                let Some(host) = self.hosts.get(index) else {
+                    return;
+                };
+                let name = host.name.clone();
+                let command = Some(input.trim().to_string()).filter(|c| !c.is_empty());
+                if let Err(e) = self.metadata.update(&name, |meta| meta.console = command) {
+                    self.show_popup("Metadados", format!("Erro ao salvar metadados: {}", e));
+                }
+            }
+            PromptAction::Desktop(index) => {
+                let Some(host) = self.hosts.get(index).cloned() else {
+                    return;
+                };
+                if let Err(e) = self.open_desktop(&host, input.trim()) {
+                    self.show_popup("Acesso gráfico", format!("Erro: {}", e));
+                }
+            }
+            PromptAction::SyncTo(index) => {
+                let Some(host) = self.hosts.get(index) else {
+                    return;
+                };
+                let name = host.name.clone();
+                let selection: Vec<String> = input.split_whitespace().map(str::to_string).collect();
+                if let Err(e) = self.review_sync_to_host(&name, &selection) {
+                    self.show_popup("Sincronizar", format!("Erro ao preparar a sincronização: {}", e));
+                }
+            }
+            PromptAction::RunCommand(index) => {
+                let Some(host) = self.hosts.get(index) else {
+                    return;
+                };
+                let command = input.trim().to_string();
+                if command.is_empty() {
+                    return;
+                }
+                let name = host.name.clone();
+                if let Err(e) = self.suspend_tui(|| ConnectivityTest::run_remote(&name, &command)) {
+                    self.show_popup("Comando remoto", format!("Erro ao executar o comando: {}", e));
+                }
+            }
+            PromptAction::ConnectWithOptions(index) => {
+                let Some(host) = self.hosts.get(index).cloned() else {
+                    return;
+                };
+                self.last_connect_args = input.trim().to_string();
+                let result = ssh_config::split_args(&input)
+                    .ok_or_else(|| "aspas sem fechar nas opções".into())
+                    .and_then(|args| self.connect_ssh_with(&host, &args));
+                if let Err(e) = result {
+                    self.show_popup("Conexão SSH", format!("Erro na conexão SSH: {}", e));
+                }
+            }
+            PromptAction::RemoteEdit(index) => {
+                let Some(host) = self.hosts.get(index) else {
+                    return;
+                };
+                let name = host.name.clone();
+                let alias = effective::host_alias(host).to_string();
+                let dir = input.trim().to_string();
+                if let Err(e) = self.open_remote_editor(&alias, &dir) {
+                    self.show_popup("Editor remoto", format!("Erro ao abrir o editor: {}", e));
+                    return;
+                }
+                let remote_dir = (!dir.is_empty()).then_some(dir);
+                if let Err(e) = self.metadata.update(&name, |meta| meta.remote_dir = remote_dir) {
+                    self.show_popup("Metadados", format!("Erro ao salvar metadados: {}", e));
+                }
+            }
+            PromptAction::Notes(index) => {
+                let Some(host) = self.hosts.get(index) else {
+                    return;
+                };
+                let name = host.name.clone();
+                let notes = if input.is_empty() { None } else { Some(input) };
+                if let Err(e) = self.metadata.update(&name, |meta| meta.notes = notes) {
+                    self.show_popup("Metadados", format!("Erro ao salvar metadados: {}", e));
+                }
+            }
+            PromptAction::Tags(index) => {
+                let Some(host) = self.hosts.get(index) else {
+                    return;
+                };
+                let name = host.name.clone();
+                let tags = metadata::parse_tags(&input);
+                if let Err(e) = self.metadata.update(&name, |meta| meta.tags = tags) {
+                    self.show_popup("Metadados", format!("Erro ao salvar metadados: {}", e));
+                }
+                // `tag:` na busca enxerga as tags novas
+                self.refresh_haystack();
+            }
+            PromptAction::Mac(index) => {
+                let Some(host) = self.hosts.get(index) else {
+                    return;
+                };
+                let name = host.name.clone();
+                let mac = match metadata::normalize_mac(&input) {
+                    Some(mac) => Some(mac),
+                    None if input.is_empty() => None,
+                    None => {
+                        self.show_popup("Metadados", format!("Endereço MAC inválido: '{}'", input));
+                        return;
+                    }
+                };
+                if let Err(e) = self.metadata.update(&name, |meta| meta.mac = mac) {
+                    self.show_popup("Metadados", format!("Erro ao salvar metadados: {}", e));
+                }
+            }
+        }
+    }
+    
+    fn render_prompt(&mut self, f: &mut Frame) {
+        use ratatui::widgets::Clear;
+        
+        let area = f.size();
+        let width = 60.min(area.width.saturating_sub(4));
+        let prompt_area = ratatui::layout::Rect {
+            x: (area.width - width) / 2,
+            y: area.height.saturating_sub(3) / 2,
+            width,
+            height: 3.min(area.height),
+        };
+        
+        f.render_widget(Clear, prompt_area);
+        let prompt = Paragraph::new(format!("{}_", self.prompt_input))
+            .block(Block::default().borders(Borders::ALL).title(format!("{} (Enter: OK | Esc: Cancel)", self.prompt_title)))
+            .style(Style::default().fg(Color::Yellow));
+        f.render_widget(prompt, prompt_area);
+    }
+    
+    fn show_popup(&mut self, title: &str, message: String) {
+        if self.state != AppState::Popup {
+            self.previous_state = self.state.clone();
+        }
+        self.popup_title = title.to_string();
+        self.popup_message = message;
+        self.state = AppState::Popup;
+    }
+    
+    // Popup de um resultado em segundo plano: só por cima da lista; em outra
+    // tela fica guardado até a volta, para não trocar o `previous_state` dela
+    fn background_popup(&mut self, title: &str, message: String) {
+        if self.state == AppState::List {
+            self.show_popup(title, message);
+        } else {
+            self.deferred_popups.push((title.to_string(), message));
+        }
+    }
+
+    // Opções efetivas do host, como pares (chave minúscula, valor)
+    fn effective_pairs(&self, index: usize) -> Vec<(String, String)> {
+        let (options, _) = effective::resolve(&self.hosts, index);
+        options.into_iter().map(|o| (o.key, o.value)).collect()
+    }
+
+    // Lê a área de transferência e abre o formulário de novo host já preenchido;
+    // a pasta fica em branco para o usuário escolher onde importar
+    fn import_from_clipboard(&mut self) {
+        let imported = match launch::read_clipboard().and_then(|text| share::parse_import(&text)) {
+            Ok(imported) => imported,
+            Err(e) => {
+                self.show_popup("Importar", format!("Nada para importar: {}", e));
+                return;
+            }
+        };
+        let mut form = HostForm { host: imported.alias.clone(), ..HostForm::default() };
+        for (key, value) in &imported.directives {
+            let lower = key.to_lowercase();
+            let toggle = (0..FIELD_COUNT).find(|&i| HostForm::toggle_key(i) == Some(lower.as_str()));
+            let field = HostForm::field_index(&lower).or(toggle).filter(|&i| form.get_field(i).is_empty());
+            let value = if toggle.is_some() { value.to_lowercase() } else { value.clone() };
+            match field {
+                _ if lower == "localforward" => form.add_forward_spec(&value),
+                // Valores que o campo alternável não aceita seguem como opção extra
+                Some(i) if HostForm::toggle_options(i).is_none_or(|options| options.contains(&value.as_str())) => form.set_field(i, value),
+                _ => form.extra_options.push((key.clone(), value)),
+            }
+        }
+        // Nome que já existe: o seletor de conflitos decide entre o atual, o importado ou a mescla
+        let existing = self.hosts.iter().find(|h| !h.is_separator && h.name == imported.alias).and_then(|h| h.source_file.clone());
+        if let Some(path) = existing {
+            let mut lines = vec![format!("Host {}", imported.alias)];
+            lines.extend(imported.directives.iter().map(|(key, value)| format!("    {} {}", key, ssh_config::quote(key, value))));
+            let block = HostBlock { name: imported.alias.clone(), lines };
+            let merge = SshConfig::read_text(&path).ok().and_then(|content| FileMerge::import(path, &content, block));
+            match merge {
+                Some(merge) if merge.conflicts.is_empty() => {
+                    self.show_popup("Importar", format!("O host {} já existe com as mesmas opções ({} importado)", imported.alias, imported.source));
+                    return;
+                }
+                Some(merge) => {
+                    self.merges = vec![merge];
+                    self.import_merge = true;
+                    self.conflict_state.select(Some(0));
+                    self.state = AppState::Conflicts;
+                    return;
+                }
+                None => {}
+            }
+        }
+        self.form = form;
+        self.editing_host_index = None;
+        self.state = AppState::Form;
+        if self.hosts.iter().any(|h| !h.is_separator && h.name == imported.alias) {
+            self.show_popup("Importar", format!("Já existe um host {} ({} importado); troque o nome antes de salvar", imported.alias, imported.source));
+        }
+    }
+
+    // Copia o bloco do host (ou as opções efetivas) como snippet cercado
+    fn copy_snippet(&mut self, index: usize, effective: bool) {
+        let block = if effective {
+            let alias = effective::host_alias(&self.hosts[index]).to_string();
+            share::effective_block(&alias, &self.effective_pairs(index))
+        } else {
+            self.selected_raw_block()
+        };
+        let snippet = share::snippet(&block);
+        let message = match launch::copy_to_clipboard(&snippet) {
+            Ok(via) => format!("Bloco copiado ({} linhas, via {})", block.len(), via),
+            Err(e) => format!("Erro ao copiar: {}", e),
+        };
+        self.show_popup("Compartilhar", message);
+    }
+
+    fn render_qr(&mut self, f: &mut Frame) {
+        use ratatui::widgets::Clear;
+
+        let Some(host) = self.hosts.get(self.qr_host) else {
+            return;
+        };
+        let alias = effective::host_alias(host).to_string();
+        let options = self.effective_pairs(self.qr_host);
+        let data = if self.qr_json { share::json_blob(&alias, &options) } else { share::ssh_uri(&alias, &options) };
+
+        let area = f.size();
+        // O QR e o link levam o HostName e as chaves: na tela compartilhada ficam ocultos
+        let mut lines: Vec<Line> = match share::qr_lines(&data) {
+            _ if self.privacy_mode => vec![Line::from("Modo privacidade: o QR e o link ficam ocultos (H mostra)")],
+            Ok(rows) if rows.first().is_some_and(|r| r.chars().count() as u16 + 2 <= area.width) && rows.len() as u16 + 4 <= area.height => {
+                rows.into_iter().map(|r| Line::from(Span::styled(r, Style::default().fg(Color::White).bg(Color::Black)))).collect()
+            }
+            Ok(_) => vec![Line::from("Terminal pequeno demais para o QR; aumente a janela")],
+            Err(e) => vec![Line::from(format!("Erro ao gerar o QR: {}", e))],
+        };
+        if !self.privacy_mode {
+            lines.push(Line::from(Span::styled(data, Style::default().fg(Color::Gray))));
+        }
+
+        let mode = if self.qr_json { "JSON" } else { "ssh://" };
+        let title = format!("Share {} as {} (j: toggle | H: privacy | Esc: close)", alias, mode);
+        let content_width = lines.iter().map(|l| l.width()).max().unwrap_or(0).max(title.chars().count());
+        let width = (content_width as u16).saturating_add(2).min(area.width);
+        let height = (lines.len() as u16 + 2).min(area.height);
+        let popup_area = ratatui::layout::Rect {
+            x: (area.width - width) / 2,
+            y: (area.height - height) / 2,
+            width,
+            height,
+        };
+        let popup = Paragraph::new(lines)
+            .alignment(Alignment::Center)
+            .wrap(ratatui::widgets::Wrap { trim: false })
+            .block(Block::default().borders(Borders::ALL).title(title));
+        f.render_widget(Clear, popup_area);
+        f.render_widget(popup, popup_area);
+    }
+
+    // Seção da ajuda da tela atual, e se nela `?` é texto digitado (só o F1 abre)
+    fn help_section(&self) -> Option<(usize, bool)> {
+        let section = match self.state {
+            AppState::List if self.table_view => (help::TABLE, false),
+            AppState::List => (help::LIST, false),
+            AppState::QuickActions => (help::QUICK_ACTIONS, false),
+            AppState::Search => (help::SEARCH, true),
+            AppState::Form | AppState::Edit | AppState::Confirm | AppState::ConfirmEdit => (help::FORM, true),
+            AppState::Includes => (help::INCLUDES, false),
+            AppState::Tunnels => (help::TUNNELS, false),
+            AppState::JumpChain => (help::JUMP_CHAIN, false),
+            AppState::Topology => (help::TOPOLOGY, false),
+            AppState::History => (help::HISTORY, false),
+            AppState::Trash => (help::TRASH, false),
+            AppState::Audit => (help::AUDIT, false),
+            AppState::KeyMap => (help::KEY_MAP, false),
+            AppState::Rotation => (help::ROTATION, false),
+            AppState::SyncDiff => (help::SYNC_DIFF, false),
+            AppState::Conflicts => (help::CONFLICTS, false),
+            AppState::SmartGroups => (help::SMART_GROUPS, false),
+            _ => return None,
+        };
+        Some(section)
+    }
+
+    // Abre com a seção da tela de origem no topo; as demais ficam acessíveis rolando
+    fn open_help(&mut self, section: usize) {
+        self.help_scroll = help::SECTIONS[..section].iter().map(|s| s.keys.len() as u16 + 2).sum();
+        self.previous_state = self.state.clone();
+        self.state = AppState::Help;
+    }
+
+    fn render_help(&mut self, f: &mut Frame) {
+        use ratatui::widgets::Clear;
+
+        let area = f.size();
+        let width = 90.min(area.width.saturating_sub(4));
+        let height = area.height.saturating_sub(2);
+        let help_area = Rect {
+            x: (area.width - width) / 2,
+            y: (area.height - height) / 2,
+            width,
+            height,
+        };
+        let key_width = help::SECTIONS.iter().flat_map(|s| s.keys).map(|(k, _)| k.chars().count()).max().unwrap_or(0);
+        let mut lines = Vec::new();
+        for section in help::SECTIONS {
+            lines.push(Line::from(Span::styled(section.title, Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD))));
+            for (keys, action) in section.keys {
+                lines.push(Line::from(vec![
+                    Span::styled(format!("  {:<width$}  ", keys, width = key_width), Style::default().fg(Color::Cyan)),
+                    Span::raw(*action),
                 ]));
             }
+            lines.push(Line::from(""));
+        }
+        // Não rola além da última linha
+        let max_scroll = (lines.len() as u16).saturating_sub(height.saturating_sub(2));
+        self.help_scroll = self.help_scroll.min(max_scroll);
+
+        f.render_widget(Clear, help_area);
+        let help = Paragraph::new(lines)
+            .scroll((self.help_scroll, 0))
+            .block(Block::default().borders(Borders::ALL).title("Keybindings (↑/↓ PgUp/PgDn: scroll | Esc/?: close)"));
+        f.render_widget(help, help_area);
+    }
+
+    fn render_popup(&mut self, f: &mut Frame) {
+        use ratatui::widgets::{Clear, Paragraph};
+        use ratatui::layout::Alignment;
+        
+        let area = f.size();
+        
+        // Calcular área do popup (centralizado)
+        let popup_width = 60.min(area.width - 4);
+        // Mensagens com várias linhas (ex.: portas verificadas) aumentam o popup
+        let message_lines = self.popup_message.lines().count() as u16;
+        let popup_height = (message_lines + 4).max(5).min(area.height.saturating_sub(1));
+        let x = (area.width - popup_width) / 2;
+        let y = (area.height - popup_height) / 2;
+        
+        let popup_area = ratatui::layout::Rect {
+            x,
+            y,
+            width: popup_width,
+            height: popup_height,
+        };
+        
+        // Limpar área do popup
+        f.render_widget(Clear, popup_area);
+        
+        // Renderizar popup
+        let popup = Paragraph::new(self.popup_message.clone())
+            .block(Block::default().borders(Borders::ALL).title(self.popup_title.clone()))
+            .alignment(Alignment::Center)
+            .wrap(ratatui::widgets::Wrap { trim: true });
+        
+        f.render_widget(popup, popup_area);
+        
+        // Renderizar instrução
+        let help_area = ratatui::layout::Rect {
+            x,
+            y: y + popup_height,
+            width: popup_width,
+            height: 1,
+        };
+        
+        let help = Paragraph::new("Enter/Esc: Fechar")
+            .alignment(Alignment::Center)
+            .style(Style::default().fg(Color::Gray));
+        
+        f.render_widget(help, help_area);
+    }
+    
+    fn connect_ssh(&mut self, host: &SshHost) -> Result<(), Box<dyn std::error::Error>> {
+        self.connect_ssh_with(host, &[])
+    }
+
+    // Argumentos extras (`-vvv`, `-o ProxyJump=...`, `-L ...`) valem só para esta conexão
+    fn connect_ssh_with(&mut self, host: &SshHost, extra_args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+        self.open_session(host, extra_args, self.app_config.record_sessions)
+    }
+
+    // Com `record`, a sessão roda dentro do `asciinema rec`
+    fn open_session(&mut self, host: &SshHost, extra_args: &[String], record: bool) -> Result<(), Box<dyn std::error::Error>> {
+        if !self.is_target(host) {
+            return Err(format!("{} é um padrão, não um host; pattern_actions = true permite conectar", host.name).into());
+        }
+        let argv = ConnectivityTest::session_argv(host, &self.metadata.get(&host.name), extra_args)?;
+        if record {
+            self.suspend_tui(|| ConnectivityTest::run_session(&host.name, &argv, true))?;
+            return self.record_history(&host.name);
+        }
+        // No kitty/WezTerm a sessão abre numa aba e a interface continua aberta
+        let mut tab_error = None;
+        if let Some(terminal) = self.tab_terminal {
+            match terminal.open_tab(&host.name, &argv) {
+                Ok(()) => return self.record_history(&host.name),
+                Err(e) => {
+                    self.tab_terminal = None;
+                    tab_error = Some(format!(
+                        "Não foi possível abrir uma aba no {} ({}); as sessões voltam a abrir neste terminal.\n`terminal_tabs = false` desliga a tentativa.",
+                        terminal.label(), e
+                    ));
+                }
+            }
+        }
+        self.suspend_tui(|| ConnectivityTest::run_session(&host.name, &argv, false))?;
+        if let Some(message) = tab_error {
+            self.show_popup("Abas", message);
+        }
+        self.record_history(&host.name)
+    }
+
+    // Só sessões sem erro entram no histórico; a ordem "recentes" e a frecência
+    // da busca mudam com elas
+    fn record_history(&mut self, host: &str) -> Result<(), Box<dyn std::error::Error>> {
+        if let Some(host_db) = &self.host_db {
+            host_db.record_connection(host)?;
+        }
+        self.metadata.record_connection(host)?;
+        self.refresh_haystack();
+        if self.saved_state.sort_recent {
+            self.list_rows = None;
+        }
+        Ok(())
+    }
+
+    fn run_ssh(&mut self, host_name: &str, extra_args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+        self.suspend_tui(|| ConnectivityTest::connect_ssh(host_name, extra_args, None))
+    }
+    
+    fn open_in_editor(&mut self, host: &SshHost) -> Result<(), Box<dyn std::error::Error>> {
+        let config_path = self.host_config_path(host);
+        let editor = std::env::var("VISUAL")
+            .or_else(|_| std::env::var("EDITOR"))
+            .unwrap_or_else(|_| "vi".to_string());
+        let mut parts = editor.split_whitespace();
+        let program = parts.next().unwrap_or("vi").to_string();
+        let mut args: Vec<String> = parts.map(|p| p.to_string()).collect();
+        if host.line > 0 {
+            args.push(format!("+{}", host.line));
+        }
+        args.push(config_path.to_string_lossy().to_string());
+        
+        self.suspend_tui(|| {
+            let status = std::process::Command::new(&program).args(&args).status()?;
+            if !status.success() {
+                return Err(format!("{} terminou com código {:?}", program, status.code()).into());
+            }
+            Ok(())
+        })?;
+        
+        // O arquivo pode ter mudado no editor
+        self.reload_hosts()?;
+        let file = config_path.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+        let folder = host.source_dir.clone().unwrap_or_default();
+        self.git_commit(format!("lazysshrs: edit {}/{} in editor", folder, file), vec![config_path]);
+        Ok(())
+    }
+    
+    // Guarda o endereço nos metadados e abre o cliente RDP/VNC configurado
+    fn open_desktop(&mut self, host: &SshHost, value: &str) -> Result<(), Box<dyn std::error::Error>> {
+        let alias = effective::host_alias(host);
+        let hostname = host.hostname.as_deref().unwrap_or(alias);
+        let desktop = launch::Desktop::parse(value, hostname)
+            .ok_or_else(|| format!("endereço inválido: {} (use rdp://host:porta ou vnc://host:porta)", value))?;
+        let value = value.to_string();
+        self.metadata.update(&host.name, |meta| meta.desktop = Some(value))?;
+        let client = if desktop.protocol == "rdp" { &self.app_config.rdp_client } else { &self.app_config.vnc_client };
+        launch::spawn_detached(&desktop.command(client)?)
+    }
+
+    fn open_remote_editor(&mut self, alias: &str, dir: &str) -> Result<(), Box<dyn std::error::Error>> {
+        let editor = launch::remote_editor(&self.app_config.remote_editor, alias, dir);
+        if editor.program.is_empty() {
+            return Err("remote_editor está vazio".into());
+        }
+        if editor.graphical {
+            std::process::Command::new(&editor.program)
+                .args(&editor.args)
+                .stdin(std::process::Stdio::null())
+                .stdout(std::process::Stdio::null())
+                .stderr(std::process::Stdio::null())
+                .spawn()
+                .map_err(|e| format!("{}: {}", editor.program, e))?;
+            return Ok(());
+        }
+        self.suspend_tui(|| {
+            let status = std::process::Command::new(&editor.program).args(&editor.args).status()?;
+            if !status.success() {
+                return Err(format!("{} terminou com código {:?}", editor.program, status.code()).into());
+            }
+            Ok(())
+        })
+    }
+    
+    fn suspend_tui<F>(&mut self, run: F) -> Result<(), Box<dyn std::error::Error>>
+    where
+        F: FnOnce() -> Result<(), Box<dyn std::error::Error>>,
+    {
+        use crossterm::{
+            execute,
+            terminal::{disable_raw_mode, enable_raw_mode, LeaveAlternateScreen, EnterAlternateScreen},
+        };
+        use std::io;
+        
+        // Sair completamente do modo TUI
+        disable_raw_mode()?;
+        execute!(io::stdout(), LeaveAlternateScreen)?;
+        
+        let result = run();
+        
+        // Restaurar modo TUI
+        execute!(io::stdout(), EnterAlternateScreen)?;
+        enable_raw_mode()?;
+        self.needs_clear = true;
+        
+        result
+    }
+    
+    // Nomes e bônus de uso para a busca; refeito quando os hosts ou o histórico mudam
+    fn refresh_haystack(&self) {
+        self.search.set_haystack(search::haystack(&self.hosts, &self.metadata, self.app_config.search_frecency_weight));
+    }
+
+    fn reload_hosts(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+        let selected_name = self.list_state.selected()
+            .and_then(|i| self.hosts.get(i))
+            .map(|h| h.name.clone());
+        
+        let mut config = SshConfig::load_from_workdir(&self.app_config.get_workdir())?;
+        folder::arrange_sections(&mut config, &self.app_config.section_order);
+        self.hosts = config.hosts;
+        self.table_rows = None;
+        self.list_rows = None;
+        self.files = config.files;
+        self.key_problems = keys::check_identities(&self.hosts);
+        self.agent_forwarding = audit::agent_forwarding(&self.hosts, &self.app_config.trusted_hosts);
+        self.index = HostIndex::build(&self.hosts);
+        self.refresh_haystack();
+        self.raw_cache = None;
+        self.shadow_cache = None;
+        self.group_counts = None;
+        
+        let index = selected_name
+            .and_then(|name| self.index.position(&name))
+            .or_else(|| self.index.first_host());
+        self.list_state.select(index);
+        Ok(())
+    }
 
-            if let Some(user) = &host.user {
-                lines.push(Line::from(vec![
-                    Span::styled("User: ", Style::default().fg(Color::Yellow)),
-                    Span::raw(user),
-                ]));
+    // Commita os arquivos de configuração alterados, se o workdir for um repositório
+    fn git_commit(&mut self, message: String, paths: Vec<std::path::PathBuf>) {
+        let workdir = self.app_config.get_workdir();
+        if self.app_config.git_autocommit && self.git_status.is_some() {
+            if let Err(e) = git::commit(&workdir, &paths, &message) {
+                self.show_popup("Git", format!("Erro ao commitar: {}", e));
             }
+        }
+        self.git_status = git::status(&workdir);
+    }
 
-            if let Some(port) = host.port {
-                lines.push(Line::from(vec![
-                    Span::styled("Port: ", Style::default().fg(Color::Yellow)),
-                    Span::raw(port.to_string()),
-                ]));
-            }
+    // Busca as cópias do outro host e abre o diff antes de enviar
+    fn review_sync_to_host(&mut self, host: &str, selection: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+        let workdir = self.app_config.get_workdir();
+        let files = sync::collect_files(&workdir, selection, &self.app_config.sync_excludes)?;
+        let remote_dir = self.app_config.sync_remote_dir.clone();
+        let mut remote = Vec::new();
+        self.suspend_tui(|| {
+            remote = sync::fetch_remote(host, &remote_dir, &files)?;
+            Ok(())
+        })?;
+        let diffs = files.into_iter().zip(remote)
+            .map(|(path, remote)| FileDiff {
+                local: std::fs::read_to_string(workdir.join(&path)).ok(),
+                path,
+                remote,
+                excluded: false,
+            })
+            .collect();
+        self.open_sync_diff(SyncTarget::Host(host.to_string()), diffs);
+        Ok(())
+    }
 
-            if let Some(identity_file) = &host.identity_file {
-                lines.push(Line::from(vec![
-                    Span::styled("Identity File: ", Style::default().fg(Color::Yellow)),
-                    Span::raw(identity_file),
-                ]));
-            }
+    fn review_git_push(&mut self) {
+        if self.git_status.is_none() {
+            self.show_popup("Git", "O workdir não é um repositório git".to_string());
+            return;
+        }
+        let workdir = self.app_config.get_workdir();
+        let mut outgoing = Vec::new();
+        if let Err(e) = self.suspend_tui(|| {
+            outgoing = git::outgoing(&workdir)?;
+            Ok(())
+        }) {
+            self.show_popup("Git", format!("Erro ao comparar com o remoto: {}", e));
+            return;
+        }
+        if outgoing.is_empty() {
+            self.show_popup("Git", "Nada a enviar: o remoto já tem todos os commits".to_string());
+            return;
+        }
+        self.open_sync_diff(SyncTarget::GitPush, outgoing);
+    }
 
-            for (key, value) in &host.other_options {
-                lines.push(Line::from(vec![
-                    Span::styled(format!("{}: ", key), Style::default().fg(Color::Yellow)),
-                    Span::raw(value),
-                ]));
+    fn open_sync_diff(&mut self, target: SyncTarget, files: Vec<FileDiff>) {
+        self.sync_target = Some(target);
+        self.sync_files = files;
+        // Começa pelo primeiro arquivo que muda de fato
+        let first = self.sync_files.iter().position(|f| f.status() != FileStatus::Unchanged).unwrap_or(0);
+        self.sync_state.select(if self.sync_files.is_empty() { None } else { Some(first) });
+        self.diff_scroll = 0;
+        self.state = AppState::SyncDiff;
+    }
+
+    fn confirm_sync(&mut self) {
+        self.state = AppState::List;
+        match self.sync_target.take() {
+            Some(SyncTarget::Host(host)) => {
+                let files: Vec<_> = self.sync_files.iter().filter(|f| !f.excluded).map(|f| f.path.clone()).collect();
+                let message = match self.sync_to_host(&host, &files) {
+                    Ok(message) => message,
+                    Err(e) => format!("Erro ao sincronizar: {}", e),
+                };
+                self.show_popup("Sincronizar", message);
             }
+            Some(SyncTarget::GitPush) => self.git_push(),
+            None => {}
+        }
+        self.sync_files.clear();
+    }
 
-            Paragraph::new(lines)
-        } else {
-            Paragraph::new("No host selected")
+    // Envia os arquivos para o ~/.ssh de outro host, sem as chaves privadas
+    fn sync_to_host(&mut self, host: &str, files: &[std::path::PathBuf]) -> Result<String, Box<dyn std::error::Error>> {
+        let workdir = self.app_config.get_workdir();
+        let remote_dir = self.app_config.sync_remote_dir.clone();
+        let mut method = "";
+        self.suspend_tui(|| {
+            method = sync::push(&workdir, files, host, &remote_dir)?;
+            Ok(())
+        })?;
+        Ok(format!("{} arquivo(s) enviados para {}:{} via {}", files.len(), host, remote_dir, method))
+    }
+
+    fn git_pull(&mut self) {
+        if self.git_status.is_none() {
+            self.show_popup("Git", "O workdir não é um repositório git".to_string());
+            return;
+        }
+        let workdir = self.app_config.get_workdir();
+        let mut outcome = None;
+        let result = self.suspend_tui(|| {
+            outcome = Some(git::pull(&workdir)?);
+            Ok(())
+        });
+        let message = match (result, outcome) {
+            (Err(e), _) => format!("Erro no pull: {}", e),
+            (Ok(()), Some(PullOutcome::UpToDate)) => "Já está atualizado".to_string(),
+            (Ok(()), Some(PullOutcome::Updated)) => match self.reload_hosts() {
+                Ok(()) => "Configuração atualizada a partir do remoto".to_string(),
+                Err(e) => format!("Pull concluído, mas a configuração não pôde ser lida: {}", e),
+            },
+            (Ok(()), Some(PullOutcome::Conflict(files))) => match self.open_conflicts(&files) {
+                Ok(None) => return,
+                Ok(Some(message)) => message,
+                Err(e) => {
+                    let _ = git::abort_merge(&workdir);
+                    format!(
+                        "Conflito com o remoto; o merge foi desfeito e nada mudou:\n{}\n\n{}\nResolva no terminal (git pull) e abra o lazysshrs de novo",
+                        files.join("\n"),
+                        e,
+                    )
+                }
+            },
+            (Ok(()), None) => return,
         };
+        self.git_status = git::status(&workdir);
+        self.show_popup("Git", message);
+    }
 
-        let details_block = details.block(Block::default().borders(Borders::ALL).title("Host Details"));
-        f.render_widget(details_block, chunks[1]);
+    // Monta o seletor por host; conflitos só de linha (hosts diferentes) se resolvem sozinhos.
+    // Devolve a mensagem final quando não há nada para o usuário escolher
+    fn open_conflicts(&mut self, files: &[String]) -> Result<Option<String>, Box<dyn std::error::Error>> {
+        let workdir = self.app_config.get_workdir();
+        let mut merges = Vec::new();
+        for file in files {
+            if Path::new(file).file_name().is_none_or(|n| n != "config") {
+                return Err(format!("{} não é um arquivo de hosts", file).into());
+            }
+            let local = git::stage(&workdir, 2, file).ok_or_else(|| format!("{} foi removido localmente", file))?;
+            let remote = git::stage(&workdir, 3, file).ok_or_else(|| format!("{} foi removido no remoto", file))?;
+            let base = git::stage(&workdir, 1, file);
+            merges.push(FileMerge::new(workdir.join(file), base.as_deref(), &local, &remote));
+        }
+        self.merges = merges;
+        if self.merges.iter().all(|m| m.conflicts.is_empty()) {
+            self.apply_conflicts()?;
+            return Ok(Some("Hosts diferentes nos dois lados: o merge foi resolvido automaticamente".to_string()));
+        }
+        self.conflict_state.select(Some(0));
+        self.state = AppState::Conflicts;
+        Ok(None)
     }
 
-    fn render_form(&mut self, f: &mut Frame, title: &str) {
-        use ratatui::widgets::{Clear, Paragraph};
-        use ratatui::layout::Alignment;
-        
-        let area = f.size();
-        f.render_widget(Clear, area);
-        
-        let form_area = Layout::default()
-            .direction(Direction::Vertical)
-            .constraints([Constraint::Length(10), Constraint::Min(0)])
-            .split(area)[0];
-        
-        let mut lines = vec![];
-        let field_names = HostForm::field_names();
-        
-        for (i, name) in field_names.iter().enumerate() {
-            let value = self.form.get_field(i);
-            let style = if i == self.form.current_field {
-                Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD)
-            } else {
-                Style::default()
-            };
-            
-            lines.push(Line::from(vec![
-                Span::styled(format!("{}: ", name), style),
-                Span::styled(value, style),
-            ]));
+    // Conflito na posição `index` da lista (achatada entre os arquivos)
+    fn conflict_at(&self, index: usize) -> Option<(usize, usize)> {
+        let mut offset = 0;
+        for (file, merge) in self.merges.iter().enumerate() {
+            if index < offset + merge.conflicts.len() {
+                return Some((file, index - offset));
+            }
+            offset += merge.conflicts.len();
         }
-        
-        lines.push(Line::from(""));
-        lines.push(Line::from("Tab/Shift+Tab: Navigate | Enter: OK | Esc: Cancel"));
-        
-        let form = Paragraph::new(lines)
-            .block(Block::default().borders(Borders::ALL).title(title))
-            .alignment(Alignment::Left);
-        
-        f.render_widget(form, form_area);
+        None
     }
-    
-    fn render_confirm(&mut self, f: &mut Frame, title: &str) {
-        use ratatui::widgets::{Clear, Paragraph};
-        use ratatui::layout::Alignment;
-        
-        let area = f.size();
-        f.render_widget(Clear, area);
-        
-        let confirm_area = Layout::default()
-            .direction(Direction::Vertical)
-            .constraints([Constraint::Length(12), Constraint::Min(0)])
-            .split(area)[0];
-        
-        let mut lines = vec![Line::from("Confirm host configuration:"), Line::from("")];
-        let field_names = HostForm::field_names();
-        
-        for (i, name) in field_names.iter().enumerate() {
-            let value = self.form.get_field(i);
-            if !value.is_empty() {
-                lines.push(Line::from(vec![
-                    Span::styled(format!("{}: ", name), Style::default().fg(Color::Yellow)),
-                    Span::raw(value),
-                ]));
+
+    // Navegação e escolha por host, iguais no pull e na importação
+    fn handle_conflict_choice(&mut self, code: KeyCode) {
+        match code {
+            KeyCode::Down | KeyCode::Up => {
+                let len: usize = self.merges.iter().map(|m| m.conflicts.len()).sum();
+                if len > 0 {
+                    let i = self.conflict_state.selected().unwrap_or(0);
+                    let i = if code == KeyCode::Down { (i + 1) % len } else { (i + len - 1) % len };
+                    self.conflict_state.select(Some(i));
+                }
             }
+            KeyCode::Char('l') => self.set_resolution(|_| Resolution::Local),
+            KeyCode::Char('r') => self.set_resolution(|_| Resolution::Remote),
+            KeyCode::Char('m') => self.set_resolution(|_| Resolution::Merge),
+            KeyCode::Char(' ') | KeyCode::Right => self.set_resolution(|r| r.next()),
+            KeyCode::Left => self.set_resolution(|r| r.next().next()),
+            _ => {}
         }
-        
-        lines.push(Line::from(""));
-        lines.push(Line::from("Enter: Save | Esc: Back to form"));
-        
-        let confirm = Paragraph::new(lines)
-            .block(Block::default().borders(Borders::ALL).title(title))
-            .alignment(Alignment::Left);
-        
-        f.render_widget(confirm, confirm_area);
     }
-    
-    fn save_host(&mut self) -> Result<(), Box<dyn std::error::Error>> {
-        use std::fs::{self, OpenOptions};
-        use std::io::Write;
-        
-        let config_path = self.app_config.get_workdir().join(&self.form.folder).join("config");
-        let is_new_file = !config_path.exists();
-        
-        // Criar diretório se não existir
-        if let Some(parent) = config_path.parent() {
-            fs::create_dir_all(parent)?;
+
+    fn set_resolution(&mut self, choose: impl FnOnce(Resolution) -> Resolution) {
+        if let Some((file, i)) = self.conflict_state.selected().and_then(|i| self.conflict_at(i)) {
+            let conflict = &mut self.merges[file].conflicts[i];
+            conflict.resolution = choose(conflict.resolution);
         }
-        
-        // Abrir arquivo para escrita
-        let mut file = OpenOptions::new()
-            .create(true)
-            .append(true)
-            .open(&config_path)?;
-        
-        // Escrever configuração do host
-        if config_path.metadata()?.len() > 0 {
-            writeln!(file)?; // Linha em branco se arquivo não estiver vazio
+    }
+
+    fn apply_conflicts(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+        let workdir = self.app_config.get_workdir();
+        let mut paths = Vec::new();
+        for merge in &self.merges {
+            backup::write(&merge.path, merge.resolve())?;
+            paths.push(merge.path.to_string_lossy().to_string());
         }
-        
-        writeln!(file, "Host {}", self.form.host)?;
-        writeln!(file, "    Hostname {}", self.form.hostname)?;
-        writeln!(file, "    User {}", self.form.user)?;
-        
-        if !self.form.port.is_empty() {
-            writeln!(file, "    Port {}", self.form.port)?;
+        git::conclude_merge(&workdir, &paths)?;
+        self.merges.clear();
+        self.reload_hosts()
+    }
+
+    // Grava a escolha do seletor de importação no arquivo do host existente
+    // (ou no banco, se o arquivo for gerado) e commita
+    fn apply_import(&mut self) -> Result<String, Box<dyn std::error::Error>> {
+        self.import_merge = false;
+        let merge = self.merges.pop().ok_or("nada para importar")?;
+        let conflict = merge.conflicts.first().ok_or("nada para importar")?;
+        let (name, resolution) = (conflict.host.clone(), conflict.resolution);
+        let generated = db::is_generated(&merge.path);
+        backup::write(&merge.path, merge.resolve())?;
+        self.reload_hosts()?;
+        if let (Some(host_db), true) = (&self.host_db, generated) {
+            let host = self.hosts.iter()
+                .find(|h| h.name == name && h.source_file.as_ref() == Some(&merge.path))
+                .ok_or_else(|| format!("Host {} não encontrado em {}", name, merge.path.display()))?;
+            host_db.upsert(&HostRecord::from_host(host, host.source_dir.as_deref().unwrap_or_default()))?;
+            self.regenerate_from_db()?;
         }
-        if !self.form.identity_file.is_empty() {
-            writeln!(file, "    IdentityFile {}", self.form.identity_file)?;
+        self.raw_cache = None;
+        self.git_commit(format!("lazysshrs: import host {}", name), vec![merge.path.clone()]);
+        Ok(match resolution {
+            Resolution::Local => format!("{} ficou como estava", name),
+            Resolution::Remote => format!("{} substituído pelo host importado", name),
+            Resolution::Merge => format!("Campos importados mesclados em {}", name),
+        })
+    }
+
+    fn git_push(&mut self) {
+        if self.git_status.is_none() {
+            self.show_popup("Git", "O workdir não é um repositório git".to_string());
+            return;
         }
-        if !self.form.local_forward.is_empty() {
-            writeln!(file, "    LocalForward {}", self.form.local_forward)?;
+        let workdir = self.app_config.get_workdir();
+        let message = match self.suspend_tui(|| git::push(&workdir)) {
+            Ok(()) => "Alterações enviadas ao remoto".to_string(),
+            Err(e) => format!("Erro no push: {}", e),
+        };
+        self.git_status = git::status(&workdir);
+        self.show_popup("Git", message);
+    }
+
+    fn jump_candidates(&self) -> Vec<usize> {
+        self.hosts.iter().enumerate()
+            .filter(|(_, h)| !h.is_separator && !h.name.contains('*') && !h.name.contains('?'))
+            .map(|(i, _)| i)
+            .collect()
+    }
+    
+    fn save_jump_chain(&mut self) {
+        if self.jump_chain.len() < 2 {
+            self.show_popup("Jump Chain", "Selecione ao menos um salto e o host de destino".to_string());
+            return;
         }
         
-        // Adicionar Include se for arquivo novo
-        if is_new_file {
-            self.add_include_to_main_config(&config_path)?;
-        }
+        let (target, jumps) = self.jump_chain.split_last().unwrap();
+        let target = target.clone();
+        let proxy_jump = jumps.join(",");
         
-        Ok(())
+        let Some(host_index) = self.index.position(&target) else {
+            return;
+        };
+        let config_path = self.host_config_path(&self.hosts[host_index]);
+        
+        let message = match SshConfig::set_host_option(&config_path, &target, "ProxyJump", &proxy_jump) {
+            Ok(()) => {
+                self.hosts[host_index].other_options.set("ProxyJump", &proxy_jump);
+                self.raw_cache = None;
+                self.shadow_cache = None;
+                self.git_commit(format!("lazysshrs: set ProxyJump for {}", target), vec![config_path.clone()]);
+                format!("ProxyJump {} salvo em {}", proxy_jump, target)
+            }
+            Err(e) => format!("Erro ao salvar ProxyJump: {}", e),
+        };
+        self.show_popup("Jump Chain", message);
     }
     
-    fn add_include_to_main_config(&self, new_config_path: &Path) -> Result<(), Box<dyn std::error::Error>> {
-        use std::fs::{self, OpenOptions};
-        use std::io::Write;
+    fn render_jump_chain(&mut self, f: &mut Frame) {
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Length(4), Constraint::Min(0), Constraint::Length(1)])
+            .split(f.size());
         
-        let main_config = self.app_config.get_main_config_path();
+        // Cadeia atual e comando resultante
+        let (chain_text, command_text) = match self.jump_chain.split_last() {
+            Some((target, jumps)) => {
+                let mut args = ConnectivityTest::jump_args(jumps);
+                args.insert(0, "ssh".to_string());
+                args.push(target.clone());
+                (self.jump_chain.join(" → "), args.join(" "))
+            }
+            None => ("(vazia)".to_string(), String::new()),
+        };
         
-        let include_line = format!("Include {}", new_config_path.display());
+        let header = Paragraph::new(vec![
+            Line::from(vec![
+                Span::styled("Chain: ", Style::default().fg(Color::Yellow)),
+                Span::raw(chain_text),
+            ]),
+            Line::from(vec![
+                Span::styled("Command: ", Style::default().fg(Color::Yellow)),
+                Span::raw(command_text),
+            ]),
+        ])
+        .block(Block::default().borders(Borders::ALL).title("Jump Chain Builder"));
+        f.render_widget(header, chunks[0]);
         
-        if main_config.exists() {
-            let content = fs::read_to_string(&main_config)?;
-            if !content.contains(&include_line) {
-                // Reescrever arquivo com Include no início
-                let mut file = OpenOptions::new()
-                    .write(true)
-                    .truncate(true)
-                    .open(&main_config)?;
-                
-                writeln!(file, "{}", include_line)?;
-                if !content.is_empty() {
-                    writeln!(file)?; // Linha em branco
-                    write!(file, "{}", content)?;
-                }
+        let items: Vec<ListItem> = self.jump_candidates().iter().map(|&i| {
+            let host = &self.hosts[i];
+            match self.jump_chain.iter().position(|n| n == &host.name) {
+                Some(pos) => ListItem::new(Line::from(Span::styled(
+                    format!("{} [{}]", host.name, pos + 1),
+                    Style::default().fg(Color::Green),
+                ))),
+                None => ListItem::new(Line::from(Span::raw(&host.name))),
             }
-        } else {
-            // Criar arquivo principal se não existir
-            fs::create_dir_all(main_config.parent().unwrap())?;
-            let mut file = OpenOptions::new()
-                .create(true)
-                .write(true)
-                .open(&main_config)?;
-            writeln!(file, "{}", include_line)?;
-        }
+        }).collect();
         
-        Ok(())
+        let hosts_list = List::new(items)
+            .block(Block::default().borders(Borders::ALL).title("Hosts (o último da cadeia é o destino)"))
+            .highlight_style(Style::default().add_modifier(Modifier::BOLD))
+            .highlight_symbol(">> ");
+        f.render_stateful_widget(hosts_list, chunks[1], &mut self.jump_list_state);
+        
+        let help = Paragraph::new("Enter: Add hop | Backspace: Remove | c: Connect | s: Save as ProxyJump | Esc: Back")
+            .style(Style::default().fg(Color::Gray));
+        f.render_widget(help, chunks[2]);
     }
 
-    fn next(&mut self) {
-        let mut i = match self.list_state.selected() {
-            Some(i) => if i >= self.hosts.len() - 1 { 0 } else { i + 1 },
-            None => 0,
+    fn render_topology(&mut self, f: &mut Frame) {
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Min(0), Constraint::Length(1)])
+            .split(f.size());
+        let panes = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
+            .split(chunks[0]);
+        
+        let items: Vec<ListItem> = if self.topology_rows.is_empty() {
+            vec![ListItem::new(Line::from("Nenhum host usa ProxyJump/ProxyCommand"))]
+        } else {
+            self.topology_rows.iter().map(|row| {
+                let style = if !row.known {
+                    Style::default().fg(Color::Red)
+                } else if !row.dependents.is_empty() {
+                    Style::default().fg(Color::Cyan)
+                } else {
+                    Style::default()
+                };
+                ListItem::new(Line::from(vec![
+                    Span::styled(&row.prefix, Style::default().fg(Color::Gray)),
+                    Span::styled(&row.name, style),
+                ]))
+            }).collect()
         };
         
-        while i < self.hosts.len() && self.hosts[i].is_separator {
-            i = if i >= self.hosts.len() - 1 { 0 } else { i + 1 };
-        }
+        let tree = List::new(items)
+            .block(Block::default().borders(Borders::ALL).title("Bastion Topology"))
+            .highlight_style(Style::default().add_modifier(Modifier::BOLD))
+            .highlight_symbol(">> ");
+        f.render_stateful_widget(tree, panes[0], &mut self.topology_state);
         
-        self.list_state.select(Some(i));
-    }
-
-    fn previous(&mut self) {
-        let mut i = match self.list_state.selected() {
-            Some(i) => if i == 0 { self.hosts.len() - 1 } else { i - 1 },
-            None => 0,
+        // Impacto da queda do host selecionado
+        let impact = match self.topology_state.selected().and_then(|i| self.topology_rows.get(i)) {
+            Some(row) => {
+                let mut lines = vec![Line::from(vec![
+                    Span::styled("Se cair: ", Style::default().fg(Color::Yellow)),
+                    Span::raw(format!("{} ({} hosts afetados)", row.name, row.dependents.len())),
+                ])];
+                if !row.known {
+                    lines.push(Line::from(Span::styled("Host não definido na configuração", Style::default().fg(Color::Red))));
+                }
+                lines.push(Line::from(""));
+                for dependent in &row.dependents {
+                    lines.push(Line::from(format!("  {}", dependent)));
+                }
+                Paragraph::new(lines)
+            }
+            None => Paragraph::new("No host selected"),
         };
+        f.render_widget(impact.block(Block::default().borders(Borders::ALL).title("Impact")), panes[1]);
         
-        while i < self.hosts.len() && self.hosts[i].is_separator {
-            i = if i == 0 { self.hosts.len() - 1 } else { i - 1 };
-        }
-        
-        self.list_state.select(Some(i));
+        let help = Paragraph::new("↑/↓: Navigate | Enter: Go to host | Esc: Back")
+            .style(Style::default().fg(Color::Gray));
+        f.render_widget(help, chunks[1]);
     }
 
-    fn update_search(&mut self) {
-        self.filtered_hosts.clear();
-        
-        if self.search_query.is_empty() {
-            return;
-        }
+    fn render_tunnels(&mut self, f: &mut Frame) {
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Min(0), Constraint::Length(1)])
+            .split(f.size());
         
-        for (i, host) in self.hosts.iter().enumerate() {
-            if !host.is_separator {
-                if let Some(_) = self.matcher.fuzzy_match(&host.name, &self.search_query) {
-                    self.filtered_hosts.push(i);
+        self.tunnels.refresh();
+        let mut items: Vec<ListItem> = self.tunnels.tunnels.iter().map(|tunnel| {
+            let status = match &tunnel.status {
+                TunnelStatus::Running => {
+                    let uptime = tunnel.started_at.elapsed().map(|d| d.as_secs()).unwrap_or(0);
+                    Span::styled(format!("ativo há {}m{:02}s", uptime / 60, uptime % 60), Style::default().fg(Color::Green))
                 }
-            }
+                TunnelStatus::Exited(reason) => Span::styled(format!("encerrado: {}", reason), Style::default().fg(Color::Red)),
+            };
+            ListItem::new(Line::from(vec![
+                Span::styled(format!("{:<20} ", tunnel.host), Style::default().fg(Color::Cyan)),
+                Span::raw(format!("{} → {}:{}  ", tunnel.forward.local, tunnel.forward.remote_host, tunnel.forward.remote_port)),
+                status,
+            ]))
+        }).collect();
+        // Túneis persistentes vêm depois, com o estado da unit no systemd
+        items.extend(self.tunnels.persistent.iter().map(|tunnel| {
+            let color = if tunnel.status == "active" { Color::Green } else { Color::Red };
+            ListItem::new(Line::from(vec![
+                Span::styled(format!("{:<20} ", tunnel.host), Style::default().fg(Color::Cyan)),
+                Span::raw(format!("{} → {}:{}  ", tunnel.forward.local, tunnel.forward.remote_host, tunnel.forward.remote_port)),
+                Span::styled(format!("systemd: {}", tunnel.status), Style::default().fg(color)),
+            ]))
+        }));
+        if items.is_empty() {
+            items.push(ListItem::new(Line::from("Nenhum túnel ativo (use f na lista de hosts)")));
         }
         
-        // Ordenar por score de match
-        self.filtered_hosts.sort_by(|&a, &b| {
-            let score_a = self.matcher.fuzzy_match(&self.hosts[a].name, &self.search_query).unwrap_or(0);
-            let score_b = self.matcher.fuzzy_match(&self.hosts[b].name, &self.search_query).unwrap_or(0);
-            score_b.cmp(&score_a)
-        });
+        let list = List::new(items)
+            .block(Block::default().borders(Borders::ALL).title(format!("Tunnels ({} active)", self.tunnels.active_count())))
+            .highlight_style(Style::default().add_modifier(Modifier::BOLD))
+            .highlight_symbol(">> ");
+        f.render_stateful_widget(list, chunks[0], &mut self.tunnel_state);
+        
+        let help = Paragraph::new("↑/↓: Navigate | s: Make persistent (systemd) | x: Stop tunnel | Esc: Back")
+            .style(Style::default().fg(Color::Gray));
+        f.render_widget(help, chunks[1]);
     }
-    
-    fn next_search_result(&mut self) {
-        if !self.filtered_hosts.is_empty() {
-            let current = self.list_state.selected().unwrap_or(0);
-            if let Some(pos) = self.filtered_hosts.iter().position(|&i| i == current) {
-                let next_pos = (pos + 1) % self.filtered_hosts.len();
-                self.list_state.select(Some(self.filtered_hosts[next_pos]));
-            } else if !self.filtered_hosts.is_empty() {
-                self.list_state.select(Some(self.filtered_hosts[0]));
+
+    fn render_sync_diff(&mut self, f: &mut Frame) {
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Min(0), Constraint::Length(1)])
+            .split(f.size());
+        let panes = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([Constraint::Percentage(25), Constraint::Percentage(75)])
+            .split(chunks[0]);
+
+        let items: Vec<ListItem> = self.sync_files.iter().map(|file| {
+            let status = file.status();
+            let color = match status {
+                FileStatus::New => Color::Green,
+                FileStatus::Deleted => Color::Red,
+                FileStatus::Changed => Color::Yellow,
+                FileStatus::Unchanged => Color::DarkGray,
+            };
+            let mut style = Style::default();
+            if file.excluded {
+                style = style.fg(Color::DarkGray).add_modifier(Modifier::CROSSED_OUT);
             }
-        }
+            ListItem::new(Line::from(vec![
+                Span::raw(if file.excluded { "[ ] " } else { "[x] " }),
+                Span::styled(format!("{:<8}", status.label()), Style::default().fg(color)),
+                Span::styled(file.path.display().to_string(), style),
+            ]))
+        }).collect();
+        let destination = match &self.sync_target {
+            Some(SyncTarget::Host(host)) => format!("{}:{}", host, self.app_config.sync_remote_dir),
+            _ => "git push".to_string(),
+        };
+        let sending = self.sync_files.iter().filter(|f| !f.excluded).count();
+        let list = List::new(items)
+            .block(Block::default().borders(Borders::ALL).title(format!("{} ({}/{} files)", destination, sending, self.sync_files.len())))
+            .highlight_style(Style::default().add_modifier(Modifier::BOLD))
+            .highlight_symbol(">> ");
+        f.render_stateful_widget(list, panes[0], &mut self.sync_state);
+
+        let sides = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
+            .split(panes[1]);
+        let file = self.sync_state.selected().and_then(|i| self.sync_files.get(i));
+        let rows = file.map(|file| {
+            diff::side_by_side(file.remote.as_deref().unwrap_or_default(), file.local.as_deref().unwrap_or_default())
+        }).unwrap_or_default();
+        // Linhas com realce de sintaxe; as alteradas ficam marcadas na margem
+        let side = |pick: fn(&diff::Row) -> &Option<(usize, String)>, marker: &'static str, color: Color| -> Vec<Line> {
+            rows.iter().map(|row| match pick(row) {
+                Some((number, text)) => {
+                    let gutter = if row.kind == RowKind::Same {
+                        Span::styled(format!("{:>4}  ", number), Style::default().fg(Color::DarkGray))
+                    } else {
+                        Span::styled(format!("{:>4}{} ", number, marker), Style::default().fg(color).add_modifier(Modifier::BOLD))
+                    };
+                    let mut line = highlight::highlight_line(text);
+                    line.spans.insert(0, gutter);
+                    line
+                }
+                None => Line::from(""),
+            }).collect()
+        };
+        let left = Paragraph::new(side(|r| &r.left, "-", Color::Red))
+            .scroll((self.diff_scroll, 0))
+            .block(Block::default().borders(Borders::ALL).title("Remote (current)"));
+        let right = Paragraph::new(side(|r| &r.right, "+", Color::Green))
+            .scroll((self.diff_scroll, 0))
+            .block(Block::default().borders(Borders::ALL).title("Local (will be sent)"));
+        f.render_widget(left, sides[0]);
+        f.render_widget(right, sides[1]);
+
+        let help = Paragraph::new("↑/↓: Select file | Space: Exclude/include | PgUp/PgDn: Scroll | Enter: Sync | Esc: Cancel")
+            .style(Style::default().fg(Color::Gray));
+        f.render_widget(help, chunks[1]);
     }
-    
-    fn prev_search_result(&mut self) {
-        if !self.filtered_hosts.is_empty() {
-            let current = self.list_state.selected().unwrap_or(0);
-            if let Some(pos) = self.filtered_hosts.iter().position(|&i| i == current) {
-                let prev_pos = if pos == 0 { self.filtered_hosts.len() - 1 } else { pos - 1 };
-                self.list_state.select(Some(self.filtered_hosts[prev_pos]));
-            } else if !self.filtered_hosts.is_empty() {
-                self.list_state.select(Some(self.filtered_hosts[0]));
-            }
+
+    fn render_conflicts(&mut self, f: &mut Frame) {
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Min(0), Constraint::Length(1)])
+            .split(f.size());
+        let panes = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([Constraint::Percentage(30), Constraint::Percentage(70)])
+            .split(chunks[0]);
+
+        let workdir = self.app_config.get_workdir();
+        let items: Vec<ListItem> = self.merges.iter().flat_map(|merge| {
+            let file = merge.path.strip_prefix(&workdir).unwrap_or(&merge.path).display().to_string();
+            merge.conflicts.iter().map(move |conflict| ListItem::new(Line::from(vec![
+                Span::styled(format!("{:<14} ", conflict.resolution.label()), Style::default().fg(Color::Yellow)),
+                Span::raw(conflict.host.clone()),
+                Span::styled(format!("  {}", file), Style::default().fg(Color::DarkGray)),
+            ])))
+        }).collect();
+        let total = items.len();
+        let list = List::new(items)
+            .block(Block::default().borders(Borders::ALL).title(format!("Conflicts ({} hosts)", total)))
+            .highlight_style(Style::default().add_modifier(Modifier::BOLD))
+            .highlight_symbol(">> ");
+        f.render_stateful_widget(list, panes[0], &mut self.conflict_state);
+
+        let right = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Percentage(60), Constraint::Percentage(40)])
+            .split(panes[1]);
+        let sides = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
+            .split(right[0]);
+        let conflict = self.conflict_state.selected()
+            .and_then(|i| self.conflict_at(i))
+            .map(|(file, i)| &self.merges[file].conflicts[i]);
+        let text = |block: Option<&crate::conflict::HostBlock>| block.map(|b| b.text()).unwrap_or_default();
+        let rows = conflict
+            .map(|c| diff::side_by_side(&text(c.local.as_ref()), &text(c.remote.as_ref())))
+            .unwrap_or_default();
+        // Linhas que diferem entre os lados levam um `▌` amarelo na margem
+        let side = |pick: fn(&diff::Row) -> &Option<(usize, String)>| -> Vec<Line> {
+            rows.iter().map(|row| match pick(row) {
+                Some((_, text)) => {
+                    let marker = if row.kind == RowKind::Same { " " } else { "▌" };
+                    let mut line = highlight::highlight_line(text);
+                    line.spans.insert(0, Span::styled(marker, Style::default().fg(Color::Yellow)));
+                    line
+                }
+                None => Line::from(""),
+            }).collect()
+        };
+        let removed = |side: &str| Line::from(Span::styled(format!("(removed {})", side), Style::default().fg(Color::Red)));
+        let mut local = side(|r| &r.left);
+        let mut remote = side(|r| &r.right);
+        if conflict.is_some_and(|c| c.local.is_none()) {
+            local = vec![removed("locally")];
         }
+        if conflict.is_some_and(|c| c.remote.is_none()) {
+            remote = vec![removed("on remote")];
+        }
+        f.render_widget(Paragraph::new(local).block(Block::default().borders(Borders::ALL).title("Local")), sides[0]);
+        let remote_title = if self.import_merge { "Imported" } else { "Remote" };
+        f.render_widget(Paragraph::new(remote).block(Block::default().borders(Borders::ALL).title(remote_title)), sides[1]);
+
+        let result = match conflict.map(|c| c.resolved()) {
+            Some(Some(block)) => block.lines.iter().map(|l| highlight::highlight_line(l)).collect(),
+            Some(None) => vec![Line::from(Span::styled("(host will be removed)", Style::default().fg(Color::Red)))],
+            None => Vec::new(),
+        };
+        f.render_widget(Paragraph::new(result).block(Block::default().borders(Borders::ALL).title("Result")), right[1]);
+
+        let help = if self.import_merge {
+            "l: Keep local | r: Take imported | m: Merge fields | Enter: Apply | Esc: Cancel import"
+        } else {
+            "↑/↓: Select host | l: Keep local | r: Take remote | m: Merge fields | Enter: Apply all | Esc: Abort merge"
+        };
+        let help = Paragraph::new(help)
+            .style(Style::default().fg(Color::Gray));
+        f.render_widget(help, chunks[1]);
     }
-    
-    fn render_search(&mut self, f: &mut Frame) {
+
+    fn render_includes(&mut self, f: &mut Frame) {
         let chunks = Layout::default()
             .direction(Direction::Vertical)
-            .constraints([Constraint::Length(3), Constraint::Min(0)])
+            .constraints([Constraint::Min(0), Constraint::Length(1)])
             .split(f.size());
         
-        // Barra de busca
-        let search_text = format!("Search: {}", self.search_query);
-        let search_bar = Paragraph::new(search_text)
-            .block(Block::default().borders(Borders::ALL).title("Fuzzy Search"))
-            .style(Style::default().fg(Color::Yellow));
-        f.render_widget(search_bar, chunks[0]);
-        
-        // Lista filtrada
-        let list_chunks = Layout::default()
-            .direction(Direction::Horizontal)
-            .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
-            .split(chunks[1]);
-        
-        let items: Vec<ListItem> = if self.search_query.is_empty() {
-            vec![ListItem::new(Line::from("Type to search..."))]
-        } else if self.filtered_hosts.is_empty() {
-            vec![ListItem::new(Line::from("No matches found"))]
-        } else {
-            self.filtered_hosts.iter().map(|&i| {
-                let host = &self.hosts[i];
-                ListItem::new(Line::from(Span::raw(&host.name)))
-            }).collect()
-        };
+        let workdir = self.app_config.get_workdir();
+        let items: Vec<ListItem> = self.files.iter().enumerate().map(|(i, file)| {
+            let path = file.path.strip_prefix(&workdir).unwrap_or(&file.path);
+            let mut spans = vec![
+                Span::styled(format!("{:>3}. ", i + 1), Style::default().fg(Color::Gray)),
+                Span::raw("  ".repeat(file.depth)),
+                Span::raw(path.display().to_string()),
+            ];
+            if self.merge_source == Some(i) {
+                spans.push(Span::styled(" [merge]", Style::default().fg(Color::Magenta)));
+            }
+            if file.missing {
+                spans.push(Span::styled(" (não encontrado)", Style::default().fg(Color::Red)));
+            } else {
+                spans.push(Span::styled(format!(" ({} hosts)", file.host_count), Style::default().fg(Color::Yellow)));
+            }
+            ListItem::new(Line::from(spans))
+        }).collect();
         
-        let hosts_list = List::new(items)
-            .block(Block::default().borders(Borders::ALL).title(format!("Results ({})", self.filtered_hosts.len())))
+        let total: usize = self.files.iter().map(|f| f.host_count).sum();
+        let files_list = List::new(items)
+            .block(Block::default().borders(Borders::ALL).title(format!("Config Files ({} files, {} hosts)", self.files.len(), total)))
             .highlight_style(Style::default().add_modifier(Modifier::BOLD))
             .highlight_symbol(">> ");
+        f.render_stateful_widget(files_list, chunks[0], &mut self.include_state);
         
-        f.render_stateful_widget(hosts_list, list_chunks[0], &mut self.list_state);
-        
-        // Detalhes do host selecionado
-        let selected_host = self.list_state.selected()
-            .and_then(|i| self.hosts.get(i))
-            .filter(|host| !host.is_separator);
-        
-        let details = if let Some(host) = selected_host {
-            let mut lines = vec![
-                Line::from(vec![
-                    Span::styled("Host: ", Style::default().fg(Color::Yellow)),
-                    Span::raw(&host.name),
-                ]),
-            ];
-            
-            if let Some(hostname) = &host.hostname {
-                lines.push(Line::from(vec![
-                    Span::styled("Hostname: ", Style::default().fg(Color::Yellow)),
-                    Span::raw(hostname),
-                ]));
-            }
-            
-            if let Some(user) = &host.user {
-                lines.push(Line::from(vec![
-                    Span::styled("User: ", Style::default().fg(Color::Yellow)),
-                    Span::raw(user),
-                ]));
-            }
-            
-            Paragraph::new(lines)
+        let help_text = if self.merge_source.is_some() {
+            "Select the target file and press m to merge | Esc: Cancel merge"
         } else {
-            Paragraph::new("No host selected")
+            "↑/↓: Navigate | Enter: Go to hosts | n: New folder | r: Rename | m: Merge | Esc: Back"
         };
-        
-        let details_block = details.block(Block::default().borders(Borders::ALL).title("Host Details"));
-        f.render_widget(details_block, list_chunks[1]);
-        
-        // Instruções
-        let help_text = "↑/↓: Navigate | Enter: Select | Esc: Cancel";
         let help = Paragraph::new(help_text)
             .style(Style::default().fg(Color::Gray));
-        
-        let help_area = Layout::default()
-            .direction(Direction::Vertical)
-            .constraints([Constraint::Min(0), Constraint::Length(1)])
-            .split(f.size())[1];
-        
-        f.render_widget(help, help_area);
+        f.render_widget(help, chunks[1]);
     }
-    
-    fn load_host_for_editing(&mut self, host_index: usize) {
-        if let Some(host) = self.hosts.get(host_index) {
-            self.editing_host_index = Some(host_index);
-            
-            // Determinar pasta baseada no source_dir ou usar pasta padrão
-            let folder = host.source_dir.clone().unwrap_or_else(|| "main".to_string());
-            
-            self.form = HostForm {
-                folder,
-                host: host.name.clone(),
-                hostname: host.hostname.clone().unwrap_or_default(),
-                user: host.user.clone().unwrap_or_default(),
-                port: host.port.map(|p| p.to_string()).unwrap_or_default(),
-                identity_file: host.identity_file.clone().unwrap_or_default(),
-                local_forward: host.other_options.get("localforward").cloned().unwrap_or_default(),
-                current_field: 0,
-            };
+
+    // Arquivos `<pasta>/config` são renomeados pela pasta; os demais pelo próprio nome
+    fn include_rename_target(&self, index: usize) -> Option<std::path::PathBuf> {
+        let file = self.files.get(index)?;
+        let workdir = self.app_config.get_workdir();
+        let parent = file.path.parent()?;
+        if file.path.file_name().is_some_and(|n| n == "config") && parent != workdir {
+            Some(parent.to_path_buf())
+        } else {
+            Some(file.path.clone())
         }
     }
     
-    fn update_host(&mut self) -> Result<(), Box<dyn std::error::Error>> {
-        if let Some(host_index) = self.editing_host_index {
-            // Para edição, precisamos remover o host antigo e adicionar o novo
-            // Por simplicidade, vamos apenas atualizar os dados na memória
-            // e depois reescrever o arquivo
-            self.remove_host_from_file(host_index)?;
-            self.save_host()?;
-        }
-        Ok(())
+    fn include_display_name(&self, index: usize) -> String {
+        self.include_rename_target(index)
+            .and_then(|p| p.file_name().map(|n| n.to_string_lossy().to_string()))
+            .unwrap_or_default()
     }
     
-    fn remove_host_from_file(&mut self, host_index: usize) -> Result<(), Box<dyn std::error::Error>> {
+    fn rename_include(&mut self, index: usize, new_name: &str) -> Result<String, Box<dyn std::error::Error>> {
         use std::fs;
         
-        if let Some(host) = self.hosts.get(host_index) {
-            let source_dir = host.source_dir.clone().unwrap_or_else(|| "ssh".to_string());
-            let config_path = if source_dir == "ssh" {
-                self.app_config.get_main_config_path()
-            } else {
-                self.app_config.get_workdir().join(&source_dir).join("config")
-            };
-            
-            if config_path.exists() {
-                let content = fs::read_to_string(&config_path)?;
-                let mut new_content = String::new();
-                let mut lines = content.lines();
-                let mut _skip_until_next_host = false;
-                
-                while let Some(line) = lines.next() {
-                    let trimmed = line.trim();
-                    
-                    if trimmed.starts_with("Host ") {
-                        if trimmed == format!("Host {}", host.name) {
-                            // Pular linhas até o próximo Host ou fim do arquivo
-                            while let Some(next_line) = lines.next() {
-                                let next_trimmed = next_line.trim();
-                                if next_trimmed.starts_with("Host ") {
-                                    new_content.push_str(next_line);
-                                    new_content.push('\n');
-                                    break;
-                                }
-                            }
-                            // Continuar processamento
-                        } else {
-                            new_content.push_str(line);
-                            new_content.push('\n');
-                        }
-                    } else {
-                        new_content.push_str(line);
-                        new_content.push('\n');
-                    }
-                }
-                
-                fs::write(&config_path, new_content)?;
-            }
+        if self.files.get(index).is_none_or(|f| f.depth == 0 || f.missing) {
+            return Err("apenas arquivos incluídos existentes podem ser renomeados".into());
+        }
+        if new_name.is_empty() || new_name.contains('/') {
+            return Err("nome inválido".into());
         }
         
-        Ok(())
-    }
-    
-    fn test_connectivity(&mut self, host: &SshHost) {
-        if let (Some(hostname), Some(port)) = (&host.hostname, host.port) {
-            self.previous_state = self.state.clone();
-            
-            let success = ConnectivityTest::test_tcp_connection(hostname, port);
-            
-            self.popup_message = if success {
-                format!("Host {} respondeu na porta {}", hostname, port)
-            } else {
-                format!("Host {} não respondeu na porta {}", hostname, port)
-            };
-            
-            self.state = AppState::Popup;
-        } else {
-            self.previous_state = self.state.clone();
-            self.popup_message = "Host não possui hostname ou porta configurados".to_string();
-            self.state = AppState::Popup;
+        let old = self.include_rename_target(index).ok_or("arquivo inválido")?;
+        let new = old.with_file_name(new_name);
+        if new.exists() {
+            return Err(format!("{} já existe", new.display()).into());
+        }
+        
+        fs::rename(&old, &new)?;
+        
+        // Includes que apontavam para o caminho antigo agora estão no novo lugar
+        let config_files: Vec<_> = self.files.iter()
+            .filter(|f| !f.missing)
+            .map(|f| match f.path.strip_prefix(&old) {
+                Ok(rest) => new.join(rest),
+                Err(_) => f.path.clone(),
+            })
+            .collect();
+        let mut paths = SshConfig::rewrite_includes(&config_files, &old, Some(&new))?;
+        // Os arquivos movidos saem do caminho antigo e entram no novo
+        for file in self.files.iter().filter(|f| !f.missing) {
+            if let Ok(rest) = file.path.strip_prefix(&old) {
+                paths.push(file.path.clone());
+                paths.push(new.join(rest));
+            }
         }
+        self.reload_hosts()?;
+        self.git_commit(format!("lazysshrs: rename {} to {}", old.display(), new_name), paths);
+        
+        Ok(format!("{} renomeado para {}", old.display(), new.display()))
     }
     
-    fn render_popup(&mut self, f: &mut Frame) {
-        use ratatui::widgets::{Clear, Paragraph};
-        use ratatui::layout::Alignment;
+    fn merge_includes(&mut self, source: usize, target: usize) -> Result<String, Box<dyn std::error::Error>> {
+        use std::fs;
         
-        let area = f.size();
+        let source_file = self.files.get(source).ok_or("arquivo inválido")?.clone();
+        let target_file = self.files.get(target).ok_or("arquivo inválido")?.clone();
+        if source == target || source_file.depth == 0 || source_file.missing || target_file.missing {
+            return Err("selecione dois arquivos diferentes; o config principal não pode ser a origem".into());
+        }
         
-        // Calcular área do popup (centralizado)
-        let popup_width = 60.min(area.width - 4);
-        let popup_height = 5;
-        let x = (area.width - popup_width) / 2;
-        let y = (area.height - popup_height) / 2;
+        let source_content = SshConfig::read_text(&source_file.path)?;
+        let mut target_content = SshConfig::read_text(&target_file.path)?;
+        if !target_content.is_empty() && !target_content.ends_with('\n') {
+            target_content.push('\n');
+        }
+        if !target_content.trim().is_empty() {
+            target_content.push('\n');
+        }
+        target_content.push_str(&source_content);
+        backup::write(&target_file.path, target_content)?;
         
-        let popup_area = ratatui::layout::Rect {
-            x,
-            y,
-            width: popup_width,
-            height: popup_height,
-        };
+        // Remover o Include do arquivo de origem e o próprio arquivo
+        let config_files: Vec<_> = self.files.iter()
+            .filter(|f| !f.missing && f.path != source_file.path)
+            .map(|f| f.path.clone())
+            .collect();
+        let mut paths = SshConfig::rewrite_includes(&config_files, &source_file.path, None)?;
+        paths.extend([source_file.path.clone(), target_file.path.clone()]);
+        fs::remove_file(&source_file.path)?;
+        if let Some(parent) = source_file.path.parent() {
+            if parent != self.app_config.get_workdir() && fs::read_dir(parent)?.next().is_none() {
+                fs::remove_dir(parent)?;
+            }
+        }
+        self.reload_hosts()?;
+        self.git_commit(
+            format!("lazysshrs: merge {} into {}", source_file.path.display(), target_file.path.display()),
+            paths,
+        );
         
-        // Limpar área do popup
-        f.render_widget(Clear, popup_area);
+        Ok(format!("{} mesclado em {}", source_file.path.display(), target_file.path.display()))
+    }
+
+    fn create_include_folder(&mut self, name: &str) -> Result<String, Box<dyn std::error::Error>> {
+        use std::fs;
         
-        // Renderizar popup
-        let popup = Paragraph::new(self.popup_message.clone())
-            .block(Block::default().borders(Borders::ALL).title("Teste de Conectividade"))
-            .alignment(Alignment::Center)
-            .wrap(ratatui::widgets::Wrap { trim: true });
+        if name.is_empty() || name.contains('/') {
+            return Err("nome inválido".into());
+        }
         
-        f.render_widget(popup, popup_area);
+        let config_path = self.app_config.get_workdir().join(name).join("config");
+        if config_path.exists() {
+            return Err(format!("{} já existe", config_path.display()).into());
+        }
+        if let Some(parent) = config_path.parent() {
+            fs::create_dir_all(parent)?;
+        }
         
-        // Renderizar instrução
-        let help_area = ratatui::layout::Rect {
-            x,
-            y: y + popup_height,
-            width: popup_width,
-            height: 1,
-        };
+        let mut content = self.app_config.folder_template.replace("{folder}", name);
+        if !content.is_empty() && !content.ends_with('\n') {
+            content.push('\n');
+        }
+        backup::write(&config_path, content)?;
         
-        let help = Paragraph::new("Enter/Esc: Fechar")
-            .alignment(Alignment::Center)
-            .style(Style::default().fg(Color::Gray));
+        let mut paths = vec![config_path.clone()];
+        if self.add_include_to_main_config(&config_path)? {
+            paths.push(self.app_config.get_main_config_path());
+        }
+        self.reload_hosts()?;
+        self.git_commit(format!("lazysshrs: add folder {}", name), paths);
+        if let Some(index) = self.files.iter().position(|f| f.path == config_path) {
+            self.include_state.select(Some(index));
+        }
         
-        f.render_widget(help, help_area);
+        Ok(format!("Pasta {} criada em {}", name, config_path.display()))
     }
-    
-    fn connect_ssh(&mut self, host: &SshHost) -> Result<(), Box<dyn std::error::Error>> {
-        use crossterm::{
-            execute,
-            terminal::{disable_raw_mode, enable_raw_mode, LeaveAlternateScreen, EnterAlternateScreen},
+
+    fn render_smart_groups(&mut self, f: &mut Frame) {
+        use ratatui::widgets::Clear;
+
+        let area = f.size();
+        let width = 60.min(area.width.saturating_sub(4));
+        let height = (self.app_config.saved_searches.len() as u16 + 2).min(area.height.saturating_sub(2));
+        let picker_area = Rect {
+            x: (area.width - width) / 2,
+            y: (area.height - height) / 2,
+            width,
+            height,
         };
-        use std::io;
-        
-        // Sair completamente do modo TUI
-        disable_raw_mode()?;
-        execute!(io::stdout(), LeaveAlternateScreen)?;
+
+        // Contagem de hosts de cada grupo com a configuração atual
+        if self.group_counts.is_none() {
+            let haystack = search::haystack(&self.hosts, &self.metadata, self.app_config.search_frecency_weight);
+            self.group_counts = Some(self.app_config.saved_searches.iter()
+                .map(|g| search::rank(&haystack, &g.query, self.app_config.matcher).len())
+                .collect());
+        }
+        let counts = self.group_counts.as_deref().unwrap_or_default();
+        let items: Vec<ListItem> = self.app_config.saved_searches.iter().enumerate()
+            .map(|(i, g)| {
+                let count = counts.get(i).copied().unwrap_or_default();
+                ListItem::new(Line::from(vec![
+                    Span::raw(format!("{} ({}) ", g.name, count)),
+                    Span::styled(g.query.clone(), Style::default().fg(Color::Gray)),
+                ]))
+            })
+            .collect();
+        let groups = List::new(items)
+            .block(Block::default().borders(Borders::ALL).title("Smart Groups (Enter: Apply | d: Delete | Esc: Cancel)"))
+            .highlight_style(Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD))
+            .highlight_symbol(">> ");
+
+        f.render_widget(Clear, picker_area);
+        f.render_stateful_widget(groups, picker_area, &mut self.group_state);
+    }
+
+    fn render_identity_picker(&mut self, f: &mut Frame) {
+        use ratatui::widgets::Clear;
+
+        let area = f.size();
+        let width = 80.min(area.width.saturating_sub(4));
+        let height = (self.identity_choices.len() as u16 + 3).min(area.height.saturating_sub(2));
+        let picker_area = Rect {
+            x: (area.width - width) / 2,
+            y: (area.height - height) / 2,
+            width,
+            height,
+        };
+
+        let mut items = vec![ListItem::new(Line::from(Span::styled("none (agent/default)", Style::default().fg(Color::Gray))))];
+        items.extend(self.identity_choices.iter().map(|pair| {
+            let path = if self.privacy_mode { privacy::mask_value("identityfile", &pair.path) } else { pair.path.clone() };
+            ListItem::new(Line::from(vec![
+                Span::raw(format!("{:<28} ", path)),
+                Span::styled(format!("{:<20} ", pair.key_type), Style::default().fg(Color::Cyan)),
+                Span::styled(pair.comment.clone(), Style::default().fg(Color::Gray)),
+            ]))
+        }));
+        let picker = List::new(items)
+            .block(Block::default().borders(Borders::ALL).title("Identity File (Enter: Use | Esc: Cancel)"))
+            .highlight_style(Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD))
+            .highlight_symbol(">> ");
+
+        f.render_widget(Clear, picker_area);
+        f.render_stateful_widget(picker, picker_area, &mut self.identity_state);
+    }
+
+    fn render_template_picker(&mut self, f: &mut Frame) {
+        use ratatui::widgets::Clear;
         
-        // Executar conexão SSH
-        let result = ConnectivityTest::connect_ssh(&host.name);
+        let area = f.size();
+        let width = 50.min(area.width.saturating_sub(4));
+        let height = (self.app_config.templates.len() as u16 + 2).min(area.height.saturating_sub(2));
+        let picker_area = ratatui::layout::Rect {
+            x: (area.width - width) / 2,
+            y: (area.height - height) / 2,
+            width,
+            height,
+        };
         
-        // Restaurar modo TUI
-        execute!(io::stdout(), EnterAlternateScreen)?;
-        enable_raw_mode()?;
+        let items: Vec<ListItem> = self.app_config.templates.iter()
+            .map(|t| ListItem::new(Line::from(t.name.clone())))
+            .collect();
+        let templates = List::new(items)
+            .block(Block::default().borders(Borders::ALL).title("Templates (Enter: Apply | Esc: Cancel)"))
+            .highlight_style(Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD))
+            .highlight_symbol(">> ");
         
-        result
+        f.render_widget(Clear, picker_area);
+        f.render_stateful_widget(templates, picker_area, &mut self.template_state);
     }
-}
\ No newline at end of file
+}
+
+// Monta o painel de detalhes na ordem de `order`: cada nome é uma diretiva
+// (`hostname`, `proxyjump`...) ou um trecho (`status`, `tags`, `notes`...), e
+// `options` traz as diretivas que não foram citadas. Uma linha em branco
+// separa as diretivas dos demais trechos
+fn arrange_details<'a>(order: &[String], mut sections: Vec<(String, Vec<Line<'a>>)>) -> Vec<Line<'a>> {
+    const EXTRAS: [&str; 9] = ["description", "hostkeys", "status", "check", "tags", "mac", "connections", "notes", "source"];
+    let is_extra = |key: &str| EXTRAS.contains(&key);
+    let named = |key: &str| order.iter().any(|o| o.eq_ignore_ascii_case(key));
+
+    let mut picked = Vec::new();
+    for name in order {
+        if name.eq_ignore_ascii_case("options") {
+            // Remove as diretivas restantes mantendo a ordem do bloco
+            let (options, rest): (Vec<_>, Vec<_>) = sections.into_iter().partition(|(key, _)| !is_extra(key) && !named(key));
+            picked.extend(options);
+            sections = rest;
+        } else {
+            // Diretivas repetidas (LocalForward) vêm todas juntas
+            let (matching, rest): (Vec<_>, Vec<_>) = sections.into_iter().partition(|(key, _)| key.eq_ignore_ascii_case(name));
+            picked.extend(matching);
+            sections = rest;
+        }
+    }
+
+    let mut lines = Vec::new();
+    let mut previous_extra = None;
+    for (key, section) in picked {
+        let extra = is_extra(&key);
+        if previous_extra == Some(false) && extra && !matches!(key.as_str(), "notes" | "description") {
+            lines.push(Line::from(""));
+        }
+        previous_extra = Some(extra);
+        lines.extend(section);
+    }
+    lines
+}
+
+// Caminhos digitados nos prompts de exportação aceitam `~/`
+fn expand_home(path: &str) -> Result<std::path::PathBuf, Box<dyn std::error::Error>> {
+    Ok(match path.strip_prefix("~/") {
+        Some(rest) => home::home_dir().ok_or("diretório home não encontrado")?.join(rest),
+        None => std::path::PathBuf::from(path),
+    })
+}
+
+fn severity_color(severity: Severity) -> Color {
+    match severity {
+        Severity::High => Color::Red,
+        Severity::Medium => Color::Yellow,
+        Severity::Low => Color::Blue,
+    }
+}
+
+// Caixa de confirmação centralizada sobre a tela atual
+fn render_dialog(f: &mut Frame, title: &str, lines: Vec<Line>) {
+    use ratatui::widgets::Clear;
+
+    let area = f.size();
+    let width = 70.min(area.width.saturating_sub(4));
+    let height = (lines.len() as u16 + 2).min(area.height);
+    let dialog_area = Rect {
+        x: (area.width - width) / 2,
+        y: area.height.saturating_sub(height) / 2,
+        width,
+        height,
+    };
+    f.render_widget(Clear, dialog_area);
+    let dialog = Paragraph::new(lines)
+        .block(Block::default().borders(Borders::ALL).title(title.to_string()))
+        .style(Style::default().fg(Color::Red));
+    f.render_widget(dialog, dialog_area);
+}
+
+// Índice da conexão rápida (1–9) à esquerda do host; sem índice, o espaço equivalente
+fn quick_label(n: Option<usize>) -> Span<'static> {
+    match n {
+        Some(n) => Span::styled(format!("{} ", n + 1), Style::default().fg(Color::DarkGray)),
+        None => Span::raw("  "),
+    }
+}
+
+// Texto com os caracteres nas posições `hits` (em ordem) destacados, como no skim/fzf
+fn highlight_matches(text: &str, hits: &[usize], style: Style) -> Vec<Span<'static>> {
+    let highlight = style.fg(Color::Yellow).add_modifier(Modifier::BOLD);
+    let mut spans = Vec::new();
+    let mut run = String::new();
+    let mut run_hit = false;
+    for (n, c) in text.chars().enumerate() {
+        let hit = hits.binary_search(&n).is_ok();
+        if hit != run_hit && !run.is_empty() {
+            spans.push(Span::styled(std::mem::take(&mut run), if run_hit { highlight } else { style }));
+        }
+        run_hit = hit;
+        run.push(c);
+    }
+    if !run.is_empty() {
+        spans.push(Span::styled(run, if run_hit { highlight } else { style }));
+    }
+    spans
+}
+
+// Primeira linha visível para manter a seleção dentro de uma janela de `height` linhas
+fn scroll_offset(selected: Option<usize>, offset: usize, height: usize, len: usize) -> usize {
+    let max_offset = len.saturating_sub(height);
+    let Some(selected) = selected else {
+        return offset.min(max_offset);
+    };
+    if selected < offset {
+        selected
+    } else if height > 0 && selected >= offset + height {
+        selected + 1 - height
+    } else {
+        offset.min(max_offset)
+    }
+}
+
+// Barra de rolagem na borda direita, só quando a lista não cabe na área
+fn render_scrollbar(f: &mut Frame, area: Rect, len: usize, height: usize, offset: usize) {
+    if len <= height {
+        return;
+    }
+    let mut state = ScrollbarState::new(len.saturating_sub(height)).position(offset);
+    let scrollbar = Scrollbar::new(ScrollbarOrientation::VerticalRight)
+        .begin_symbol(None)
+        .end_symbol(None);
+    f.render_stateful_widget(scrollbar, area.inner(&Margin { vertical: 1, horizontal: 0 }), &mut state);
+}
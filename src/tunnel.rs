@@ -0,0 +1,148 @@
+use std::io::Read;
+use std::net::TcpStream;
+use std::process::{Child, Command, Stdio};
+use std::time::SystemTime;
+
+use crate::forward::ForwardSpec;
+use crate::systemd::{self, PersistentTunnel};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum TunnelStatus {
+    Running,
+    Exited(String),
+}
+
+pub struct Tunnel {
+    pub host: String,
+    pub forward: ForwardSpec,
+    pub started_at: SystemTime,
+    pub status: TunnelStatus,
+    child: Option<Child>,
+}
+
+#[derive(Default)]
+pub struct TunnelManager {
+    pub tunnels: Vec<Tunnel>,
+    // Units do systemd, consultadas só ao abrir a tela de túneis
+    pub persistent: Vec<PersistentTunnel>,
+}
+
+impl TunnelManager {
+    pub fn start(&mut self, host: &str, forward: &ForwardSpec) -> Result<usize, Box<dyn std::error::Error>> {
+        // BatchMode evita que o ssh peça senha no terminal da TUI
+        let child = Command::new("ssh")
+            .args(["-N", "-o", "BatchMode=yes", "-o", "ExitOnForwardFailure=yes", "-L"])
+            .arg(forward.to_l_arg())
+            .arg(host)
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::piped())
+            .spawn()?;
+
+        self.tunnels.push(Tunnel {
+            host: host.to_string(),
+            forward: forward.clone(),
+            started_at: SystemTime::now(),
+            status: TunnelStatus::Running,
+            child: Some(child),
+        });
+        Ok(self.tunnels.len() - 1)
+    }
+
+    // Reaproveita um túnel ativo com a mesma porta local ou abre um novo
+    pub fn ensure(&mut self, host: &str, forward: &ForwardSpec) -> Result<usize, Box<dyn std::error::Error>> {
+        self.refresh();
+        let existing = self.tunnels.iter().position(|t| {
+            t.status == TunnelStatus::Running && t.host == host && t.forward.local_port() == forward.local_port()
+        });
+        match existing {
+            Some(index) => Ok(index),
+            None => self.start(host, forward),
+        }
+    }
+
+    // Se a porta local já aceita conexões; `None` enquanto o ssh ainda sobe,
+    // `Some(false)` se ele terminou
+    pub fn readiness(&mut self, index: usize) -> Option<bool> {
+        let Some(port) = self.tunnels.get(index).and_then(|t| t.forward.local_port()) else {
+            return Some(false);
+        };
+        if TcpStream::connect(("127.0.0.1", port)).is_ok() {
+            return Some(true);
+        }
+        self.refresh();
+        (self.tunnels[index].status != TunnelStatus::Running).then_some(false)
+    }
+
+    // Atualiza o status dos túneis cujo processo ssh terminou
+    pub fn refresh(&mut self) {
+        for tunnel in &mut self.tunnels {
+            let Some(child) = tunnel.child.as_mut() else {
+                continue;
+            };
+            if let Ok(Some(status)) = child.try_wait() {
+                let mut stderr = String::new();
+                if let Some(mut pipe) = child.stderr.take() {
+                    let _ = pipe.read_to_string(&mut stderr);
+                }
+                let reason = stderr.lines().last().map(|l| l.trim().to_string()).filter(|l| !l.is_empty());
+                tunnel.status = TunnelStatus::Exited(reason.unwrap_or_else(|| format!("código {:?}", status.code())));
+                tunnel.child = None;
+            }
+        }
+    }
+
+    pub fn stop(&mut self, index: usize) {
+        if let Some(tunnel) = self.tunnels.get_mut(index) {
+            if let Some(mut child) = tunnel.child.take() {
+                let _ = child.kill();
+                let _ = child.wait();
+            }
+            tunnel.status = TunnelStatus::Exited("encerrado".to_string());
+        }
+    }
+
+    pub fn remove(&mut self, index: usize) {
+        if index < self.tunnels.len() {
+            self.stop(index);
+            self.tunnels.remove(index);
+        }
+    }
+
+    pub fn refresh_persistent(&mut self) {
+        self.persistent = systemd::list();
+    }
+
+    // Passa o túnel para uma unit do systemd; o processo local é encerrado para liberar a porta
+    pub fn make_persistent(&mut self, index: usize) -> Result<String, Box<dyn std::error::Error>> {
+        let tunnel = self.tunnels.get(index).ok_or("túnel inexistente")?;
+        let (host, forward) = (tunnel.host.clone(), tunnel.forward.clone());
+        self.remove(index);
+        let unit = systemd::install(&host, &forward)?;
+        self.refresh_persistent();
+        Ok(unit)
+    }
+
+    pub fn remove_persistent(&mut self, index: usize) -> Result<(), Box<dyn std::error::Error>> {
+        let tunnel = self.persistent.get(index).ok_or("unit inexistente")?;
+        systemd::remove(&tunnel.unit)?;
+        self.refresh_persistent();
+        Ok(())
+    }
+
+    pub fn stop_all(&mut self) {
+        for index in 0..self.tunnels.len() {
+            self.stop(index);
+        }
+    }
+
+    pub fn active_count(&self) -> usize {
+        self.tunnels.iter().filter(|t| t.status == TunnelStatus::Running).count()
+    }
+}
+
+impl Drop for TunnelManager {
+    fn drop(&mut self) {
+        self.stop_all();
+    }
+}